@@ -0,0 +1,489 @@
+//! Headless front end to the same processing engine as the app, for
+//! scripting exports without a display. Run with `--help` for usage.
+
+use app_lib::engine::binaries;
+use app_lib::engine::concat;
+use app_lib::engine::job::{
+    self, CancelMode, JobOutcome, JobPlan, JobStatus, PreparedJob, ProcessVideoParams, ProgressPayload, TrimMode,
+};
+use app_lib::engine::manifest;
+use app_lib::engine::naming::{self, CollisionPolicy};
+use app_lib::engine::paths;
+use app_lib::engine::presets::{self, Preset, PRESETS_FILE_NAME};
+use app_lib::engine::probe;
+use app_lib::engine::runner::{self, ProgressReader};
+use app_lib::engine::settings::{self, Settings, SETTINGS_FILE_NAME};
+use app_lib::engine::subtitles::SubtitleTrack;
+use serde::de::DeserializeOwned;
+use serde_json::Map;
+use std::collections::HashSet;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tokio::sync::Notify;
+use uuid::Uuid;
+
+const USAGE: &str = "\
+Usage: ffmpeg-gui-cli <command> [options]
+
+Commands:
+  probe <input>                      Print stream and format details as JSON
+  trim <input>                       Export part of a file
+  burn-subs <input> <subtitles>      Export with subtitles burned into the video
+  mux-subs <input> <subtitles>...    Export with subtitles added as selectable tracks;
+                                     the first is shown by default
+  concat <input> <input>...          Join files in order
+  batch <manifest>                   Run the jobs in a JSON, YAML or CSV manifest
+
+Options:
+  -o, --output <file>                Output file; may contain name tokens such as {name},
+                                     with {{ and }} for literal braces.
+                                     Defaults to the filename template from the settings
+  --start <time>                     Trim start, in seconds or [HH:]MM:SS[.ms]
+  --end <time>                       Trim end
+  --mode <accurate|fast|smart>       How trims are cut (default: accurate)
+  --preset <name>                    Use a built-in or saved preset
+  --sub-lang <codes>                 Comma-separated ISO 639-2 languages for mux-subs,
+                                     in file order, e.g. eng,fra
+  --on-collision <overwrite|increment|skip|fail>
+                                     What to do when the output exists (default: overwrite)
+  -h, --help                         Show this help
+
+Settings and presets are read from the app's config directory.";
+
+/// Exit code for a run stopped with Ctrl+C, as shells report SIGINT.
+const INTERRUPTED_EXIT_CODE: u8 = 130;
+
+#[derive(Debug, Default)]
+struct Options {
+    positional: Vec<String>,
+    output: Option<String>,
+    start_time: Option<f64>,
+    end_time: Option<f64>,
+    trim_mode: TrimMode,
+    preset: Option<String>,
+    subtitle_languages: Vec<String>,
+    collision_policy: CollisionPolicy,
+}
+
+/// Loads settings and presets once, then runs jobs one after another.
+struct Cli {
+    settings: Settings,
+    user_presets: Vec<Preset>,
+    /// Outputs claimed by earlier jobs of this run.
+    claimed: HashSet<PathBuf>,
+    interrupted: Arc<AtomicBool>,
+    interrupt: Arc<Notify>,
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let mut args = std::env::args().skip(1);
+    let Some(command) = args.next() else {
+        eprintln!("{}", USAGE);
+        return ExitCode::FAILURE;
+    };
+
+    if command == "-h" || command == "--help" {
+        println!("{}", USAGE);
+        return ExitCode::SUCCESS;
+    }
+
+    let options = match parse_options(args) {
+        Ok(Some(options)) => options,
+        Ok(None) => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Err(e) => {
+            eprintln!("error: {}\n\n{}", e, USAGE);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut cli = Cli::load();
+
+    let result = match command.as_str() {
+        "probe" => probe_command(&options).await,
+        "trim" | "burn-subs" | "mux-subs" | "concat" => match job_params(&command, &options, &cli.settings, &cli.user_presets) {
+            Ok(params) => cli.run_jobs(vec![params], 0).await,
+            Err(e) => Err(e),
+        },
+        "batch" => match cli.read_batch(&options) {
+            Ok((jobs, rejected)) => cli.run_jobs(jobs, rejected).await,
+            Err(e) => Err(e),
+        },
+        other => Err(format!("Unknown command \"{}\"\n\n{}", other, USAGE)),
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(_) if cli.interrupted.load(Ordering::SeqCst) => ExitCode::from(INTERRUPTED_EXIT_CODE),
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Parses everything after the command. Returns `None` when help was asked for.
+fn parse_options(mut args: impl Iterator<Item = String>) -> Result<Option<Options>, String> {
+    let mut options = Options::default();
+
+    while let Some(arg) = args.next() {
+        let mut value = |flag: &str| args.next().ok_or_else(|| format!("{} needs a value", flag));
+
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "-o" | "--output" => options.output = Some(value(&arg)?),
+            "--start" => options.start_time = Some(manifest::parse_timecode(&value(&arg)?)?),
+            "--end" => options.end_time = Some(manifest::parse_timecode(&value(&arg)?)?),
+            "--mode" => options.trim_mode = parse_choice(&value(&arg)?, "--mode")?,
+            "--preset" => options.preset = Some(value(&arg)?),
+            "--sub-lang" => {
+                options.subtitle_languages = value(&arg)?.split(',').map(|code| code.trim().to_string()).collect()
+            }
+            "--on-collision" => options.collision_policy = parse_choice(&value(&arg)?, "--on-collision")?,
+            flag if flag.starts_with('-') && flag.len() > 1 => return Err(format!("Unknown option {}", flag)),
+            _ => options.positional.push(arg),
+        }
+    }
+
+    Ok(Some(options))
+}
+
+/// Reads `value` as one of the snake_case names the app's JSON API uses.
+fn parse_choice<T: DeserializeOwned>(value: &str, flag: &str) -> Result<T, String> {
+    serde_json::from_value(serde_json::Value::String(value.to_string()))
+        .map_err(|_| format!("Invalid value for {}: {}", flag, value))
+}
+
+fn format_clock(seconds: f64) -> String {
+    naming::format_time_token(seconds).replace('-', ":")
+}
+
+fn expect_positional(options: &Options, count: usize, what: &str) -> Result<(), String> {
+    if options.positional.len() != count {
+        return Err(format!("Expected {}", what));
+    }
+    Ok(())
+}
+
+async fn probe_command(options: &Options) -> Result<(), String> {
+    expect_positional(options, 1, "one input file")?;
+    let info = probe::probe_media_info(&options.positional[0]).await?;
+    let json = serde_json::to_string_pretty(&info).map_err(|e| format!("Failed to serialize media info: {}", e))?;
+    println!("{}", json);
+    Ok(())
+}
+
+/// Builds the job for `trim`, `burn-subs`, `mux-subs` or `concat`.
+fn job_params(
+    command: &str,
+    options: &Options,
+    settings: &Settings,
+    user_presets: &[Preset],
+) -> Result<ProcessVideoParams, String> {
+    let mut params = match command {
+        "trim" => {
+            expect_positional(options, 1, "one input file")?;
+            ProcessVideoParams {
+                input_file: options.positional[0].clone(),
+                ..Default::default()
+            }
+        }
+        "burn-subs" => {
+            expect_positional(options, 2, "an input file and a subtitle file")?;
+            ProcessVideoParams {
+                input_file: options.positional[0].clone(),
+                subtitle_file: Some(options.positional[1].clone()),
+                ..Default::default()
+            }
+        }
+        "mux-subs" => {
+            if options.positional.len() < 2 {
+                return Err("Expected an input file and at least one subtitle file".to_string());
+            }
+            if options.subtitle_languages.len() > options.positional.len() - 1 {
+                return Err("More --sub-lang codes than subtitle files".to_string());
+            }
+
+            let subtitle_tracks = options.positional[1..]
+                .iter()
+                .enumerate()
+                .map(|(index, file)| SubtitleTrack {
+                    file: file.clone(),
+                    language: options.subtitle_languages.get(index).cloned(),
+                    default: index == 0,
+                    ..Default::default()
+                })
+                .collect();
+
+            ProcessVideoParams {
+                input_file: options.positional[0].clone(),
+                subtitle_tracks,
+                ..Default::default()
+            }
+        }
+        _ => concat::concat_params(options.positional.clone(), String::new(), None)?,
+    };
+
+    params.start_time = options.start_time;
+    params.end_time = options.end_time;
+    params.trim_mode = options.trim_mode;
+    params.preset = options.preset.clone();
+    params.collision_policy = options.collision_policy;
+    params.output_template = true;
+    params.output_file = match options.output {
+        Some(ref output) => output.clone(),
+        None => default_output(&params, settings, user_presets)?,
+    };
+    Ok(params)
+}
+
+/// The settings' default output, keeping the input's extension unless the
+/// preset names one.
+fn default_output(params: &ProcessVideoParams, settings: &Settings, user_presets: &[Preset]) -> Result<String, String> {
+    let extension = match params.preset {
+        Some(ref name) => presets::find_preset(user_presets, name).map(|preset| preset.extension),
+        None => None,
+    };
+    settings.default_output(&params.input_file, extension)
+}
+
+impl Cli {
+    /// Reads the app's settings and presets. Problems are reported and
+    /// replaced by defaults, as the app does at startup.
+    fn load() -> Self {
+        let config_dir = paths::app_config_dir();
+
+        let settings = match config_dir
+            .as_ref()
+            .map_err(Clone::clone)
+            .and_then(|dir| settings::read_settings(&dir.join(SETTINGS_FILE_NAME)))
+        {
+            Ok(settings) => settings,
+            Err(e) => {
+                eprintln!("warning: could not load settings, using defaults: {}", e);
+                Settings::default()
+            }
+        };
+
+        let user_presets = match config_dir.and_then(|dir| presets::read_presets(&dir.join(PRESETS_FILE_NAME))) {
+            Ok(user_presets) => user_presets,
+            Err(e) => {
+                eprintln!("warning: could not load saved presets: {}", e);
+                Vec::new()
+            }
+        };
+
+        binaries::install(binaries::resolve(&settings.binaries));
+
+        let interrupted = Arc::new(AtomicBool::new(false));
+        let interrupt = Arc::new(Notify::new());
+        {
+            let interrupted = interrupted.clone();
+            let interrupt = interrupt.clone();
+            tokio::spawn(async move {
+                while tokio::signal::ctrl_c().await.is_ok() {
+                    interrupted.store(true, Ordering::SeqCst);
+                    interrupt.notify_one();
+                }
+            });
+        }
+
+        Self {
+            settings,
+            user_presets,
+            claimed: HashSet::new(),
+            interrupted,
+            interrupt,
+        }
+    }
+
+    /// Expands a manifest and checks every row before anything runs,
+    /// printing the rows that fail. Returns the valid jobs and the number of
+    /// rejected rows.
+    fn read_batch(&self, options: &Options) -> Result<(Vec<ProcessVideoParams>, usize), String> {
+        expect_positional(options, 1, "one manifest file")?;
+        let manifest = manifest::read_manifest(Path::new(&options.positional[0]))?;
+
+        let mut jobs = Vec::new();
+        let mut rejected = 0;
+        for (index, row) in manifest.expand(&Map::new()).into_iter().enumerate() {
+            let checked = row
+                .and_then(|params| presets::resolve_preset(&self.user_presets, self.settings.apply_default_encoding(params)))
+                .and_then(|params| job::validate_inputs(&params).map(|_| params));
+
+            match checked {
+                Ok(params) => jobs.push(params),
+                Err(e) => {
+                    eprintln!("Row {}: {}", manifest.row(index), e);
+                    rejected += 1;
+                }
+            }
+        }
+        Ok((jobs, rejected))
+    }
+
+    /// Runs `jobs` in order. Every job is attempted; the run fails if any of
+    /// them did or `rejected` rows were left out, and stops at the first
+    /// Ctrl+C.
+    async fn run_jobs(&mut self, jobs: Vec<ProcessVideoParams>, rejected: usize) -> Result<(), String> {
+        let count = jobs.len();
+        let mut failed = rejected;
+
+        for (index, params) in jobs.into_iter().enumerate() {
+            let label = format!("[{}/{}]", index + 1, count);
+            let params = ProcessVideoParams {
+                batch_index: params.batch_index.or(Some(index + 1)),
+                ..params
+            };
+
+            match self.run_job(params, &label).await {
+                Ok(outcome) if outcome.status == JobStatus::Cancelled => {
+                    eprintln!("{} Cancelled", label);
+                    return Err("Cancelled".to_string());
+                }
+                Ok(outcome) if outcome.status == JobStatus::Failed => {
+                    eprintln!("{} Failed: {}", label, outcome.error.unwrap_or_default());
+                    failed += 1;
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    eprintln!("{} Failed: {}", label, e);
+                    failed += 1;
+                }
+            }
+        }
+
+        if failed > 0 {
+            return Err(format!("{} of {} job(s) failed", failed, count + rejected));
+        }
+        Ok(())
+    }
+
+    /// Settles the job's options and output name the way `queue_job` does in
+    /// the app, then runs it.
+    async fn run_job(&mut self, params: ProcessVideoParams, label: &str) -> Result<JobOutcome, String> {
+        let mut params = presets::resolve_preset(&self.user_presets, self.settings.apply_default_encoding(params))?;
+        job::validate_inputs(&params)?;
+
+        let rendered = naming::render_output(&params).await?;
+        let claimed = &self.claimed;
+        let Some(output) = naming::resolve_collision(&rendered, params.collision_policy, |candidate| {
+            candidate.exists() || claimed.contains(candidate)
+        })?
+        else {
+            eprintln!("{} Skipped: {} already exists", label, rendered.display());
+            return Ok(JobOutcome {
+                status: JobStatus::Skipped,
+                exit_code: None,
+                error: None,
+                stderr_tail: Vec::new(),
+                cancel_mode: None,
+                output_file: None,
+            });
+        };
+
+        params.output_file = output.to_string_lossy().to_string();
+        self.claimed.insert(output);
+        eprintln!("{} {} -> {}", label, params.input_file, params.output_file);
+
+        let job_id = Uuid::new_v4();
+        let prepared = PreparedJob::prepare(job_id, &params).await?;
+        let result = self.run_plan(job_id, &prepared.plan, label).await;
+        let outcome = prepared.finish(job_id, result)?;
+
+        if let (JobStatus::Completed, Some(output_file)) = (outcome.status, &outcome.output_file) {
+            eprintln!("{} Done: {}", label, output_file.display());
+        }
+        Ok(outcome)
+    }
+
+    /// Runs each step in turn, printing progress, until one does not complete
+    /// or Ctrl+C is pressed.
+    async fn run_plan(&self, job_id: Uuid, plan: &JobPlan, label: &str) -> Result<JobOutcome, String> {
+        let total = plan.total_duration();
+        let mut offset = 0.0;
+        let mut outcome = JobOutcome::failed("Job has no ffmpeg steps".to_string());
+
+        for step in &plan.steps {
+            if self.interrupted.load(Ordering::SeqCst) {
+                return Ok(JobOutcome::cancelled(CancelMode::Abort, Vec::new()));
+            }
+
+            let runner::SpawnedStep {
+                mut child,
+                stdout,
+                stderr,
+            } = runner::spawn_step(&step.args)?;
+
+            let output = async {
+                tokio::join!(
+                    print_progress(stdout, job_id, offset, total, label),
+                    runner::collect_stderr_tail(stderr, job_id)
+                )
+            };
+
+            tokio::select! {
+                (_, stderr_tail) = output => {
+                    outcome = runner::exit_outcome(child.wait().await, stderr_tail);
+                }
+                _ = self.interrupt.notified() => {
+                    let _ = child.kill().await;
+                    eprintln!();
+                    return Ok(JobOutcome::cancelled(CancelMode::Abort, Vec::new()));
+                }
+            }
+
+            if outcome.status != JobStatus::Completed {
+                break;
+            }
+            offset += step.duration;
+        }
+
+        Ok(outcome)
+    }
+}
+
+/// Rewrites one status line on stderr for each progress report.
+async fn print_progress(
+    stdout: impl tokio::io::AsyncRead + Unpin,
+    job_id: Uuid,
+    offset: f64,
+    total: f64,
+    label: &str,
+) {
+    let mut reader = ProgressReader::new(stdout);
+    let mut printed = false;
+
+    while let Some(progress) = reader.next().await {
+        let payload = ProgressPayload::from_progress(job_id, &progress, offset, total);
+
+        let mut line = format!(
+            "\r{} {:5.1}%  {} / {}",
+            label,
+            payload.percent,
+            format_clock(payload.seconds),
+            format_clock(total)
+        );
+        if let Some(speed) = payload.speed {
+            line.push_str(&format!("  {:.2}x", speed));
+        }
+        if let Some(eta) = payload.eta_seconds {
+            line.push_str(&format!("  ETA {}", format_clock(eta)));
+        }
+
+        let mut stderr = std::io::stderr();
+        let _ = write!(stderr, "{}\x1b[K", line);
+        let _ = stderr.flush();
+        printed = true;
+    }
+
+    if printed {
+        eprintln!();
+    }
+}
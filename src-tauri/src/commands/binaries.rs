@@ -0,0 +1,70 @@
+use crate::engine::binaries::{self, BinaryPaths, ResolvedBinaries};
+use crate::commands::settings::save_settings;
+use crate::engine::settings::Settings;
+use crate::state::AppState;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, State};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BinaryStatus {
+    pub ffmpeg: PathBuf,
+    pub ffprobe: PathBuf,
+    pub version: String,
+}
+
+/// Checks that `resolved` are working ffmpeg/ffprobe binaries of a supported
+/// version. Returns the ffmpeg version.
+pub async fn verify_binaries(resolved: &ResolvedBinaries) -> Result<String, String> {
+    let version = binaries::query_version(&resolved.ffmpeg).await?;
+    binaries::check_min_version(&version)?;
+    binaries::query_version(&resolved.ffprobe).await?;
+    Ok(version)
+}
+
+#[tauri::command]
+pub async fn get_ffmpeg_binaries() -> Result<BinaryStatus, String> {
+    let resolved = binaries::current();
+    let version = verify_binaries(&resolved).await?;
+
+    Ok(BinaryStatus {
+        ffmpeg: resolved.ffmpeg,
+        ffprobe: resolved.ffprobe,
+        version,
+    })
+}
+
+/// Validates and saves the binaries to use. Passing no paths switches back to
+/// auto-discovery. When only ffmpeg is given, an ffprobe next to it is preferred.
+#[tauri::command]
+pub async fn set_ffmpeg_path(
+    ffmpeg_path: Option<String>,
+    ffprobe_path: Option<String>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<BinaryStatus, String> {
+    for path in ffmpeg_path.iter().chain(ffprobe_path.iter()) {
+        if !Path::new(path).is_file() {
+            return Err(format!("{} does not exist", path));
+        }
+    }
+
+    let paths = BinaryPaths {
+        ffmpeg_path,
+        ffprobe_path,
+    };
+    let resolved = binaries::resolve(&paths);
+    let version = verify_binaries(&resolved).await?;
+
+    let settings = Settings {
+        binaries: paths,
+        ..state.settings.lock().await.clone()
+    };
+    save_settings(&app, state.inner(), settings).await?;
+
+    Ok(BinaryStatus {
+        ffmpeg: resolved.ffmpeg,
+        ffprobe: resolved.ffprobe,
+        version,
+    })
+}
@@ -0,0 +1,357 @@
+use crate::engine::binaries;
+use crate::engine::container::Container;
+use crate::engine::job::ProcessVideoParams;
+use crate::engine::encoding::{AudioCodec, VideoCodec};
+use crate::state::AppState;
+use serde::Serialize;
+use tauri::State;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CodecKind {
+    Video,
+    Audio,
+    Subtitle,
+    Data,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CodecEntry {
+    pub name: String,
+    pub kind: CodecKind,
+    pub description: String,
+}
+
+/// What the local ffmpeg build can do, as reported by its own listings.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct FfmpegCapabilities {
+    /// Version string from `ffmpeg -version`, e.g. `6.1.1` or `N-113000-g1234abcd`.
+    pub version: Option<String>,
+    /// `--enable-*` and other flags the build was configured with.
+    pub configuration: Vec<String>,
+    pub encoders: Vec<CodecEntry>,
+    pub decoders: Vec<CodecEntry>,
+    pub muxers: Vec<String>,
+    pub filters: Vec<String>,
+    pub hwaccels: Vec<String>,
+}
+
+impl FfmpegCapabilities {
+    pub fn has_encoder(&self, name: &str) -> bool {
+        self.encoders.iter().any(|encoder| encoder.name == name)
+    }
+
+    pub fn has_filter(&self, name: &str) -> bool {
+        self.filters.iter().any(|filter| filter == name)
+    }
+
+    /// Rejects jobs that need an encoder or filter this build lacks.
+    pub fn check_params(&self, params: &ProcessVideoParams) -> Result<(), String> {
+        let encoding = params.effective_encoding();
+
+        if encoding.video.codec != VideoCodec::Copy && !self.has_encoder(encoding.video.codec.ffmpeg_name()) {
+            return Err(format!(
+                "This ffmpeg build has no {} encoder",
+                encoding.video.codec.ffmpeg_name()
+            ));
+        }
+
+        let audio = encoding.audio.codec;
+        if !matches!(audio, AudioCodec::Copy | AudioCodec::None) && !self.has_encoder(audio.ffmpeg_name()) {
+            return Err(format!("This ffmpeg build has no {} encoder", audio.ffmpeg_name()));
+        }
+
+        if params.subtitle_file.is_some() && !self.has_filter("subtitles") {
+            return Err("This ffmpeg build was compiled without libass, so subtitles cannot be burned in".to_string());
+        }
+
+        if let Some(container) = Container::from_path(&params.output_file) {
+            for track in &params.subtitle_tracks {
+                let codec = track.codec(container)?;
+                if !self.has_encoder(codec) {
+                    return Err(format!("This ffmpeg build has no {} subtitle encoder", codec));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Extracts the version and configure flags from `ffmpeg -version`.
+pub fn parse_version(output: &str) -> (Option<String>, Vec<String>) {
+    let version = output
+        .lines()
+        .next()
+        .and_then(|line| line.strip_prefix("ffmpeg version "))
+        .and_then(|rest| rest.split_whitespace().next())
+        .map(str::to_string);
+
+    let configuration = output
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("configuration:"))
+        .map(|flags| flags.split_whitespace().map(str::to_string).collect())
+        .unwrap_or_default();
+
+    (version, configuration)
+}
+
+/// Rows after the ` ------` / ` --` separator that ends a listing's legend.
+fn listing_rows(output: &str) -> impl Iterator<Item = &str> {
+    output
+        .lines()
+        .skip_while(|line| !line.trim_start().starts_with("--"))
+        .skip(1)
+        .filter(|line| !line.trim().is_empty())
+}
+
+/// Parses `ffmpeg -encoders` or `ffmpeg -decoders`.
+pub fn parse_codecs(output: &str) -> Vec<CodecEntry> {
+    listing_rows(output)
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            let flags = parts.next()?;
+            let name = parts.next()?;
+            let kind = match flags.chars().next()? {
+                'V' => CodecKind::Video,
+                'A' => CodecKind::Audio,
+                'S' => CodecKind::Subtitle,
+                _ => CodecKind::Data,
+            };
+
+            Some(CodecEntry {
+                name: name.to_string(),
+                kind,
+                description: parts.collect::<Vec<_>>().join(" "),
+            })
+        })
+        .collect()
+}
+
+/// Parses `ffmpeg -muxers`. Some rows list several comma-separated names.
+pub fn parse_muxers(output: &str) -> Vec<String> {
+    listing_rows(output)
+        .filter_map(|line| line.split_whitespace().nth(1))
+        .flat_map(|names| names.split(','))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Parses `ffmpeg -filters`, whose rows read `flags name in->out description`.
+pub fn parse_filters(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            let _flags = parts.next()?;
+            let name = parts.next()?;
+            parts.next()?.contains("->").then(|| name.to_string())
+        })
+        .collect()
+}
+
+/// Parses `ffmpeg -hwaccels`: a heading followed by one method per line.
+pub fn parse_hwaccels(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .skip_while(|line| !line.trim_end().ends_with(':'))
+        .skip(1)
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+async fn run_ffmpeg_listing(flag: &str) -> Result<String, String> {
+    let output = binaries::ffmpeg_command()
+        .args(["-hide_banner", flag])
+        .output()
+        .await
+        .map_err(|e| format!("Failed to run ffmpeg {}: {}", flag, e))?;
+
+    if !output.status.success() {
+        return Err(format!("ffmpeg {} exited with {:?}", flag, output.status.code()));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+pub async fn discover_capabilities() -> Result<FfmpegCapabilities, String> {
+    let version_output = binaries::ffmpeg_command()
+        .arg("-version")
+        .output()
+        .await
+        .map_err(|e| format!("Failed to run ffmpeg -version: {}", e))?;
+    let (version, configuration) = parse_version(&String::from_utf8_lossy(&version_output.stdout));
+
+    Ok(FfmpegCapabilities {
+        version,
+        configuration,
+        encoders: parse_codecs(&run_ffmpeg_listing("-encoders").await?),
+        decoders: parse_codecs(&run_ffmpeg_listing("-decoders").await?),
+        muxers: parse_muxers(&run_ffmpeg_listing("-muxers").await?),
+        filters: parse_filters(&run_ffmpeg_listing("-filters").await?),
+        hwaccels: parse_hwaccels(&run_ffmpeg_listing("-hwaccels").await?),
+    })
+}
+
+/// Returns the cached report, discovering it on first use or when `refresh` is set.
+pub async fn capabilities(state: &AppState, refresh: bool) -> Result<FfmpegCapabilities, String> {
+    let mut cached = state.capabilities.lock().await;
+
+    if refresh || cached.is_none() {
+        *cached = Some(discover_capabilities().await?);
+    }
+
+    Ok(cached.clone().expect("capabilities were just discovered"))
+}
+
+#[tauri::command]
+pub async fn get_ffmpeg_capabilities(
+    refresh: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<FfmpegCapabilities, String> {
+    capabilities(state.inner(), refresh.unwrap_or(false)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::subtitles::SubtitleTrack;
+
+    const VERSION: &str = "ffmpeg version 6.1.1 Copyright (c) 2000-2023 the FFmpeg developers
+built with gcc 13.2.0
+configuration: --prefix=/usr --enable-gpl --enable-libx264 --enable-libass
+libavutil      58. 29.100 / 58. 29.100
+";
+
+    const ENCODERS: &str = "Encoders:
+ V..... = Video
+ A..... = Audio
+ S..... = Subtitle
+ .F.... = Frame-level multithreading
+ ------
+ V....D libx264              libx264 H.264 / AVC / MPEG-4 AVC / MPEG-4 part 10 (codec h264)
+ V....D libvpx-vp9           libvpx VP9 (codec vp9)
+ A....D aac                  AAC (Advanced Audio Coding)
+ S..... srt                  SubRip subtitle
+";
+
+    const MUXERS: &str = "File formats:
+ D. = Demuxing supported
+ .E = Muxing supported
+ --
+  E matroska        Matroska
+  E mp4             MP4 (MPEG-4 Part 14)
+  E webm            WebM
+";
+
+    const FILTERS: &str = "Filters:
+  T.. = Timeline support
+  .S. = Slice threading
+  ... = Unsupported
+ ... acopy             A->A       Copy the input audio unchanged to the output.
+ TS. scale             V->V       Scale the input video size and/or convert the image format.
+ ... subtitles         V->V       Render text subtitles onto input video using the libass library.
+ ... concat            N->N       Concatenate audio and video streams.
+";
+
+    const HWACCELS: &str = "Hardware acceleration methods:
+vdpau
+cuda
+vaapi
+
+";
+
+    #[test]
+    fn test_parse_version() {
+        let (version, configuration) = parse_version(VERSION);
+        assert_eq!(version.as_deref(), Some("6.1.1"));
+        assert!(configuration.contains(&"--enable-libass".to_string()));
+        assert_eq!(configuration.len(), 4);
+    }
+
+    #[test]
+    fn test_parse_codecs() {
+        let encoders = parse_codecs(ENCODERS);
+        assert_eq!(encoders.len(), 4);
+        assert_eq!(encoders[0].name, "libx264");
+        assert_eq!(encoders[0].kind, CodecKind::Video);
+        assert_eq!(encoders[2].kind, CodecKind::Audio);
+        assert_eq!(encoders[3].kind, CodecKind::Subtitle);
+        assert_eq!(encoders[1].description, "libvpx VP9 (codec vp9)");
+    }
+
+    #[test]
+    fn test_parse_muxers_filters_and_hwaccels() {
+        assert_eq!(parse_muxers(MUXERS), vec!["matroska", "mp4", "webm"]);
+        assert_eq!(parse_filters(FILTERS), vec!["acopy", "scale", "subtitles", "concat"]);
+        assert_eq!(parse_hwaccels(HWACCELS), vec!["vdpau", "cuda", "vaapi"]);
+    }
+
+    #[test]
+    fn test_check_params_rejects_missing_encoder() {
+        let caps = FfmpegCapabilities {
+            encoders: parse_codecs(ENCODERS),
+            filters: parse_filters(FILTERS),
+            ..Default::default()
+        };
+        let mut params = ProcessVideoParams {
+            input_file: "/in.mp4".to_string(),
+            output_file: "/out.mkv".to_string(),
+            subtitle_file: Some("/subs.srt".to_string()),
+            ..Default::default()
+        };
+        assert!(caps.check_params(&params).is_ok());
+
+        let mut encoding = params.effective_encoding();
+        encoding.video.codec = VideoCodec::Libsvtav1;
+        params.encoding = Some(encoding);
+        assert_eq!(
+            caps.check_params(&params).unwrap_err(),
+            "This ffmpeg build has no libsvtav1 encoder"
+        );
+    }
+
+    #[test]
+    fn test_check_params_requires_subtitles_filter() {
+        let caps = FfmpegCapabilities {
+            encoders: parse_codecs(ENCODERS),
+            ..Default::default()
+        };
+        let params = ProcessVideoParams {
+            input_file: "/in.mp4".to_string(),
+            output_file: "/out.mp4".to_string(),
+            subtitle_file: Some("/subs.srt".to_string()),
+            ..Default::default()
+        };
+        assert!(caps.check_params(&params).unwrap_err().contains("libass"));
+    }
+
+    #[test]
+    fn test_check_params_requires_subtitle_track_encoder() {
+        let caps = FfmpegCapabilities {
+            encoders: parse_codecs(ENCODERS),
+            ..Default::default()
+        };
+        let params = ProcessVideoParams {
+            input_file: "/in.mp4".to_string(),
+            output_file: "/out.mkv".to_string(),
+            subtitle_tracks: vec![SubtitleTrack {
+                file: "/subs.srt".to_string(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        assert!(caps.check_params(&params).is_ok());
+
+        let params = ProcessVideoParams {
+            output_file: "/out.mp4".to_string(),
+            ..params
+        };
+        assert_eq!(
+            caps.check_params(&params).unwrap_err(),
+            "This ffmpeg build has no mov_text subtitle encoder"
+        );
+    }
+}
@@ -0,0 +1,18 @@
+use crate::commands::queue;
+use crate::engine::concat;
+use crate::engine::encoding::EncodingProfile;
+use crate::state::AppState;
+use tauri::{AppHandle, State};
+
+/// Queues a job joining `input_files` in order into `output_file`.
+#[tauri::command]
+pub async fn concat_videos(
+    input_files: Vec<String>,
+    output_file: String,
+    encoding: Option<EncodingProfile>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let params = concat::concat_params(input_files, output_file, encoding)?;
+    queue::queue_job(params, &app, state.inner()).await
+}
@@ -0,0 +1,342 @@
+use crate::commands::queue;
+use crate::engine::job::{JobOutcome, JobStatus, ProcessVideoParams};
+use crate::engine::storage;
+use crate::state::AppState;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager, State};
+use uuid::Uuid;
+
+const HISTORY_DIR_NAME: &str = "history";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobRecord {
+    pub job_id: Uuid,
+    pub params: ProcessVideoParams,
+    pub status: JobStatus,
+    pub started_at: String,
+    pub finished_at: Option<String>,
+    pub exit_code: Option<i32>,
+    pub output_size: Option<u64>,
+    pub error: Option<String>,
+    pub stderr_tail: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct JobSummary {
+    pub job_id: Uuid,
+    pub input_file: String,
+    pub output_file: String,
+    pub status: JobStatus,
+    pub started_at: String,
+    pub finished_at: Option<String>,
+    pub output_size: Option<u64>,
+}
+
+impl JobRecord {
+    pub fn started(job_id: Uuid, params: ProcessVideoParams) -> Self {
+        Self {
+            job_id,
+            params,
+            status: JobStatus::Running,
+            started_at: chrono::Local::now().to_rfc3339(),
+            finished_at: None,
+            exit_code: None,
+            output_size: None,
+            error: None,
+            stderr_tail: Vec::new(),
+        }
+    }
+
+    pub fn finish(&mut self, outcome: JobOutcome) {
+        let kept_output = outcome.keeps_output();
+        self.status = outcome.status;
+        self.exit_code = outcome.exit_code;
+        self.error = outcome.error;
+        self.stderr_tail = outcome.stderr_tail;
+        self.finished_at = Some(chrono::Local::now().to_rfc3339());
+
+        if kept_output {
+            if let Some(output_file) = outcome.output_file {
+                self.params.output_file = output_file.to_string_lossy().to_string();
+            }
+            self.output_size = std::fs::metadata(&self.params.output_file)
+                .map(|metadata| metadata.len())
+                .ok();
+        }
+    }
+
+    pub fn summary(&self) -> JobSummary {
+        JobSummary {
+            job_id: self.job_id,
+            input_file: self.params.input_file.clone(),
+            output_file: self.params.output_file.clone(),
+            status: self.status,
+            started_at: self.started_at.clone(),
+            finished_at: self.finished_at.clone(),
+            output_size: self.output_size,
+        }
+    }
+}
+
+fn history_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+    Ok(data_dir.join(HISTORY_DIR_NAME))
+}
+
+fn record_path(dir: &Path, job_id: Uuid) -> PathBuf {
+    dir.join(format!("{}.json", job_id))
+}
+
+pub fn write_record(dir: &Path, record: &JobRecord) -> Result<(), String> {
+    storage::write_json_atomic(&record_path(dir, record.job_id), record)
+        .map_err(|e| format!("Failed to write job record: {}", e))
+}
+
+pub fn read_record(dir: &Path, job_id: Uuid) -> Result<JobRecord, String> {
+    let contents =
+        std::fs::read(record_path(dir, job_id)).map_err(|_| "Job not found in history".to_string())?;
+    serde_json::from_slice(&contents).map_err(|e| format!("Failed to parse job record: {}", e))
+}
+
+/// Returns all readable records, newest first.
+pub fn read_records(dir: &Path) -> Vec<JobRecord> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut records: Vec<JobRecord> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().and_then(|ext| ext.to_str()) == Some("json"))
+        .filter_map(|path| match std::fs::read(&path) {
+            Ok(contents) => match serde_json::from_slice(&contents) {
+                Ok(record) => Some(record),
+                Err(e) => {
+                    log::warn!("Skipping unreadable job record {:?}: {}", path, e);
+                    None
+                }
+            },
+            Err(_) => None,
+        })
+        .collect();
+
+    records.sort_by(|a: &JobRecord, b: &JobRecord| b.started_at.cmp(&a.started_at));
+    records
+}
+
+/// Marks records left in the running state by a previous session as interrupted.
+pub fn mark_interrupted(dir: &Path) -> usize {
+    let mut count = 0;
+    for mut record in read_records(dir) {
+        if record.status == JobStatus::Running {
+            record.status = JobStatus::Interrupted;
+            if write_record(dir, &record).is_ok() {
+                count += 1;
+            }
+        }
+    }
+    count
+}
+
+/// Deletes finished records. Records of jobs that are still running are kept.
+pub fn clear_records(dir: &Path) -> Result<(), String> {
+    for record in read_records(dir) {
+        if record.status != JobStatus::Running {
+            std::fs::remove_file(record_path(dir, record.job_id))
+                .map_err(|e| format!("Failed to delete job record: {}", e))?;
+        }
+    }
+    Ok(())
+}
+
+/// Persists a record, logging rather than failing the job if the write fails.
+pub fn save_record(app: &AppHandle, record: &JobRecord) {
+    if let Err(e) = history_dir(app).and_then(|dir| write_record(&dir, record)) {
+        log::warn!("Could not save job history for {}: {}", record.job_id, e);
+    }
+}
+
+/// Called once at startup to close out jobs that were running when the app exited.
+pub fn recover_history(app: &AppHandle) {
+    match history_dir(app) {
+        Ok(dir) => {
+            let count = mark_interrupted(&dir);
+            if count > 0 {
+                log::info!("Marked {} job(s) from previous session as interrupted", count);
+            }
+        }
+        Err(e) => log::warn!("Could not locate job history: {}", e),
+    }
+}
+
+#[tauri::command]
+pub async fn list_job_history(app: AppHandle) -> Result<Vec<JobSummary>, String> {
+    let dir = history_dir(&app)?;
+    Ok(read_records(&dir).iter().map(JobRecord::summary).collect())
+}
+
+#[tauri::command]
+pub async fn get_job_details(job_id: String, app: AppHandle) -> Result<JobRecord, String> {
+    let uuid = Uuid::parse_str(&job_id).map_err(|_| "Invalid job ID".to_string())?;
+    read_record(&history_dir(&app)?, uuid)
+}
+
+#[tauri::command]
+pub async fn clear_job_history(app: AppHandle) -> Result<(), String> {
+    clear_records(&history_dir(&app)?)
+}
+
+/// Queues a new job with the same parameters as a job from the history.
+#[tauri::command]
+pub async fn rerun_job(
+    job_id: String,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let uuid = Uuid::parse_str(&job_id).map_err(|_| "Invalid job ID".to_string())?;
+    let record = read_record(&history_dir(&app)?, uuid)?;
+    queue::queue_job(record.params, &app, state.inner()).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn record(started_at: &str, status: JobStatus) -> JobRecord {
+        let mut record = JobRecord::started(
+            Uuid::new_v4(),
+            ProcessVideoParams {
+                input_file: "/input/video.mp4".to_string(),
+                output_file: "/output/video.mp4".to_string(),
+                start_time: Some(1.0),
+                end_time: Some(2.0),
+                subtitle_file: None,
+                ..Default::default()
+            },
+        );
+        record.started_at = started_at.to_string();
+        record.status = status;
+        record
+    }
+
+    #[test]
+    fn test_write_and_read_record_roundtrip() {
+        let dir = tempdir().unwrap();
+        let mut original = record("2024-01-01T10:00:00+00:00", JobStatus::Failed);
+        original.exit_code = Some(1);
+        original.stderr_tail = vec!["Invalid data found".to_string()];
+
+        write_record(dir.path(), &original).unwrap();
+        let loaded = read_record(dir.path(), original.job_id).unwrap();
+
+        assert_eq!(loaded.job_id, original.job_id);
+        assert_eq!(loaded.status, JobStatus::Failed);
+        assert_eq!(loaded.exit_code, Some(1));
+        assert_eq!(loaded.stderr_tail, vec!["Invalid data found".to_string()]);
+        assert_eq!(loaded.params.start_time, Some(1.0));
+    }
+
+    #[test]
+    fn test_read_record_missing_job() {
+        let dir = tempdir().unwrap();
+        let result = read_record(dir.path(), Uuid::new_v4());
+        assert_eq!(result.unwrap_err(), "Job not found in history");
+    }
+
+    #[test]
+    fn test_read_records_newest_first() {
+        let dir = tempdir().unwrap();
+        let older = record("2024-01-01T10:00:00+00:00", JobStatus::Completed);
+        let newer = record("2024-01-02T10:00:00+00:00", JobStatus::Completed);
+        write_record(dir.path(), &older).unwrap();
+        write_record(dir.path(), &newer).unwrap();
+
+        let records = read_records(dir.path());
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].job_id, newer.job_id);
+        assert_eq!(records[1].job_id, older.job_id);
+    }
+
+    #[test]
+    fn test_read_records_skips_corrupt_files() {
+        let dir = tempdir().unwrap();
+        write_record(dir.path(), &record("2024-01-01T10:00:00+00:00", JobStatus::Completed)).unwrap();
+        std::fs::write(dir.path().join("broken.json"), "{ not json").unwrap();
+
+        assert_eq!(read_records(dir.path()).len(), 1);
+    }
+
+    #[test]
+    fn test_mark_interrupted_only_touches_running_jobs() {
+        let dir = tempdir().unwrap();
+        let running = record("2024-01-01T10:00:00+00:00", JobStatus::Running);
+        let completed = record("2024-01-01T11:00:00+00:00", JobStatus::Completed);
+        write_record(dir.path(), &running).unwrap();
+        write_record(dir.path(), &completed).unwrap();
+
+        assert_eq!(mark_interrupted(dir.path()), 1);
+        assert_eq!(
+            read_record(dir.path(), running.job_id).unwrap().status,
+            JobStatus::Interrupted
+        );
+        assert_eq!(
+            read_record(dir.path(), completed.job_id).unwrap().status,
+            JobStatus::Completed
+        );
+    }
+
+    #[test]
+    fn test_clear_records_keeps_running_jobs() {
+        let dir = tempdir().unwrap();
+        let running = record("2024-01-01T10:00:00+00:00", JobStatus::Running);
+        let failed = record("2024-01-01T11:00:00+00:00", JobStatus::Failed);
+        write_record(dir.path(), &running).unwrap();
+        write_record(dir.path(), &failed).unwrap();
+
+        clear_records(dir.path()).unwrap();
+
+        let records = read_records(dir.path());
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].job_id, running.job_id);
+    }
+
+    #[test]
+    fn test_finish_records_outcome() {
+        let mut record = record("2024-01-01T10:00:00+00:00", JobStatus::Running);
+        record.finish(JobOutcome {
+            status: JobStatus::Failed,
+            exit_code: Some(1),
+            error: Some("boom".to_string()),
+            stderr_tail: vec!["boom".to_string()],
+            cancel_mode: None,
+            output_file: None,
+        });
+
+        assert_eq!(record.status, JobStatus::Failed);
+        assert_eq!(record.exit_code, Some(1));
+        assert!(record.finished_at.is_some());
+        assert!(record.output_size.is_none());
+    }
+
+    #[test]
+    fn test_finish_records_committed_output() {
+        let dir = tempdir().unwrap();
+        let output = dir.path().join("video_1.mp4");
+        std::fs::write(&output, "new").unwrap();
+
+        let mut record = record("2024-01-01T10:00:00+00:00", JobStatus::Running);
+        record.finish(JobOutcome {
+            status: JobStatus::Completed,
+            error: None,
+            output_file: Some(output.clone()),
+            ..JobOutcome::failed(String::new())
+        });
+        assert_eq!(record.params.output_file, output.to_string_lossy());
+        assert_eq!(record.output_size, Some(3));
+    }
+}
@@ -0,0 +1,64 @@
+use crate::commands::queue;
+use crate::engine::job::ProcessVideoParams;
+use crate::engine::manifest::{self, RowError};
+use crate::state::AppState;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::path::Path;
+use tauri::{AppHandle, State};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchManifestResult {
+    /// IDs of the queued jobs, in manifest order.
+    pub job_ids: Vec<String>,
+    /// Jobs that were not queued.
+    pub errors: Vec<RowError>,
+}
+
+/// Queues every job of a JSON, YAML or CSV manifest. All rows are checked
+/// before any is queued; rows that fail are reported and the rest still run.
+/// `defaults` fill in fields that neither the manifest defaults nor the row
+/// set, which lets CSV rows share an encoding or preset.
+#[tauri::command]
+pub async fn run_batch_manifest(
+    file_path: String,
+    defaults: Option<Map<String, Value>>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<BatchManifestResult, String> {
+    let manifest = manifest::read_manifest(Path::new(&file_path))?;
+
+    let mut errors = Vec::new();
+    let mut valid: Vec<(usize, ProcessVideoParams)> = Vec::new();
+    for (index, row) in manifest.expand(&defaults.unwrap_or_default()).into_iter().enumerate() {
+        let checked = match row {
+            Ok(params) => queue::prepare_job(params, &app, state.inner()).await,
+            Err(error) => Err(error),
+        };
+
+        match checked {
+            Ok(params) => valid.push((manifest.row(index), params)),
+            Err(error) => errors.push(RowError {
+                row: manifest.row(index),
+                error,
+            }),
+        }
+    }
+
+    let mut job_ids = Vec::new();
+    for (row, params) in valid {
+        match queue::queue_job(params, &app, state.inner()).await {
+            Ok(job_id) => job_ids.push(job_id),
+            Err(error) => errors.push(RowError { row, error }),
+        }
+    }
+    errors.sort_by_key(|error| error.row);
+
+    log::info!(
+        "Queued {} job(s) from manifest {}, {} row(s) rejected",
+        job_ids.len(),
+        file_path,
+        errors.len()
+    );
+    Ok(BatchManifestResult { job_ids, errors })
+}
@@ -1,4 +1,14 @@
 pub mod dialog;
 pub mod video;
+pub mod binaries;
+pub mod capabilities;
+pub mod settings;
+pub mod presets;
+pub mod naming;
 pub mod process;
+pub mod concat;
+pub mod queue;
+pub mod manifest;
+pub mod watch;
+pub mod history;
 pub mod logging;
@@ -0,0 +1,57 @@
+use crate::commands::presets;
+use crate::engine::job::ProcessVideoParams;
+use crate::engine::naming::{self, CollisionPolicy};
+use crate::state::{AppState, JobQueue};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, State};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OutputNamePreview {
+    /// Where the job would write, after applying the collision policy.
+    pub output_file: String,
+    /// Whether the rendered name is already taken by a file or a queued job.
+    pub exists: bool,
+    /// The job would be skipped under the `skip` policy.
+    pub skipped: bool,
+}
+
+/// A path is taken if the file exists or a pending or running job writes to it.
+fn is_taken(queue: &JobQueue, path: &Path) -> bool {
+    path.exists() || queue.writes_to(path)
+}
+
+/// Applies the collision policy against existing files and queued jobs.
+/// Returns `None` when the job should be skipped.
+pub fn resolve_output(
+    path: &Path,
+    policy: CollisionPolicy,
+    queue: &JobQueue,
+) -> Result<Option<PathBuf>, String> {
+    naming::resolve_collision(path, policy, |candidate| is_taken(queue, candidate))
+}
+
+/// Shows the file a job would write to, without queueing it.
+#[tauri::command]
+pub async fn preview_output_name(
+    params: ProcessVideoParams,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<OutputNamePreview, String> {
+    let params = presets::resolve_preset(&app, params)?;
+    let rendered = naming::render_output(&params).await?;
+
+    let queue = state.queue.lock().await;
+    let exists = is_taken(&queue, &rendered);
+    let output_file = match params.collision_policy {
+        CollisionPolicy::Increment => resolve_output(&rendered, CollisionPolicy::Increment, &queue)?
+            .unwrap_or_else(|| rendered.clone()),
+        _ => rendered,
+    };
+
+    Ok(OutputNamePreview {
+        output_file: output_file.to_string_lossy().to_string(),
+        exists,
+        skipped: exists && params.collision_policy == CollisionPolicy::Skip,
+    })
+}
@@ -0,0 +1,123 @@
+use crate::engine::job::ProcessVideoParams;
+use crate::engine::presets::{self, Preset, PRESETS_FILE_NAME};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+
+fn presets_file_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let config_dir = app
+        .path()
+        .app_config_dir()
+        .map_err(|e| format!("Failed to get app config directory: {}", e))?;
+    Ok(config_dir.join(PRESETS_FILE_NAME))
+}
+
+fn load_user_presets(app: &AppHandle) -> Result<Vec<Preset>, String> {
+    presets::read_presets(&presets_file_path(app)?)
+}
+
+/// Looks up a built-in or user preset by name.
+pub fn find_preset(app: &AppHandle, name: &str) -> Result<Preset, String> {
+    presets::find_preset(&load_user_presets(app)?, name).ok_or_else(|| format!("Preset \"{}\" not found", name))
+}
+
+/// Swaps a preset name in `params` for the preset's options. The user's
+/// presets are only read when the job names one.
+pub fn resolve_preset(app: &AppHandle, params: ProcessVideoParams) -> Result<ProcessVideoParams, String> {
+    if params.preset.is_none() {
+        return Ok(params);
+    }
+    presets::resolve_preset(&load_user_presets(app)?, params)
+}
+
+/// Built-in presets followed by the user's own.
+#[tauri::command]
+pub async fn list_presets(app: AppHandle) -> Result<Vec<Preset>, String> {
+    let mut all = presets::built_in_presets();
+    all.extend(load_user_presets(&app)?);
+    Ok(all)
+}
+
+/// Creates a preset, or replaces the user preset with the same name.
+#[tauri::command]
+pub async fn save_preset(preset: Preset, app: AppHandle) -> Result<Vec<Preset>, String> {
+    let path = presets_file_path(&app)?;
+    let mut user_presets = presets::read_presets(&path)?;
+    presets::upsert_preset(&mut user_presets, preset)?;
+    presets::write_presets(&path, &user_presets)?;
+    Ok(user_presets)
+}
+
+#[tauri::command]
+pub async fn delete_preset(name: String, app: AppHandle) -> Result<(), String> {
+    if presets::built_in_presets().iter().any(|preset| preset.matches_name(&name)) {
+        return Err(format!("\"{}\" is a built-in preset and cannot be deleted", name));
+    }
+
+    let path = presets_file_path(&app)?;
+    let mut user_presets = presets::read_presets(&path)?;
+    let before = user_presets.len();
+    user_presets.retain(|preset| !preset.matches_name(&name));
+    if user_presets.len() == before {
+        return Err(format!("Preset \"{}\" not found", name));
+    }
+
+    presets::write_presets(&path, &user_presets)
+}
+
+/// Adds every preset in a shared JSON file. Nothing is imported if any preset
+/// is invalid. Returns the names that were imported.
+#[tauri::command]
+pub async fn import_presets(file_path: String, app: AppHandle) -> Result<Vec<String>, String> {
+    if !Path::new(&file_path).is_file() {
+        return Err("Preset file does not exist".to_string());
+    }
+
+    let imported = presets::read_presets(Path::new(&file_path))?;
+    if imported.is_empty() {
+        return Err("Preset file contains no presets".to_string());
+    }
+
+    let path = presets_file_path(&app)?;
+    let mut user_presets = presets::read_presets(&path)?;
+    let mut names = Vec::new();
+    for preset in imported {
+        names.push(preset.name.trim().to_string());
+        presets::upsert_preset(&mut user_presets, preset)?;
+    }
+
+    presets::write_presets(&path, &user_presets)?;
+    log::info!("Imported {} preset(s) from {}", names.len(), file_path);
+    Ok(names)
+}
+
+/// Writes the named user presets, or all of them when `names` is omitted, to
+/// a JSON file that `import_presets` can read.
+#[tauri::command]
+pub async fn export_presets(
+    file_path: String,
+    names: Option<Vec<String>>,
+    app: AppHandle,
+) -> Result<usize, String> {
+    let user_presets = load_user_presets(&app)?;
+
+    let selected = match names {
+        Some(names) => names
+            .iter()
+            .map(|name| {
+                user_presets
+                    .iter()
+                    .find(|preset| preset.matches_name(name))
+                    .cloned()
+                    .ok_or_else(|| format!("Preset \"{}\" not found", name))
+            })
+            .collect::<Result<Vec<_>, _>>()?,
+        None => user_presets,
+    };
+
+    if selected.is_empty() {
+        return Err("There are no presets to export".to_string());
+    }
+
+    presets::write_presets(Path::new(&file_path), &selected)?;
+    Ok(selected.len())
+}
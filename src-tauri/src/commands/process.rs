@@ -1,29 +1,17 @@
-use crate::state::{AppState, ProcessJob};
-use std::collections::VecDeque;
-use regex::Regex;
-use serde::{Deserialize, Serialize};
-use std::path::Path;
+use crate::commands::history::{self, JobRecord};
+use crate::commands::{queue, watch};
+use crate::engine::container::Container;
+use crate::engine::encoding::EncodingProfile;
+use crate::engine::job::{CancelMode, JobOutcome, JobPlan, JobStatus, PreparedJob, ProcessVideoParams, ProgressPayload};
+use crate::engine::progress::ActiveClock;
+use crate::engine::runner::{self, ProgressReader};
+use crate::state::{AppState, ProcessJob, QueuedJob};
+use serde::Serialize;
+use std::time::{Duration, Instant};
 use tauri::{AppHandle, Emitter, State};
-use tokio::io::{AsyncReadExt, BufReader};
-use tokio::process::Command;
+use tokio::io::AsyncWriteExt;
 use uuid::Uuid;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ProcessVideoParams {
-    pub input_file: String,
-    pub output_file: String,
-    pub start_time: Option<f64>,
-    pub end_time: Option<f64>,
-    pub subtitle_file: Option<String>,
-}
-
-#[derive(Debug, Clone, Serialize)]
-pub struct ProgressPayload {
-    pub job_id: String,
-    pub seconds: f64,
-    pub percent: f64,
-}
-
 #[derive(Debug, Clone, Serialize)]
 pub struct CompletePayload {
     pub job_id: String,
@@ -35,24 +23,17 @@ pub struct ErrorPayload {
     pub error: String,
 }
 
-// Extracted pure functions for testing
-pub fn parse_ffmpeg_time(line: &str) -> Option<f64> {
-    let time_regex = Regex::new(r"time=(\d+):(\d+):(\d+\.?\d*)").unwrap();
-
-    time_regex.captures(line).map(|captures| {
-        let hours: f64 = captures[1].parse().unwrap_or(0.0);
-        let minutes: f64 = captures[2].parse().unwrap_or(0.0);
-        let seconds: f64 = captures[3].parse().unwrap_or(0.0);
-        hours * 3600.0 + minutes * 60.0 + seconds
-    })
-}
+/// How long ffmpeg gets to finalize its output after `q` before it is killed.
+const FINISH_EARLY_TIMEOUT: Duration = Duration::from_secs(10);
 
-pub fn calculate_progress_percentage(current_seconds: f64, duration: f64) -> f64 {
-    if duration > 0.0 {
-        (current_seconds / duration * 100.0).min(100.0)
-    } else {
-        0.0
-    }
+#[derive(Debug, Clone, Serialize)]
+pub struct CancelledPayload {
+    pub job_id: String,
+    /// What actually happened. Finishing early falls back to `abort` when
+    /// ffmpeg does not stop in time or an intermediate step was running.
+    pub mode: CancelMode,
+    /// The playable partial output, when one was kept.
+    pub output_file: Option<String>,
 }
 
 #[tauri::command]
@@ -61,502 +42,391 @@ pub async fn process_video(
     app: AppHandle,
     state: State<'_, AppState>,
 ) -> Result<String, String> {
-    // Generate job ID
-    let job_id = Uuid::new_v4();
-
-    // Validate inputs
-    validate_inputs(&params)?;
-
-    // Build ffmpeg arguments
-    let args = build_ffmpeg_args(&params)?;
-
-    log::info!("Starting ffmpeg with args: {:?}", args);
-
-    // Spawn ffmpeg process
-    let mut command = Command::new("ffmpeg");
-    command
-        .args(&args)
-        .stderr(std::process::Stdio::piped())
-        .stdout(std::process::Stdio::piped());
-
-    // Hide console window on Windows
-    #[cfg(windows)]
-    {
-        use std::os::windows::process::CommandExt;
-        const CREATE_NO_WINDOW: u32 = 0x08000000;
-        command.creation_flags(CREATE_NO_WINDOW);
+    queue::queue_job(params, &app, state.inner()).await
+}
+
+/// Runs a job taken off the queue to completion, then frees its queue slot.
+pub async fn run_job(job: QueuedJob, app: AppHandle, state: AppState) {
+    let job_id = job.job_id;
+    let mut record = JobRecord::started(job_id, job.params.clone());
+    history::save_record(&app, &record);
+
+    let outcome = match execute_job(job_id, &job.params, &app, &state).await {
+        Ok(outcome) => outcome,
+        Err(error) => {
+            log::error!("Job {} failed to start: {}", job_id, error);
+            JobOutcome::failed(error)
+        }
+    };
+
+    emit_outcome(&app, job_id, &outcome);
+
+    let status = outcome.status;
+    record.finish(outcome);
+    history::save_record(&app, &record);
+    watch::job_finished(&job, status);
+
+    state.cancelled_jobs.lock().await.remove(&job_id);
+    state.paused_jobs.lock().await.remove(&job_id);
+    state.queue.lock().await.finish(job_id);
+    state.queue_notify.notify_one();
+}
+
+/// Emits the terminal event for a job.
+fn emit_outcome(app: &AppHandle, job_id: Uuid, outcome: &JobOutcome) {
+    match outcome.status {
+        JobStatus::Completed => {
+            let _ = app.emit(
+                "ffmpeg-complete",
+                CompletePayload {
+                    job_id: job_id.to_string(),
+                },
+            );
+        }
+        JobStatus::Failed => {
+            let _ = app.emit(
+                "ffmpeg-error",
+                ErrorPayload {
+                    job_id: job_id.to_string(),
+                    error: outcome.error.clone().unwrap_or_default(),
+                },
+            );
+        }
+        JobStatus::Cancelled => {
+            let mode = outcome.cancel_mode.unwrap_or_default();
+            let _ = app.emit(
+                "ffmpeg-cancelled",
+                CancelledPayload {
+                    job_id: job_id.to_string(),
+                    mode,
+                    output_file: outcome
+                        .output_file
+                        .as_ref()
+                        .map(|output_file| output_file.to_string_lossy().to_string()),
+                },
+            );
+        }
+        _ => {}
     }
+}
 
-    let mut child = command
-        .spawn()
-        .map_err(|e| format!("Failed to spawn ffmpeg: {}. Make sure ffmpeg is installed and in PATH.", e))?;
+/// Runs a job against a partial file next to the output, which replaces
+/// `output_file` only once every step has succeeded.
+async fn execute_job(
+    job_id: Uuid,
+    params: &ProcessVideoParams,
+    app: &AppHandle,
+    state: &AppState,
+) -> Result<JobOutcome, String> {
+    let prepared = PreparedJob::prepare(job_id, params).await?;
+    let result = run_plan(job_id, &prepared.plan, app, state).await;
+    prepared.finish(job_id, result)
+}
 
-    // Take stderr for monitoring
-    let stderr = child.stderr.take()
-        .ok_or_else(|| "Failed to capture ffmpeg stderr".to_string())?;
+/// Runs each step in turn, stopping at the first one that does not complete.
+async fn run_plan(
+    job_id: Uuid,
+    plan: &JobPlan,
+    app: &AppHandle,
+    state: &AppState,
+) -> Result<JobOutcome, String> {
+    let total = plan.total_duration();
+    let mut offset = 0.0;
+    let mut outcome = JobOutcome::failed("Job has no ffmpeg steps".to_string());
+
+    for (index, step) in plan.steps.iter().enumerate() {
+        if state.cancelled_jobs.lock().await.contains_key(&job_id) {
+            outcome = JobOutcome::cancelled(CancelMode::Abort, Vec::new());
+            break;
+        }
 
-    // Store child process in state
-    {
-        let mut jobs = state.active_jobs.lock().await;
-        jobs.insert(
-            job_id,
-            ProcessJob {
-                child,
-                job_id,
-            },
+        log::info!(
+            "Starting ffmpeg step {}/{} with args: {:?}",
+            index + 1,
+            plan.steps.len(),
+            step.args
         );
+
+        let (stdout, stderr) = spawn_ffmpeg(job_id, &step.args, state).await?;
+        outcome = monitor_ffmpeg_progress(stdout, stderr, job_id, offset, total, app.clone(), state.clone()).await;
+
+        // ffmpeg exits normally after `q`, but only the last step writes a
+        // file worth keeping
+        let cancel_mode = state.cancelled_jobs.lock().await.get(&job_id).copied();
+        if let Some(mode) = cancel_mode {
+            let finished_early = mode == CancelMode::FinishEarly
+                && outcome.status == JobStatus::Completed
+                && index + 1 == plan.steps.len();
+            let mode = if finished_early { CancelMode::FinishEarly } else { CancelMode::Abort };
+            outcome = JobOutcome::cancelled(mode, outcome.stderr_tail);
+            break;
+        }
+
+        if outcome.status != JobStatus::Completed {
+            break;
+        }
+        offset += step.duration;
     }
 
-    // Calculate total duration for progress percentage
-    let duration = params.end_time.unwrap_or(0.0) - params.start_time.unwrap_or(0.0);
+    Ok(outcome)
+}
 
-    // Spawn task to monitor ffmpeg progress
-    let app_clone = app.clone();
-    let state_clone = state.inner().clone();
-    tokio::spawn(async move {
-        monitor_ffmpeg_progress(stderr, job_id, duration, app_clone, state_clone).await;
-    });
+/// Spawns ffmpeg, registers the child under `job_id` and returns its stdout,
+/// which carries `-progress` reports, and its stderr.
+async fn spawn_ffmpeg(
+    job_id: Uuid,
+    args: &[String],
+    state: &AppState,
+) -> Result<(tokio::process::ChildStdout, tokio::process::ChildStderr), String> {
+    let runner::SpawnedStep {
+        mut child,
+        stdout,
+        stderr,
+    } = runner::spawn_step(args)?;
+
+    // A cancel that raced with the spawn is honoured straight away
+    if state.cancelled_jobs.lock().await.contains_key(&job_id) {
+        let _ = child.kill().await;
+        return Ok((stdout, stderr));
+    }
+
+    let mut clock = ActiveClock::start(Instant::now());
 
-    Ok(job_id.to_string())
+    // A job paused between steps stays paused. The pause set stays locked
+    // until the child is registered, so a pause or resume arriving meanwhile
+    // waits and then finds the process.
+    let paused_jobs = state.paused_jobs.lock().await;
+    if paused_jobs.contains(&job_id) {
+        if let Err(e) = runner::set_stopped(&child, true) {
+            let _ = child.kill().await;
+            return Err(e);
+        }
+        clock.pause(Instant::now());
+    }
+
+    // Store child process in state
+    state.active_jobs.lock().await.insert(
+        job_id,
+        ProcessJob {
+            child,
+            job_id,
+            clock,
+        },
+    );
+    drop(paused_jobs);
+
+    Ok((stdout, stderr))
 }
 
+/// Reads `-progress` blocks from ffmpeg's stdout, emitting progress, and
+/// keeps the tail of stderr for diagnostics, then reaps the process. `offset`
+/// is the media time already covered by earlier steps of the job and `total`
+/// the duration of the whole job.
 async fn monitor_ffmpeg_progress(
+    stdout: impl tokio::io::AsyncRead + Unpin,
     stderr: impl tokio::io::AsyncRead + Unpin,
     job_id: Uuid,
-    duration: f64,
+    offset: f64,
+    total: f64,
     app: AppHandle,
     state: AppState,
+) -> JobOutcome {
+    let (_, stderr_tail) = tokio::join!(
+        read_progress(stdout, job_id, offset, total, &app, &state),
+        runner::collect_stderr_tail(stderr, job_id)
+    );
+
+    // A missing entry means cancel_process already removed and killed the job
+    let Some(mut job) = state.active_jobs.lock().await.remove(&job_id) else {
+        return JobOutcome::cancelled(CancelMode::Abort, stderr_tail);
+    };
+
+    // Wait for process to complete without blocking other jobs
+    runner::exit_outcome(job.child.wait().await, stderr_tail)
+}
+
+async fn read_progress(
+    stdout: impl tokio::io::AsyncRead + Unpin,
+    job_id: Uuid,
+    offset: f64,
+    total: f64,
+    app: &AppHandle,
+    state: &AppState,
 ) {
-    let mut reader = BufReader::new(stderr);
-    let mut buf = [0u8; 2048];
-    let mut pending = String::new();
-    let mut stderr_tail: VecDeque<String> = VecDeque::with_capacity(50);
+    let mut reader = ProgressReader::new(stdout);
 
-    while let Ok(bytes_read) = reader.read(&mut buf).await {
-        if bytes_read == 0 {
-            break;
+    while let Some(mut progress) = reader.next().await {
+        // Keep time spent paused out of the speed and ETA
+        let active = state.active_jobs.lock().await.get(&job_id).map(|job| job.clock.active(Instant::now()));
+        if let Some(active) = active {
+            progress = progress.with_active_time(active);
         }
 
-        pending.push_str(&String::from_utf8_lossy(&buf[..bytes_read]));
-
-        loop {
-            if let Some(pos) = pending.find(['\r', '\n']) {
-                let (segment, rest) = pending.split_at(pos);
-                let trimmed = segment.trim();
-                if !trimmed.is_empty() {
-                    log::debug!("ffmpeg stderr [{}]: {}", job_id, trimmed);
-
-                    if stderr_tail.len() == 50 {
-                        stderr_tail.pop_front();
-                    }
-                    stderr_tail.push_back(trimmed.to_string());
-
-                    if let Some(current_seconds) = parse_ffmpeg_time(trimmed) {
-                        let percent = calculate_progress_percentage(current_seconds, duration);
-
-                        let _ = app.emit(
-                            "ffmpeg-progress",
-                            ProgressPayload {
-                                job_id: job_id.to_string(),
-                                seconds: current_seconds,
-                                percent,
-                            },
-                        );
-                        log::info!(
-                            "Emitted ffmpeg-progress for job {}: seconds={}, percent={}",
-                            job_id,
-                            current_seconds,
-                            percent
-                        );
-                    }
-                }
-
-                // Drop the delimiter(s) and continue parsing the remainder
-                let mut rest_iter = rest.chars();
-                let _first = rest_iter.next();
-                let remaining_rest: String = rest_iter.collect();
-                let mut rest_clean = remaining_rest;
-                // Remove an additional delimiter if present (handles \r\n)
-                if rest_clean.starts_with('\n') || rest_clean.starts_with('\r') {
-                    rest_clean = rest_clean[1..].to_string();
-                }
-                pending = rest_clean;
-            } else {
-                break;
-            }
-        }
-    }
-
-    // Wait for process to complete
-    let mut jobs = state.active_jobs.lock().await;
-
-    if let Some(mut job) = jobs.remove(&job_id) {
-        match job.child.wait().await {
-            Ok(status) => {
-                if status.success() {
-                    let _ = app.emit(
-                        "ffmpeg-complete",
-                        CompletePayload {
-                            job_id: job_id.to_string(),
-                        },
-                    );
-                } else {
-                    let stderr_text = if stderr_tail.is_empty() {
-                        "No stderr captured".to_string()
-                    } else {
-                        stderr_tail.iter().cloned().collect::<Vec<_>>().join("\n")
-                    };
-
-                    let _ = app.emit(
-                        "ffmpeg-error",
-                        ErrorPayload {
-                            job_id: job_id.to_string(),
-                            error: format!(
-                                "FFmpeg exited with code {:?}. Stderr:\n{}",
-                                status.code(),
-                                stderr_text
-                            ),
-                        },
-                    );
-                }
-            }
-            Err(e) => {
-                let _ = app.emit(
-                    "ffmpeg-error",
-                    ErrorPayload {
-                        job_id: job_id.to_string(),
-                        error: format!("Process error: {}", e),
-                    },
-                );
-            }
-        }
+        let payload = ProgressPayload::from_progress(job_id, &progress, offset, total);
+        log::info!(
+            "Emitted ffmpeg-progress for job {}: seconds={}, percent={}",
+            job_id,
+            payload.seconds,
+            payload.percent
+        );
+        let _ = app.emit("ffmpeg-progress", payload);
     }
 }
 
+/// Cancels a queued or running job. `abort` (the default) kills ffmpeg and
+/// discards the output; `finish_early` lets ffmpeg finalize a playable file
+/// from what it has encoded so far. Running jobs announce the result with
+/// `ffmpeg-cancelled` once they have stopped.
 #[tauri::command]
-pub async fn cancel_process(job_id: String, app: AppHandle, state: State<'_, AppState>) -> Result<(), String> {
+pub async fn cancel_process(
+    job_id: String,
+    mode: Option<CancelMode>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
     let uuid = Uuid::parse_str(&job_id).map_err(|_| "Invalid job ID".to_string())?;
+    let mode = mode.unwrap_or_default();
 
-    let mut jobs = state.active_jobs.lock().await;
-
-    if let Some(mut job) = jobs.remove(&uuid) {
-        job.child
-            .kill()
-            .await
-            .map_err(|e| format!("Failed to kill process: {}", e))?;
+    // Jobs still waiting in the queue are simply dropped
+    if state.queue.lock().await.remove(uuid).is_some() {
+        state.queue_notify.notify_one();
 
-        // Emit cancelled event
         let _ = app.emit(
             "ffmpeg-cancelled",
-            CompletePayload {
+            CancelledPayload {
                 job_id: job_id.to_string(),
+                mode: CancelMode::Abort,
+                output_file: None,
             },
         );
 
-        Ok(())
-    } else {
-        Err("Job not found".to_string())
+        return Ok(());
     }
-}
 
-fn validate_inputs(params: &ProcessVideoParams) -> Result<(), String> {
-    if !Path::new(&params.input_file).exists() {
-        return Err("Input file does not exist".to_string());
+    if !state.queue.lock().await.is_running(uuid) {
+        return Err("Job not found".to_string());
     }
 
-    // Validate output extension
-    let valid_exts = ["mp4", "avi", "mov", "mkv", "webm"];
-    let output_ext = Path::new(&params.output_file)
-        .extension()
-        .and_then(|s| s.to_str())
-        .unwrap_or("");
-
-    if !valid_exts.contains(&output_ext) {
-        return Err(format!(
-            "Invalid output extension: {}. Supported formats: mp4, avi, mov, mkv, webm",
-            output_ext
-        ));
-    }
+    // Flag the job first so later steps of a multi-step job never start
+    state.cancelled_jobs.lock().await.insert(uuid, mode);
 
-    if let Some(ref sub_file) = params.subtitle_file {
-        if !Path::new(sub_file).exists() {
-            return Err("Subtitle file does not exist".to_string());
+    // A stopped ffmpeg cannot read `q`
+    if state.paused_jobs.lock().await.remove(&uuid) {
+        if let Some(job) = state.active_jobs.lock().await.get_mut(&uuid) {
+            runner::set_stopped(&job.child, false)?;
+            job.clock.resume(Instant::now());
         }
     }
 
-    Ok(())
-}
-
-fn build_ffmpeg_args(params: &ProcessVideoParams) -> Result<Vec<String>, String> {
-    let mut args = vec!["-i".to_string(), params.input_file.clone()];
-
-    if let (Some(start), Some(end)) = (params.start_time, params.end_time) {
-        args.push("-ss".to_string());
-        args.push(start.to_string());
-        args.push("-to".to_string());
-        args.push(end.to_string());
-    }
-
-    if let Some(ref subtitle_file) = params.subtitle_file {
-        // Escape path for ffmpeg filter (handle Windows paths, drive-letter colons, and quotes).
-        let escaped = escape_subtitle_path(subtitle_file);
-        args.push("-vf".to_string());
-        args.push(format!("subtitles=filename='{}'", escaped));
+    if mode == CancelMode::FinishEarly && request_finish_early(uuid, state.inner()).await {
+        return Ok(());
     }
 
-    args.push("-c:v".to_string());
-    args.push("libx264".to_string());
-    args.push("-c:a".to_string());
-    args.push("aac".to_string());
-    args.push("-y".to_string()); // Overwrite output file if exists
-    args.push(params.output_file.clone());
-
-    Ok(args)
-}
-
-// FFmpeg's filter syntax treats ':' as an option separator and '\' as an escape character.
-// To support Windows drive letters (e.g. C:\) and paths with spaces/quotes, we normalise
-// the path for the subtitles filter:
-//   - Replace backslashes with forward slashes so we don't need to double-escape them.
-//   - Escape drive-letter colons so they aren't interpreted as option separators.
-//   - Escape single quotes because the value is wrapped in single quotes.
-fn escape_subtitle_path(path: &str) -> String {
-    let mut escaped = path.replace('\\', "/");
-    escaped = escaped.replace(':', r"\:");
-    escaped.replace('\'', r"\'")
+    kill_job(uuid, state.inner()).await
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use tempfile::NamedTempFile;
-    use std::io::Write;
-
-    #[test]
-    fn test_parse_ffmpeg_time_standard_format() {
-        let line = "frame=  123 fps= 30 q=28.0 size=    1024kB time=00:01:30.50 bitrate= 139.2kbits/s";
-        let time = parse_ffmpeg_time(line).unwrap();
-        assert!((time - 90.5).abs() < 0.001);
-    }
-
-    #[test]
-    fn test_parse_ffmpeg_time_with_hours() {
-        let line = "time=01:30:45.25";
-        let time = parse_ffmpeg_time(line).unwrap();
-        assert!((time - 5445.25).abs() < 0.001);
+async fn kill_job(job_id: Uuid, state: &AppState) -> Result<(), String> {
+    if let Some(mut job) = state.active_jobs.lock().await.remove(&job_id) {
+        log::info!("Cancelling job {}", job.job_id);
+        job.child
+            .kill()
+            .await
+            .map_err(|e| format!("Failed to kill process: {}", e))?;
     }
+    Ok(())
+}
 
-    #[test]
-    fn test_parse_ffmpeg_time_without_decimal() {
-        let line = "time=00:00:30";
-        let time = parse_ffmpeg_time(line).unwrap();
-        assert_eq!(time, 30.0);
+/// Sends `q` to the job's ffmpeg and kills it if it has not exited after
+/// `FINISH_EARLY_TIMEOUT`. Returns false if ffmpeg could not be asked.
+async fn request_finish_early(job_id: Uuid, state: &AppState) -> bool {
+    let stdin = state
+        .active_jobs
+        .lock()
+        .await
+        .get_mut(&job_id)
+        .and_then(|job| job.child.stdin.take());
+
+    let Some(mut stdin) = stdin else {
+        return false;
+    };
+
+    if let Err(e) = stdin.write_all(b"q").await.and(stdin.flush().await) {
+        log::warn!("Could not ask job {} to finish early: {}", job_id, e);
+        return false;
     }
 
-    #[test]
-    fn test_parse_ffmpeg_time_zero_time() {
-        let line = "time=00:00:00.00";
-        let time = parse_ffmpeg_time(line).unwrap();
-        assert_eq!(time, 0.0);
-    }
+    log::info!("Asked job {} to finish early", job_id);
 
-    #[test]
-    fn test_parse_ffmpeg_time_invalid_format() {
-        let line = "invalid line without time";
-        assert!(parse_ffmpeg_time(line).is_none());
-    }
+    let state = state.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(FINISH_EARLY_TIMEOUT).await;
+        if state.active_jobs.lock().await.contains_key(&job_id) {
+            log::warn!("Job {} did not finish within {:?}; killing it", job_id, FINISH_EARLY_TIMEOUT);
+            if let Err(e) = kill_job(job_id, &state).await {
+                log::error!("{}", e);
+            }
+        }
+    });
 
-    #[test]
-    fn test_calculate_progress_percentage_normal() {
-        let percent = calculate_progress_percentage(30.0, 100.0);
-        assert_eq!(percent, 30.0);
-    }
+    true
+}
 
-    #[test]
-    fn test_calculate_progress_percentage_zero_duration() {
-        let percent = calculate_progress_percentage(50.0, 0.0);
-        assert_eq!(percent, 0.0);
-    }
+/// Suspends a running job's ffmpeg process until `resume_process`.
+#[tauri::command]
+pub async fn pause_process(job_id: String, app: AppHandle, state: State<'_, AppState>) -> Result<(), String> {
+    set_job_paused(&job_id, true, &app, state.inner()).await
+}
 
-    #[test]
-    fn test_calculate_progress_percentage_exceeds_100() {
-        let percent = calculate_progress_percentage(120.0, 100.0);
-        assert_eq!(percent, 100.0);
-    }
+#[tauri::command]
+pub async fn resume_process(job_id: String, app: AppHandle, state: State<'_, AppState>) -> Result<(), String> {
+    set_job_paused(&job_id, false, &app, state.inner()).await
+}
 
-    #[test]
-    fn test_calculate_progress_percentage_at_duration() {
-        let percent = calculate_progress_percentage(100.0, 100.0);
-        assert_eq!(percent, 100.0);
-    }
+async fn set_job_paused(job_id: &str, paused: bool, app: &AppHandle, state: &AppState) -> Result<(), String> {
+    let uuid = Uuid::parse_str(job_id).map_err(|_| "Invalid job ID".to_string())?;
+    runner::check_pause_supported()?;
 
-    #[test]
-    fn test_validate_inputs_with_nonexistent_input_file() {
-        let params = ProcessVideoParams {
-            input_file: "/nonexistent/path.mp4".to_string(),
-            output_file: "/output/file.mp4".to_string(),
-            start_time: None,
-            end_time: None,
-            subtitle_file: None,
-        };
-
-        let result = validate_inputs(&params);
-        assert!(result.is_err());
-        assert_eq!(result.unwrap_err(), "Input file does not exist");
+    if !state.queue.lock().await.is_running(uuid) {
+        return Err("Job is not running".to_string());
     }
 
-    #[test]
-    fn test_validate_inputs_with_invalid_output_extension() {
-        let mut input = NamedTempFile::new().unwrap();
-        writeln!(input, "test data").unwrap();
-
-        let params = ProcessVideoParams {
-            input_file: input.path().to_str().unwrap().to_string(),
-            output_file: "/output/file.txt".to_string(),
-            start_time: None,
-            end_time: None,
-            subtitle_file: None,
-        };
-
-        let result = validate_inputs(&params);
-        assert!(result.is_err());
-        assert!(result.unwrap_err().contains("Invalid output extension"));
+    let mut paused_jobs = state.paused_jobs.lock().await;
+    if paused_jobs.contains(&uuid) == paused {
+        return Err(if paused { "Job is already paused" } else { "Job is not paused" }.to_string());
     }
 
-    #[test]
-    fn test_validate_inputs_with_valid_extensions() {
-        let mut input = NamedTempFile::new().unwrap();
-        writeln!(input, "test data").unwrap();
-
-        let extensions = ["mp4", "avi", "mov", "mkv", "webm"];
-
-        for ext in extensions {
-            let params = ProcessVideoParams {
-                input_file: input.path().to_str().unwrap().to_string(),
-                output_file: format!("/output/file.{}", ext),
-                start_time: None,
-                end_time: None,
-                subtitle_file: None,
-            };
-
-            assert!(validate_inputs(&params).is_ok());
+    // Between steps there is no process; the next step starts stopped
+    if let Some(job) = state.active_jobs.lock().await.get_mut(&uuid) {
+        runner::set_stopped(&job.child, paused)?;
+        if paused {
+            job.clock.pause(Instant::now());
+        } else {
+            job.clock.resume(Instant::now());
         }
     }
 
-    #[test]
-    fn test_validate_inputs_with_missing_subtitle_file() {
-        let mut input = NamedTempFile::new().unwrap();
-        writeln!(input, "test data").unwrap();
-
-        let params = ProcessVideoParams {
-            input_file: input.path().to_str().unwrap().to_string(),
-            output_file: "/output/file.mp4".to_string(),
-            start_time: None,
-            end_time: None,
-            subtitle_file: Some("/nonexistent/subtitle.srt".to_string()),
-        };
-
-        let result = validate_inputs(&params);
-        assert!(result.is_err());
-        assert_eq!(result.unwrap_err(), "Subtitle file does not exist");
-    }
-
-    #[test]
-    fn test_build_ffmpeg_args_basic() {
-        let params = ProcessVideoParams {
-            input_file: "/input/video.mp4".to_string(),
-            output_file: "/output/video.mp4".to_string(),
-            start_time: None,
-            end_time: None,
-            subtitle_file: None,
-        };
-
-        let args = build_ffmpeg_args(&params).unwrap();
-
-        assert_eq!(args[0], "-i");
-        assert_eq!(args[1], "/input/video.mp4");
-        assert!(args.contains(&"-c:v".to_string()));
-        assert!(args.contains(&"libx264".to_string()));
-        assert!(args.contains(&"-c:a".to_string()));
-        assert!(args.contains(&"aac".to_string()));
-        assert!(args.contains(&"-y".to_string()));
-        assert_eq!(args.last().unwrap(), "/output/video.mp4");
-    }
-
-    #[test]
-    fn test_build_ffmpeg_args_with_trim() {
-        let params = ProcessVideoParams {
-            input_file: "/input/video.mp4".to_string(),
-            output_file: "/output/video.mp4".to_string(),
-            start_time: Some(10.5),
-            end_time: Some(60.0),
-            subtitle_file: None,
-        };
-
-        let args = build_ffmpeg_args(&params).unwrap();
-
-        let ss_idx = args.iter().position(|x| x == "-ss").unwrap();
-        let to_idx = args.iter().position(|x| x == "-to").unwrap();
-
-        assert_eq!(args[ss_idx + 1], "10.5");
-        assert_eq!(args[to_idx + 1], "60");
+    if paused {
+        paused_jobs.insert(uuid);
+    } else {
+        paused_jobs.remove(&uuid);
     }
+    drop(paused_jobs);
 
-    #[test]
-    fn test_build_ffmpeg_args_with_windows_path_escaping() {
-        let params = ProcessVideoParams {
-            input_file: "/input/video.mp4".to_string(),
-            output_file: "/output/video.mp4".to_string(),
-            start_time: None,
-            end_time: None,
-            subtitle_file: Some("C:\\Users\\Name\\subtitles.srt".to_string()),
-        };
-
-        let args = build_ffmpeg_args(&params).unwrap();
+    log::info!("{} job {}", if paused { "Paused" } else { "Resumed" }, uuid);
+    let _ = app.emit(
+        if paused { "ffmpeg-paused" } else { "ffmpeg-resumed" },
+        CompletePayload {
+            job_id: job_id.to_string(),
+        },
+    );
 
-        let vf_idx = args.iter().position(|x| x == "-vf").unwrap();
-        let filter = &args[vf_idx + 1];
-
-        // Should escape drive-letter colon and wrap as filename=
-        assert!(filter.starts_with("subtitles=filename='"));
-        assert!(filter.contains("C\\:/Users/Name/subtitles.srt"));
-    }
-
-    #[test]
-    fn test_build_ffmpeg_args_with_subtitles() {
-        let params = ProcessVideoParams {
-            input_file: "/input/video.mp4".to_string(),
-            output_file: "/output/video.mp4".to_string(),
-            start_time: None,
-            end_time: None,
-            subtitle_file: Some("/path/to/subtitle.srt".to_string()),
-        };
-
-        let args = build_ffmpeg_args(&params).unwrap();
-
-        assert!(args.contains(&"-vf".to_string()));
-        let vf_idx = args.iter().position(|x| x == "-vf").unwrap();
-        let filter = &args[vf_idx + 1];
-        assert!(filter.starts_with("subtitles=filename='"));
-    }
+    Ok(())
+}
 
-    #[test]
-    fn test_build_ffmpeg_args_with_spaces_and_quotes() {
-        let params = ProcessVideoParams {
-            input_file: "/input/video.mp4".to_string(),
-            output_file: "/output/video.mp4".to_string(),
-            start_time: None,
-            end_time: None,
-            subtitle_file: Some("D:\\My Subs\\O'Connor\\show.srt".to_string()),
-        };
-
-        let args = build_ffmpeg_args(&params).unwrap();
-
-        let vf_idx = args.iter().position(|x| x == "-vf").unwrap();
-        let filter = &args[vf_idx + 1];
-        assert_eq!(
-            filter,
-            "subtitles=filename='D\\:/My Subs/O\\'Connor/show.srt'"
-        );
-    }
+/// Returns the encoder defaults for the container implied by `output_file`.
+#[tauri::command]
+pub async fn get_default_encoding(output_file: String) -> Result<EncodingProfile, String> {
+    Container::from_path(&output_file)
+        .map(Container::default_encoding)
+        .ok_or_else(|| format!("Unsupported output format: {}", output_file))
 }
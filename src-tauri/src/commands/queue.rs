@@ -0,0 +1,300 @@
+use crate::commands::history::{self, JobRecord};
+use crate::commands::naming::resolve_output;
+use crate::commands::{capabilities, presets, settings};
+use crate::commands::process::{self, CompletePayload};
+use crate::engine::job::{self, JobOutcome, JobStatus, ProcessVideoParams};
+use crate::engine::naming::render_output;
+use crate::engine::settings::Settings;
+use crate::engine::storage;
+use crate::state::{AppState, JobQueue, QueueSnapshot, QueuedJob};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Emitter, Manager, State};
+use uuid::Uuid;
+
+const QUEUE_FILE_NAME: &str = "queue.json";
+
+/// Fills in what the job leaves to the settings, swaps its preset for the
+/// preset's options and validates the result.
+pub async fn prepare_job(
+    mut params: ProcessVideoParams,
+    app: &AppHandle,
+    state: &AppState,
+) -> Result<ProcessVideoParams, String> {
+    let settings = state.settings.lock().await.clone();
+
+    if params.output_file.trim().is_empty() {
+        let extension = match params.preset {
+            Some(ref name) => Some(presets::find_preset(app, name)?.extension),
+            None => None,
+        };
+        params.output_file = settings.default_output(&params.input_file, extension)?;
+        params.output_template = true;
+    }
+
+    let params = presets::resolve_preset(app, settings.apply_default_encoding(params))?;
+    job::validate_inputs(&params)?;
+    Ok(params)
+}
+
+/// Validates `params`, settles the output name and appends a job to the
+/// queue. Returns the job ID. Jobs skipped by their collision policy are
+/// recorded in the history without running.
+pub async fn queue_job(
+    params: ProcessVideoParams,
+    app: &AppHandle,
+    state: &AppState,
+) -> Result<String, String> {
+    queue_job_with_archive(params, None, app, state).await
+}
+
+/// `queue_job` for a job whose input is moved into `archive_dir` once it
+/// completes. Skipped jobs leave their input in place.
+pub async fn queue_job_with_archive(
+    params: ProcessVideoParams,
+    archive_dir: Option<String>,
+    app: &AppHandle,
+    state: &AppState,
+) -> Result<String, String> {
+    let mut params = prepare_job(params, app, state).await?;
+
+    // Reject settings the local ffmpeg build cannot handle before queueing
+    match capabilities::capabilities(state, false).await {
+        Ok(capabilities) => capabilities.check_params(&params)?,
+        Err(e) => log::warn!("Skipping ffmpeg capability check: {}", e),
+    }
+
+    let rendered = render_output(&params).await?;
+    // The queued job and its history record hold the final path, so a rerun
+    // does not render it again
+    params.output_template = false;
+
+    let job_id = Uuid::new_v4();
+    {
+        // Resolve under the lock so two jobs queued together cannot claim the same name
+        let mut queue = state.queue.lock().await;
+        let Some(output_file) = resolve_output(&rendered, params.collision_policy, &queue)? else {
+            drop(queue);
+            record_skipped(app, job_id, params, &rendered);
+            return Ok(job_id.to_string());
+        };
+
+        params.output_file = output_file.to_string_lossy().to_string();
+        queue.enqueue(QueuedJob {
+            job_id,
+            params,
+            held: false,
+            archive_dir,
+        });
+        save_queue(app, &queue);
+    }
+
+    log::info!("Queued job {}", job_id);
+    state.queue_notify.notify_one();
+
+    Ok(job_id.to_string())
+}
+
+fn record_skipped(app: &AppHandle, job_id: Uuid, params: ProcessVideoParams, output_file: &Path) {
+    log::info!("Skipping job {}: {} already exists", job_id, output_file.display());
+
+    let mut record = JobRecord::started(job_id, params);
+    record.params.output_file = output_file.to_string_lossy().to_string();
+    record.finish(JobOutcome {
+        status: JobStatus::Skipped,
+        exit_code: None,
+        error: Some(format!("Output file already exists: {}", output_file.display())),
+        stderr_tail: Vec::new(),
+        cancel_mode: None,
+        output_file: None,
+    });
+    history::save_record(app, &record);
+
+    let _ = app.emit(
+        "job-skipped",
+        CompletePayload {
+            job_id: job_id.to_string(),
+        },
+    );
+}
+
+/// Long-running task that starts queued jobs whenever a slot frees up or the
+/// queue changes. Spawned once from `lib.rs` during setup.
+pub async fn run_dispatcher(app: AppHandle) {
+    let state = app.state::<AppState>().inner().clone();
+
+    restore_queue(&app, &state).await;
+
+    let mut was_idle = true;
+    loop {
+        let snapshot = {
+            let mut queue = state.queue.lock().await;
+            while let Some(job) = queue.next_ready() {
+                log::info!("Starting queued job {}", job.job_id);
+                let _ = app.emit(
+                    "queue-job-started",
+                    CompletePayload {
+                        job_id: job.job_id.to_string(),
+                    },
+                );
+                tokio::spawn(process::run_job(job, app.clone(), state.clone()));
+            }
+            save_queue(&app, &queue);
+
+            let idle = queue.is_idle();
+            if idle && !was_idle {
+                let _ = app.emit("queue-drained", ());
+            }
+            was_idle = idle;
+            queue.snapshot()
+        };
+
+        let _ = app.emit("queue-updated", snapshot);
+
+        state.queue_notify.notified().await;
+    }
+}
+
+#[tauri::command]
+pub async fn enqueue_job(
+    params: ProcessVideoParams,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    queue_job(params, &app, state.inner()).await
+}
+
+#[tauri::command]
+pub async fn get_queue(state: State<'_, AppState>) -> Result<QueueSnapshot, String> {
+    Ok(state.queue.lock().await.snapshot())
+}
+
+/// Saves the limit as the `max_concurrent_jobs` setting, which applies it to
+/// the queue.
+#[tauri::command]
+pub async fn set_queue_concurrency(
+    max_concurrent: usize,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let current = state.settings.lock().await.clone();
+    let new = Settings {
+        max_concurrent_jobs: max_concurrent,
+        ..current
+    };
+    settings::save_settings(&app, state.inner(), new).await?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn move_queued_job(
+    job_id: String,
+    position: usize,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let uuid = parse_job_id(&job_id)?;
+    state.queue.lock().await.move_to(uuid, position)?;
+    state.queue_notify.notify_one();
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn pause_queued_job(job_id: String, state: State<'_, AppState>) -> Result<(), String> {
+    let uuid = parse_job_id(&job_id)?;
+    state.queue.lock().await.set_held(uuid, true)?;
+    state.queue_notify.notify_one();
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn resume_queued_job(job_id: String, state: State<'_, AppState>) -> Result<(), String> {
+    let uuid = parse_job_id(&job_id)?;
+    state.queue.lock().await.set_held(uuid, false)?;
+    state.queue_notify.notify_one();
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn remove_queued_job(job_id: String, state: State<'_, AppState>) -> Result<(), String> {
+    let uuid = parse_job_id(&job_id)?;
+    state
+        .queue
+        .lock()
+        .await
+        .remove(uuid)
+        .ok_or_else(|| "Job is not pending".to_string())?;
+    state.queue_notify.notify_one();
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn pause_queue(state: State<'_, AppState>) -> Result<(), String> {
+    state.queue.lock().await.set_paused(true);
+    state.queue_notify.notify_one();
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn resume_queue(state: State<'_, AppState>) -> Result<(), String> {
+    state.queue.lock().await.set_paused(false);
+    state.queue_notify.notify_one();
+    Ok(())
+}
+
+fn parse_job_id(job_id: &str) -> Result<Uuid, String> {
+    Uuid::parse_str(job_id).map_err(|_| "Invalid job ID".to_string())
+}
+
+fn queue_file_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+    Ok(data_dir.join(QUEUE_FILE_NAME))
+}
+
+/// Writes unfinished jobs to disk so they survive a restart.
+fn save_queue(app: &AppHandle, queue: &JobQueue) {
+    let result = queue_file_path(app).and_then(|path| {
+        storage::write_json_atomic(&path, &queue.unfinished())
+            .map_err(|e| format!("Failed to write queue file: {}", e))
+    });
+
+    if let Err(e) = result {
+        log::warn!("Could not persist job queue: {}", e);
+    }
+}
+
+/// Reloads jobs left over from a previous session. The queue starts paused so
+/// restored exports only run once the user resumes it.
+async fn restore_queue(app: &AppHandle, state: &AppState) {
+    let path = match queue_file_path(app) {
+        Ok(path) => path,
+        Err(e) => {
+            log::warn!("Could not locate job queue file: {}", e);
+            return;
+        }
+    };
+
+    let Ok(contents) = std::fs::read(&path) else {
+        return;
+    };
+
+    let jobs: Vec<QueuedJob> = match serde_json::from_slice(&contents) {
+        Ok(jobs) => jobs,
+        Err(e) => {
+            log::warn!("Ignoring unreadable job queue file {:?}: {}", path, e);
+            return;
+        }
+    };
+
+    if jobs.is_empty() {
+        return;
+    }
+
+    log::info!("Restored {} unfinished job(s) from previous session", jobs.len());
+    let mut queue = state.queue.lock().await;
+    for job in jobs {
+        queue.enqueue(job);
+    }
+    queue.set_paused(true);
+}
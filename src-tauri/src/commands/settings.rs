@@ -0,0 +1,88 @@
+use crate::commands::binaries::verify_binaries;
+use crate::engine::binaries;
+use crate::engine::settings::{self, Settings, SETTINGS_FILE_NAME};
+use crate::state::AppState;
+use std::path::PathBuf;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+fn settings_file_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let config_dir = app
+        .path()
+        .app_config_dir()
+        .map_err(|e| format!("Failed to get app config directory: {}", e))?;
+    Ok(config_dir.join(SETTINGS_FILE_NAME))
+}
+
+/// Pushes settings into the running app: log level, queue concurrency and,
+/// when they changed, the ffmpeg binaries.
+async fn apply_settings(state: &AppState, settings: &Settings, binaries_changed: bool) {
+    log::set_max_level(settings.log_level.to_filter());
+
+    if let Err(e) = state.queue.lock().await.set_max_concurrent(settings.max_concurrent_jobs) {
+        log::warn!("Ignoring concurrency setting: {}", e);
+    }
+    state.queue_notify.notify_one();
+
+    if binaries_changed {
+        binaries::install(binaries::resolve(&settings.binaries));
+        // Capabilities belong to the previous binary
+        *state.capabilities.lock().await = None;
+    }
+}
+
+/// Called once at startup, before any job runs. Unreadable settings are
+/// logged and replaced by defaults in memory; the file is left untouched.
+pub async fn load_settings(app: &AppHandle) {
+    let state = app.state::<AppState>().inner().clone();
+
+    let loaded = match settings_file_path(app).and_then(|path| settings::read_settings(&path)) {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            log::warn!("Could not load settings, using defaults: {}", e);
+            Settings::default()
+        }
+    };
+
+    apply_settings(&state, &loaded, true).await;
+    *state.settings.lock().await = loaded;
+}
+
+/// Validates, persists and applies new settings, then emits `settings-changed`.
+/// Binary paths must already have been verified by the caller.
+pub async fn save_settings(app: &AppHandle, state: &AppState, new: Settings) -> Result<Settings, String> {
+    new.validate()?;
+
+    let new = Settings {
+        schema_version: settings::CURRENT_SCHEMA_VERSION,
+        ..new
+    };
+
+    let mut current = state.settings.lock().await;
+    settings::write_settings(&settings_file_path(app)?, &new)?;
+
+    let binaries_changed = current.binaries != new.binaries;
+    apply_settings(state, &new, binaries_changed).await;
+    *current = new.clone();
+    drop(current);
+
+    let _ = app.emit("settings-changed", new.clone());
+    Ok(new)
+}
+
+#[tauri::command]
+pub async fn get_settings(state: State<'_, AppState>) -> Result<Settings, String> {
+    Ok(state.settings.lock().await.clone())
+}
+
+#[tauri::command]
+pub async fn update_settings(
+    settings: Settings,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<Settings, String> {
+    if state.settings.lock().await.binaries != settings.binaries {
+        verify_binaries(&binaries::resolve(&settings.binaries)).await?;
+    }
+
+    save_settings(&app, state.inner(), settings).await
+}
@@ -1,15 +1,22 @@
-use serde::{Deserialize, Serialize};
+use crate::engine::binaries;
+use crate::engine::probe::{self, MediaInfo, TrimKeyframes};
 use std::path::Path;
-use tokio::process::Command;
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct ProbeFormat {
-    pub duration: String,
+/// Reports the keyframes around a trim range so the UI can show where a
+/// stream-copy cut will really land.
+#[tauri::command]
+pub async fn get_trim_keyframes(
+    file_path: String,
+    start_time: Option<f64>,
+    end_time: Option<f64>,
+) -> Result<TrimKeyframes, String> {
+    probe::trim_keyframes(&file_path, start_time, end_time).await
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct ProbeOutput {
-    pub format: ProbeFormat,
+#[tauri::command]
+pub async fn probe_media(file_path: String) -> Result<MediaInfo, String> {
+    log::info!("Probing media file: {}", file_path);
+    probe::probe_media_info(&file_path).await
 }
 
 #[tauri::command]
@@ -24,133 +31,17 @@ pub async fn get_duration(file_path: String) -> Result<f64, String> {
 
     log::debug!("File exists, spawning ffprobe...");
 
-    // Spawn ffprobe
-    let output = Command::new("ffprobe")
-        .args(&[
-            "-v", "quiet",
-            "-print_format", "json",
-            "-show_format",
-            &file_path,
-        ])
-        .output()
-        .await
-        .map_err(|e| {
-            log::error!("Failed to spawn ffprobe: {}", e);
-            format!("Failed to spawn ffprobe: {}. Make sure ffprobe is installed and in PATH.", e)
-        })?;
-
-    if !output.status.success() {
-        log::error!("ffprobe failed with exit code: {:?}", output.status.code());
-        return Err(format!("ffprobe failed with exit code: {:?}", output.status.code()));
-    }
-
-    log::debug!("ffprobe output: {}", String::from_utf8_lossy(&output.stdout));
-
-    // Parse JSON output
-    let probe_data: ProbeOutput = serde_json::from_slice(&output.stdout)
-        .map_err(|e| {
-            log::error!("Failed to parse ffprobe output: {}", e);
-            format!("Failed to parse ffprobe output: {}", e)
-        })?;
-
-    let duration = probe_data.format.duration.parse::<f64>()
-        .map_err(|e| {
-            log::error!("Failed to parse duration: {}", e);
-            format!("Failed to parse duration: {}", e)
-        })?;
-
-    log::info!("Successfully got duration: {} seconds", duration);
-    Ok(duration)
+    probe::probe_duration(&file_path).await
 }
 
 #[tauri::command]
 pub async fn check_ffmpeg_availability() -> Result<bool, String> {
-    let ffmpeg_check = Command::new("ffmpeg").arg("-version").output().await;
-    let ffprobe_check = Command::new("ffprobe").arg("-version").output().await;
+    let resolved = binaries::current();
+    let ffmpeg_check = binaries::query_version(&resolved.ffmpeg).await;
+    let ffprobe_check = binaries::query_version(&resolved.ffprobe).await;
 
     match (ffmpeg_check, ffprobe_check) {
-        (Ok(ff), Ok(fp)) if ff.status.success() && fp.status.success() => Ok(true),
+        (Ok(version), Ok(_)) => binaries::check_min_version(&version).map(|_| true),
         _ => Err("FFmpeg or FFprobe not found in PATH. Please install FFmpeg and ensure it's accessible from the command line.".to_string()),
     }
 }
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use serde_json::json;
-
-    #[test]
-    fn test_probe_output_parsing_valid() {
-        let json_str = json!({
-            "format": {
-                "duration": "123.45"
-            }
-        })
-        .to_string();
-
-        let probe_data: ProbeOutput = serde_json::from_str(&json_str).unwrap();
-        let duration = probe_data.format.duration.parse::<f64>().unwrap();
-
-        assert_eq!(duration, 123.45);
-    }
-
-    #[test]
-    fn test_probe_output_parsing_integer_duration() {
-        let json_str = json!({
-            "format": {
-                "duration": "60"
-            }
-        })
-        .to_string();
-
-        let probe_data: ProbeOutput = serde_json::from_str(&json_str).unwrap();
-        let duration = probe_data.format.duration.parse::<f64>().unwrap();
-
-        assert_eq!(duration, 60.0);
-    }
-
-    #[test]
-    fn test_probe_output_parsing_invalid_duration() {
-        let json_str = json!({
-            "format": {
-                "duration": "not_a_number"
-            }
-        })
-        .to_string();
-
-        let probe_data: ProbeOutput = serde_json::from_str(&json_str).unwrap();
-        let result = probe_data.format.duration.parse::<f64>();
-
-        assert!(result.is_err());
-    }
-
-    #[test]
-    fn test_probe_output_parsing_zero_duration() {
-        let json_str = json!({
-            "format": {
-                "duration": "0"
-            }
-        })
-        .to_string();
-
-        let probe_data: ProbeOutput = serde_json::from_str(&json_str).unwrap();
-        let duration = probe_data.format.duration.parse::<f64>().unwrap();
-
-        assert_eq!(duration, 0.0);
-    }
-
-    #[test]
-    fn test_probe_output_parsing_large_duration() {
-        let json_str = json!({
-            "format": {
-                "duration": "7200.5"
-            }
-        })
-        .to_string();
-
-        let probe_data: ProbeOutput = serde_json::from_str(&json_str).unwrap();
-        let duration = probe_data.format.duration.parse::<f64>().unwrap();
-
-        assert_eq!(duration, 7200.5);
-    }
-}
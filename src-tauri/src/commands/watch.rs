@@ -0,0 +1,243 @@
+use crate::commands::{presets, queue};
+use crate::engine::job::{JobStatus, ProcessVideoParams};
+use crate::engine::watch::{self, StabilityTracker, WatchRule, WATCH_RULES_FILE_NAME};
+use crate::state::{AppState, QueuedJob};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager, State};
+use uuid::Uuid;
+
+/// How often the watch folders are scanned.
+const WATCH_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WatchQueuedPayload {
+    pub rule_id: String,
+    pub input_file: String,
+    pub job_id: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WatchErrorPayload {
+    pub rule_id: String,
+    pub input_file: String,
+    pub error: String,
+}
+
+fn watch_rules_file_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let config_dir = app
+        .path()
+        .app_config_dir()
+        .map_err(|e| format!("Failed to get app config directory: {}", e))?;
+    Ok(config_dir.join(WATCH_RULES_FILE_NAME))
+}
+
+/// Called once at startup, before the watcher runs.
+pub async fn load_watch_rules(app: &AppHandle) {
+    let state = app.state::<AppState>().inner().clone();
+
+    match watch_rules_file_path(app).and_then(|path| watch::read_watch_rules(&path)) {
+        Ok(rules) => *state.watch_rules.lock().await = rules,
+        Err(e) => log::error!("Could not load watch rules: {}", e),
+    }
+}
+
+fn save_watch_rules(app: &AppHandle, rules: &[WatchRule]) -> Result<(), String> {
+    watch::write_watch_rules(&watch_rules_file_path(app)?, rules)
+}
+
+#[tauri::command]
+pub async fn list_watch_rules(state: State<'_, AppState>) -> Result<Vec<WatchRule>, String> {
+    Ok(state.watch_rules.lock().await.clone())
+}
+
+/// Creates a rule, or replaces the rule with the same ID. Files already in
+/// the watch folder when a rule starts watching it are left alone.
+#[tauri::command]
+pub async fn save_watch_rule(
+    rule: WatchRule,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<WatchRule, String> {
+    rule.validate()?;
+    presets::find_preset(&app, &rule.preset)?;
+    std::fs::create_dir_all(&rule.output_dir).map_err(|e| format!("Failed to create output folder: {}", e))?;
+
+    let mut rules = state.watch_rules.lock().await;
+    let existing = rules.iter().position(|existing| existing.id == rule.id);
+
+    let seen = match existing {
+        Some(index) if rules[index].input_dir == rule.input_dir => rules[index].seen.clone(),
+        _ => watch::list_files(Path::new(&rule.input_dir))?.into_iter().collect(),
+    };
+    let rule = WatchRule { seen, ..rule };
+
+    let mut updated = rules.clone();
+    match existing {
+        Some(index) => updated[index] = rule.clone(),
+        None => updated.push(rule.clone()),
+    }
+    save_watch_rules(&app, &updated)?;
+    *rules = updated;
+
+    log::info!("Watching {} with preset \"{}\"", rule.input_dir, rule.preset);
+    Ok(rule)
+}
+
+#[tauri::command]
+pub async fn delete_watch_rule(rule_id: String, app: AppHandle, state: State<'_, AppState>) -> Result<(), String> {
+    let uuid = Uuid::parse_str(&rule_id).map_err(|_| "Invalid rule ID".to_string())?;
+
+    let mut rules = state.watch_rules.lock().await;
+    let mut updated = rules.clone();
+    updated.retain(|rule| rule.id != uuid);
+    if updated.len() == rules.len() {
+        return Err("Watch rule not found".to_string());
+    }
+
+    save_watch_rules(&app, &updated)?;
+    *rules = updated;
+    Ok(())
+}
+
+/// Scans the enabled rules' folders every `WATCH_POLL_INTERVAL` and queues
+/// files once they have stopped changing. Runs for the lifetime of the app.
+pub async fn run_watcher(app: AppHandle) {
+    let state = app.state::<AppState>().inner().clone();
+    let mut tracker = StabilityTracker::default();
+    let mut interval = tokio::time::interval(WATCH_POLL_INTERVAL);
+
+    loop {
+        interval.tick().await;
+        scan(&app, &state, &mut tracker).await;
+    }
+}
+
+async fn scan(app: &AppHandle, state: &AppState, tracker: &mut StabilityTracker) {
+    let rules: Vec<WatchRule> = state
+        .watch_rules
+        .lock()
+        .await
+        .iter()
+        .filter(|rule| rule.enabled)
+        .cloned()
+        .collect();
+
+    let now = Instant::now();
+    let mut waiting = HashSet::new();
+
+    for rule in rules {
+        let files = match watch::list_files(Path::new(&rule.input_dir)) {
+            Ok(files) => files,
+            Err(e) => {
+                log::warn!("Skipping watch folder: {}", e);
+                continue;
+            }
+        };
+
+        // Names that left the folder can be reused by a new recording
+        if rule.seen.iter().any(|name| !files.contains(name)) {
+            update_rule(app, state, rule.id, |rule| rule.seen.retain(|name| files.contains(name))).await;
+        }
+
+        for name in files.iter().filter(|name| rule.wants(name)) {
+            let path = Path::new(&rule.input_dir).join(name);
+            let Ok(metadata) = std::fs::metadata(&path) else {
+                continue;
+            };
+
+            if tracker.observe(&path, metadata.len(), metadata.modified().ok(), now) {
+                tracker.forget(&path);
+                queue_file(app, state, &rule, name, &path).await;
+            } else {
+                waiting.insert(path);
+            }
+        }
+    }
+
+    tracker.retain(|path| waiting.contains(path));
+}
+
+/// Applies `change` to the stored rule and saves the rules.
+async fn update_rule(app: &AppHandle, state: &AppState, rule_id: Uuid, change: impl FnOnce(&mut WatchRule)) {
+    let mut rules = state.watch_rules.lock().await;
+    let Some(rule) = rules.iter_mut().find(|rule| rule.id == rule_id) else {
+        return;
+    };
+
+    change(rule);
+    if let Err(e) = save_watch_rules(app, &rules) {
+        log::error!("Could not save watch rules: {}", e);
+    }
+}
+
+/// Queues a stable file through the same path as `process_video`.
+async fn queue_file(app: &AppHandle, state: &AppState, rule: &WatchRule, name: &str, path: &Path) {
+    // Marked before queueing so a file that is rejected is not retried on
+    // every scan; renaming it makes it new again
+    update_rule(app, state, rule.id, |rule| {
+        rule.seen.insert(name.to_string());
+    })
+    .await;
+
+    let input_file = path.to_string_lossy().to_string();
+    let result = match presets::find_preset(app, &rule.preset) {
+        Ok(preset) => {
+            let params = ProcessVideoParams {
+                input_file: input_file.clone(),
+                output_file: rule.output_file(&preset.extension),
+                output_template: true,
+                preset: Some(rule.preset.clone()),
+                collision_policy: rule.collision_policy,
+                ..Default::default()
+            };
+            queue::queue_job_with_archive(params, rule.archive_dir.clone(), app, state).await
+        }
+        Err(e) => Err(e),
+    };
+
+    match result {
+        Ok(job_id) => {
+            log::info!("Watch folder queued {} as job {}", input_file, job_id);
+            let _ = app.emit(
+                "watch-job-queued",
+                WatchQueuedPayload {
+                    rule_id: rule.id.to_string(),
+                    input_file,
+                    job_id,
+                },
+            );
+        }
+        Err(error) => {
+            log::error!("Watch folder could not queue {}: {}", input_file, error);
+            let _ = app.emit(
+                "watch-error",
+                WatchErrorPayload {
+                    rule_id: rule.id.to_string(),
+                    input_file,
+                    error,
+                },
+            );
+        }
+    }
+}
+
+/// Moves the source of a watch-folder job into its archive folder once the
+/// export has succeeded. Other jobs are ignored.
+pub fn job_finished(job: &QueuedJob, status: JobStatus) {
+    let Some(ref archive_dir) = job.archive_dir else {
+        return;
+    };
+
+    if status != JobStatus::Completed {
+        return;
+    }
+
+    let source = Path::new(&job.params.input_file);
+    match watch::archive_source(source, Path::new(archive_dir)) {
+        Ok(archived) => log::info!("Archived {:?} to {:?}", source, archived),
+        Err(e) => log::error!("Could not archive {:?}: {}", source, e),
+    }
+}
@@ -0,0 +1,206 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use tokio::process::Command;
+
+/// Oldest ffmpeg release with everything the app relies on (`-progress`
+/// microsecond timestamps, `-avoid_negative_ts make_zero` with concat).
+pub const MIN_FFMPEG_VERSION: (u32, u32) = (4, 3);
+
+/// Places ffmpeg is commonly installed that may be missing from the PATH a
+/// GUI app inherits, e.g. Homebrew on macOS.
+#[cfg(not(windows))]
+const COMMON_LOCATIONS: &[&str] = &["/opt/homebrew/bin", "/usr/local/bin", "/usr/bin", "/snap/bin", "/opt/local/bin"];
+#[cfg(windows)]
+const COMMON_LOCATIONS: &[&str] = &["C:\\ffmpeg\\bin", "C:\\Program Files\\ffmpeg\\bin", "C:\\ProgramData\\chocolatey\\bin"];
+
+/// User-chosen binary locations. `None` means auto-discover.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BinaryPaths {
+    pub ffmpeg_path: Option<String>,
+    pub ffprobe_path: Option<String>,
+}
+
+/// The binaries every ffmpeg and ffprobe invocation uses.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ResolvedBinaries {
+    pub ffmpeg: PathBuf,
+    pub ffprobe: PathBuf,
+}
+
+impl Default for ResolvedBinaries {
+    fn default() -> Self {
+        Self {
+            ffmpeg: PathBuf::from("ffmpeg"),
+            ffprobe: PathBuf::from("ffprobe"),
+        }
+    }
+}
+
+static RESOLVED: RwLock<Option<ResolvedBinaries>> = RwLock::new(None);
+
+/// Makes `binaries` the ones used by `ffmpeg_command` and `ffprobe_command`.
+pub fn install(binaries: ResolvedBinaries) {
+    log::info!("Using ffmpeg at {:?} and ffprobe at {:?}", binaries.ffmpeg, binaries.ffprobe);
+    *RESOLVED.write().unwrap_or_else(|e| e.into_inner()) = Some(binaries);
+}
+
+pub fn current() -> ResolvedBinaries {
+    RESOLVED
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+        .unwrap_or_default()
+}
+
+pub fn ffmpeg_command() -> Command {
+    Command::new(current().ffmpeg)
+}
+
+pub fn ffprobe_command() -> Command {
+    Command::new(current().ffprobe)
+}
+
+fn executable_name(name: &str) -> String {
+    format!("{}{}", name, std::env::consts::EXE_SUFFIX)
+}
+
+/// Looks for `name` in each directory of `PATH`, then in `COMMON_LOCATIONS`.
+pub fn discover(name: &str) -> Option<PathBuf> {
+    let file_name = executable_name(name);
+    let path_dirs = std::env::var_os("PATH")
+        .map(|paths| std::env::split_paths(&paths).collect::<Vec<_>>())
+        .unwrap_or_default();
+
+    path_dirs
+        .into_iter()
+        .chain(COMMON_LOCATIONS.iter().map(PathBuf::from))
+        .map(|dir| dir.join(&file_name))
+        .find(|candidate| candidate.is_file())
+}
+
+/// ffprobe that ships next to `ffmpeg`, if any.
+fn sibling_ffprobe(ffmpeg: &Path) -> Option<PathBuf> {
+    let candidate = ffmpeg.parent()?.join(executable_name("ffprobe"));
+    candidate.is_file().then_some(candidate)
+}
+
+/// Picks the binaries to use: explicit paths first, then an ffprobe next to
+/// an explicit ffmpeg, then auto-discovery, then the bare names.
+pub fn resolve(paths: &BinaryPaths) -> ResolvedBinaries {
+    let explicit_ffmpeg = paths.ffmpeg_path.as_ref().map(PathBuf::from);
+
+    let ffmpeg = explicit_ffmpeg
+        .clone()
+        .or_else(|| discover("ffmpeg"))
+        .unwrap_or_else(|| PathBuf::from("ffmpeg"));
+
+    let ffprobe = paths
+        .ffprobe_path
+        .as_ref()
+        .map(PathBuf::from)
+        .or_else(|| explicit_ffmpeg.as_deref().and_then(sibling_ffprobe))
+        .or_else(|| discover("ffprobe"))
+        .unwrap_or_else(|| PathBuf::from("ffprobe"));
+
+    ResolvedBinaries { ffmpeg, ffprobe }
+}
+
+/// Parses the major and minor release from a version string such as `6.1.1`,
+/// `n6.0` or `4.4.2-0ubuntu0.22.04.1`. Git snapshots (`N-113000-g…`) carry no
+/// release number and yield `None`.
+pub fn parse_version_number(version: &str) -> Option<(u32, u32)> {
+    let version = version.strip_prefix('n').unwrap_or(version);
+    let mut parts = version.split(|c: char| !c.is_ascii_digit());
+    let major = parts.next()?.parse().ok()?;
+    let minor = parts.next().and_then(|minor| minor.parse().ok()).unwrap_or(0);
+    Some((major, minor))
+}
+
+/// Errors when `version` is a release older than `MIN_FFMPEG_VERSION`.
+/// Unrecognised versions, such as git snapshots, are accepted.
+pub fn check_min_version(version: &str) -> Result<(), String> {
+    match parse_version_number(version) {
+        Some(found) if found < MIN_FFMPEG_VERSION => Err(format!(
+            "ffmpeg {} is too old; version {}.{} or newer is required",
+            version, MIN_FFMPEG_VERSION.0, MIN_FFMPEG_VERSION.1
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Runs `<binary> -version` and returns the version string it reports.
+pub async fn query_version(binary: &Path) -> Result<String, String> {
+    let output = Command::new(binary)
+        .arg("-version")
+        .output()
+        .await
+        .map_err(|e| format!("Failed to run {}: {}", binary.display(), e))?;
+
+    if !output.status.success() {
+        return Err(format!("{} -version exited with {:?}", binary.display(), output.status.code()));
+    }
+
+    // Both tools start with e.g. "ffprobe version 6.1.1 Copyright ..."
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut words = stdout.lines().next().unwrap_or("").split_whitespace();
+    match (words.next(), words.next(), words.next()) {
+        (Some(tool), Some("version"), Some(version)) if tool.starts_with("ff") => Ok(version.to_string()),
+        _ => Err(format!("{} does not look like an ffmpeg binary", binary.display())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_parse_version_number() {
+        assert_eq!(parse_version_number("6.1.1"), Some((6, 1)));
+        assert_eq!(parse_version_number("n6.0"), Some((6, 0)));
+        assert_eq!(parse_version_number("4.4.2-0ubuntu0.22.04.1"), Some((4, 4)));
+        assert_eq!(parse_version_number("7"), Some((7, 0)));
+        assert_eq!(parse_version_number("N-113000-g1234abcd"), None);
+    }
+
+    #[test]
+    fn test_check_min_version() {
+        assert!(check_min_version("6.1.1").is_ok());
+        assert!(check_min_version("4.3").is_ok());
+        assert!(check_min_version("N-113000-g1234abcd").is_ok());
+        assert_eq!(
+            check_min_version("4.2.7").unwrap_err(),
+            "ffmpeg 4.2.7 is too old; version 4.3 or newer is required"
+        );
+    }
+
+    #[test]
+    fn test_resolve_prefers_explicit_paths_and_sibling_ffprobe() {
+        let dir = tempdir().unwrap();
+        let ffmpeg = dir.path().join(executable_name("ffmpeg"));
+        let ffprobe = dir.path().join(executable_name("ffprobe"));
+        std::fs::write(&ffmpeg, "").unwrap();
+        std::fs::write(&ffprobe, "").unwrap();
+
+        let resolved = resolve(&BinaryPaths {
+            ffmpeg_path: Some(ffmpeg.to_string_lossy().to_string()),
+            ffprobe_path: None,
+        });
+
+        assert_eq!(resolved.ffmpeg, ffmpeg);
+        assert_eq!(resolved.ffprobe, ffprobe);
+    }
+
+    #[test]
+    fn test_resolve_explicit_ffprobe_wins() {
+        let resolved = resolve(&BinaryPaths {
+            ffmpeg_path: Some("/opt/ffmpeg-6/ffmpeg".to_string()),
+            ffprobe_path: Some("/opt/ffmpeg-5/ffprobe".to_string()),
+        });
+
+        assert_eq!(resolved.ffmpeg, PathBuf::from("/opt/ffmpeg-6/ffmpeg"));
+        assert_eq!(resolved.ffprobe, PathBuf::from("/opt/ffmpeg-5/ffprobe"));
+    }
+}
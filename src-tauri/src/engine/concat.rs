@@ -0,0 +1,461 @@
+use crate::engine::container::Container;
+use crate::engine::encoding::{AudioCodec, AudioSettings, EncodingProfile, VideoCodec, VideoSettings};
+use crate::engine::job::{self, FfmpegStep, JobPlan, ProcessVideoParams};
+use crate::engine::probe::{self, MediaInfo};
+use uuid::Uuid;
+
+/// Used when the first input does not report a frame rate or sample rate.
+const FALLBACK_FRAME_RATE: f64 = 30.0;
+const FALLBACK_SAMPLE_RATE: u32 = 48000;
+
+/// A probed concat input.
+pub struct ConcatInput {
+    pub path: String,
+    pub info: MediaInfo,
+}
+
+impl ConcatInput {
+    fn duration(&self) -> f64 {
+        self.info.duration.unwrap_or(0.0)
+    }
+}
+
+/// All files of a concat job in order: `input_file` followed by `concat_inputs`.
+pub fn input_files(params: &ProcessVideoParams) -> Vec<String> {
+    std::iter::once(params.input_file.clone())
+        .chain(params.concat_inputs.iter().cloned())
+        .collect()
+}
+
+/// True when every input has the same video and audio layout as the first, so
+/// packets can be joined with the concat demuxer without re-encoding.
+pub fn streams_match(inputs: &[ConcatInput]) -> bool {
+    let Some(first) = inputs.first() else {
+        return true;
+    };
+
+    let video_key = |info: &MediaInfo| {
+        info.first_stream("video").map(|s| {
+            let fps = s.frame_rate.map(|fps| (fps * 1000.0).round() as i64);
+            (s.codec_name.clone(), s.width, s.height, s.pix_fmt.clone(), fps)
+        })
+    };
+    let audio_key = |info: &MediaInfo| {
+        info.first_stream("audio")
+            .map(|s| (s.codec_name.clone(), s.sample_rate, s.channels))
+    };
+
+    inputs.iter().all(|input| {
+        video_key(&input.info) == video_key(&first.info) && audio_key(&input.info) == audio_key(&first.info)
+    })
+}
+
+/// The source codecs of `info` as a stream-copy check for `Container::check_encoding`.
+/// `None` when a codec is one the app cannot name.
+fn source_profile(info: &MediaInfo) -> Option<EncodingProfile> {
+    let video = VideoCodec::from_source(info.first_stream("video")?.codec_name.as_deref()?)?;
+    let audio = match info.first_stream("audio") {
+        Some(stream) => AudioCodec::from_source(stream.codec_name.as_deref()?)?,
+        None => AudioCodec::None,
+    };
+
+    Some(EncodingProfile {
+        video: VideoSettings {
+            codec: video,
+            ..Default::default()
+        },
+        audio: AudioSettings {
+            codec: audio,
+            ..Default::default()
+        },
+    })
+}
+
+/// Encoding for joining inputs with matching streams: `None` copies them.
+/// Copying needs the source codecs to fit the output container; when they do
+/// not, jobs without explicit settings re-encode with the container defaults.
+pub fn demuxer_encoding(params: &ProcessVideoParams, inputs: &[ConcatInput]) -> Result<Option<EncodingProfile>, String> {
+    let copies = params.encoding.as_ref().map_or(true, |e| e.video.codec == VideoCodec::Copy);
+    if !copies {
+        return Ok(params.encoding.clone());
+    }
+
+    let container = Container::from_path(&params.output_file);
+    let fits = match (container, inputs.first()) {
+        (Some(Container::Mkv), _) => true,
+        (Some(container), Some(first)) => {
+            source_profile(&first.info).is_some_and(|profile| container.check_encoding(&profile).is_ok())
+        }
+        _ => false,
+    };
+
+    match (fits, &params.encoding) {
+        (true, _) => Ok(None),
+        (false, None) => Ok(Some(params.effective_encoding())),
+        (false, Some(_)) => Err(format!(
+            "The source codecs cannot be copied into {}; choose an encoder",
+            container.map_or("this format", Container::label)
+        )),
+    }
+}
+
+/// Joins inputs with matching streams through the concat demuxer. Streams are
+/// copied unless an encoding profile was chosen explicitly.
+pub fn build_concat_demuxer_args(params: &ProcessVideoParams, list_file: &str) -> Vec<String> {
+    let mut args = vec![
+        "-f".to_string(),
+        "concat".to_string(),
+        "-safe".to_string(),
+        "0".to_string(),
+        "-i".to_string(),
+        list_file.to_string(),
+        "-map".to_string(),
+        "0:v?".to_string(),
+        "-map".to_string(),
+        "0:a?".to_string(),
+    ];
+
+    match params.encoding {
+        Some(ref encoding) => args.extend(encoding.to_args()),
+        None => args.extend(["-c".to_string(), "copy".to_string()]),
+    }
+
+    args.push("-y".to_string());
+    args.push(params.output_file.clone());
+    args
+}
+
+/// Joins inputs with differing streams through the concat filter. Every input
+/// is scaled and padded to the first input's frame size and frame rate, audio
+/// is resampled to the first input's rate, and inputs without audio get silence.
+/// Filter outputs cannot be stream copied, so a copy encoding is rejected.
+pub fn build_concat_filter_args(params: &ProcessVideoParams, inputs: &[ConcatInput]) -> Result<Vec<String>, String> {
+    let first_video = inputs.first().and_then(|input| input.info.first_stream("video"));
+    let width = first_video.and_then(|s| s.width).unwrap_or(1920);
+    let height = first_video.and_then(|s| s.height).unwrap_or(1080);
+    let frame_rate = first_video.and_then(|s| s.frame_rate).unwrap_or(FALLBACK_FRAME_RATE);
+
+    let sample_rate = inputs
+        .iter()
+        .find_map(|input| input.info.first_stream("audio"))
+        .and_then(|s| s.sample_rate)
+        .unwrap_or(FALLBACK_SAMPLE_RATE);
+    let with_audio = inputs.iter().any(|input| input.info.first_stream("audio").is_some());
+
+    let encoding = params.effective_encoding();
+    if encoding.video.codec == VideoCodec::Copy || (with_audio && encoding.audio.codec == AudioCodec::Copy) {
+        return Err("These files have different formats and must be re-encoded to join them".to_string());
+    }
+
+    let mut graph = Vec::new();
+    let mut concat_inputs = String::new();
+
+    for (i, input) in inputs.iter().enumerate() {
+        graph.push(format!(
+            "[{i}:v:0]scale={w}:{h}:force_original_aspect_ratio=decrease,pad={w}:{h}:(ow-iw)/2:(oh-ih)/2,setsar=1,fps={fps},format=yuv420p[v{i}]",
+            i = i,
+            w = width,
+            h = height,
+            fps = frame_rate
+        ));
+        concat_inputs.push_str(&format!("[v{}]", i));
+
+        if !with_audio {
+            continue;
+        }
+
+        if input.info.first_stream("audio").is_some() {
+            graph.push(format!(
+                "[{}:a:0]aresample={},aformat=sample_fmts=fltp:channel_layouts=stereo[a{}]",
+                i, sample_rate, i
+            ));
+        } else {
+            graph.push(format!(
+                "anullsrc=channel_layout=stereo:sample_rate={},atrim=duration={}[a{}]",
+                sample_rate,
+                input.duration(),
+                i
+            ));
+        }
+        concat_inputs.push_str(&format!("[a{}]", i));
+    }
+
+    let audio_count = if with_audio { 1 } else { 0 };
+    let outputs = if with_audio { "[outv][outa]" } else { "[outv]" };
+    graph.push(format!(
+        "{}concat=n={}:v=1:a={}{}",
+        concat_inputs,
+        inputs.len(),
+        audio_count,
+        outputs
+    ));
+
+    let mut args = Vec::new();
+    for input in inputs {
+        args.push("-i".to_string());
+        args.push(input.path.clone());
+    }
+
+    args.extend([
+        "-filter_complex".to_string(),
+        graph.join(";"),
+        "-map".to_string(),
+        "[outv]".to_string(),
+    ]);
+    if with_audio {
+        args.push("-map".to_string());
+        args.push("[outa]".to_string());
+    }
+
+    args.extend(encoding.to_args());
+    args.push("-y".to_string());
+    args.push(params.output_file.clone());
+    Ok(args)
+}
+
+/// Probes every input, then joins them with the concat demuxer when their
+/// streams match or the concat filter when they do not.
+pub async fn plan_concat(job_id: Uuid, params: &ProcessVideoParams) -> Result<JobPlan, String> {
+    let mut inputs = Vec::new();
+    for path in input_files(params) {
+        let info = probe::probe_media_info(&path).await?;
+        if info.duration.is_none() {
+            return Err(format!("Could not determine the duration of {}", path));
+        }
+        if info.first_stream("video").is_none() {
+            return Err(format!("{} has no video stream", path));
+        }
+        inputs.push(ConcatInput { path, info });
+    }
+
+    let total: f64 = inputs.iter().map(ConcatInput::duration).sum();
+
+    if !streams_match(&inputs) {
+        log::info!("Concat job {}: inputs differ, using the concat filter", job_id);
+        return Ok(JobPlan {
+            steps: vec![FfmpegStep {
+                args: build_concat_filter_args(params, &inputs)?,
+                duration: total,
+            }],
+            work_dir: None,
+        });
+    }
+
+    let encoding = demuxer_encoding(params, &inputs)?;
+    if encoding.is_some() && params.encoding.is_none() {
+        log::info!("Concat job {}: source codecs do not fit the output, re-encoding", job_id);
+    }
+    let params = ProcessVideoParams {
+        encoding,
+        ..params.clone()
+    };
+
+    let work_dir = job::create_work_dir(job_id)?;
+    let list_file = work_dir.join("inputs.txt");
+    let list: String = inputs
+        .iter()
+        .map(|input| format!("{}\n", job::concat_list_entry(std::path::Path::new(&input.path))))
+        .collect();
+    std::fs::write(&list_file, list).map_err(|e| format!("Failed to write concat list: {}", e))?;
+
+    Ok(JobPlan {
+        steps: vec![FfmpegStep {
+            args: build_concat_demuxer_args(&params, &list_file.to_string_lossy()),
+            duration: total,
+        }],
+        work_dir: Some(work_dir),
+    })
+}
+
+/// Parameters for a job joining `input_files` in order into `output_file`.
+pub fn concat_params(
+    input_files: Vec<String>,
+    output_file: String,
+    encoding: Option<EncodingProfile>,
+) -> Result<ProcessVideoParams, String> {
+    let mut files = input_files.into_iter();
+    let input_file = files.next().unwrap_or_default();
+    let concat_inputs: Vec<String> = files.collect();
+
+    if concat_inputs.is_empty() {
+        return Err("Select at least two files to concatenate".to_string());
+    }
+
+    Ok(ProcessVideoParams {
+        input_file,
+        output_file,
+        encoding,
+        concat_inputs,
+        ..Default::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input(path: &str, video: &str, audio: Option<&str>) -> ConcatInput {
+        let audio = audio
+            .map(|audio| format!(",{}", audio))
+            .unwrap_or_default();
+        let json = format!(
+            r#"{{"format": {{"format_name": "mov,mp4", "duration": "10.0"}}, "streams": [{}{}]}}"#,
+            video, audio
+        );
+        ConcatInput {
+            path: path.to_string(),
+            info: probe::parse_media_info(json.as_bytes()).unwrap(),
+        }
+    }
+
+    const H264_1080: &str = r#"{"index": 0, "codec_type": "video", "codec_name": "h264", "width": 1920, "height": 1080, "pix_fmt": "yuv420p", "avg_frame_rate": "30/1"}"#;
+    const H264_720: &str = r#"{"index": 0, "codec_type": "video", "codec_name": "h264", "width": 1280, "height": 720, "pix_fmt": "yuv420p", "avg_frame_rate": "25/1"}"#;
+    const AAC_48K: &str = r#"{"index": 1, "codec_type": "audio", "codec_name": "aac", "sample_rate": "48000", "channels": 2}"#;
+
+    fn params() -> ProcessVideoParams {
+        ProcessVideoParams {
+            input_file: "/in/a.mp4".to_string(),
+            output_file: "/out/joined.mp4".to_string(),
+            concat_inputs: vec!["/in/b.mp4".to_string()],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_input_files_keeps_order() {
+        assert_eq!(input_files(&params()), vec!["/in/a.mp4", "/in/b.mp4"]);
+    }
+
+    #[test]
+    fn test_streams_match() {
+        let same = [
+            input("/in/a.mp4", H264_1080, Some(AAC_48K)),
+            input("/in/b.mp4", H264_1080, Some(AAC_48K)),
+        ];
+        assert!(streams_match(&same));
+
+        let different_size = [
+            input("/in/a.mp4", H264_1080, Some(AAC_48K)),
+            input("/in/b.mp4", H264_720, Some(AAC_48K)),
+        ];
+        assert!(!streams_match(&different_size));
+
+        let different_rate = [
+            input("/in/a.mp4", H264_1080, Some(AAC_48K)),
+            input("/in/b.mp4", &H264_1080.replace("30/1", "25/1"), Some(AAC_48K)),
+        ];
+        assert!(!streams_match(&different_rate));
+
+        let missing_audio = [
+            input("/in/a.mp4", H264_1080, Some(AAC_48K)),
+            input("/in/b.mp4", H264_1080, None),
+        ];
+        assert!(!streams_match(&missing_audio));
+    }
+
+    #[test]
+    fn test_demuxer_encoding_copies_only_into_fitting_containers() {
+        let inputs = [
+            input("/in/a.mp4", H264_1080, Some(AAC_48K)),
+            input("/in/b.mp4", H264_1080, Some(AAC_48K)),
+        ];
+        assert_eq!(demuxer_encoding(&params(), &inputs), Ok(None));
+
+        let mkv = ProcessVideoParams {
+            output_file: "/out/joined.mkv".to_string(),
+            ..params()
+        };
+        assert_eq!(demuxer_encoding(&mkv, &inputs), Ok(None));
+
+        // H.264/AAC cannot be copied into WebM, so the WebM defaults are used
+        let webm = ProcessVideoParams {
+            output_file: "/out/joined.webm".to_string(),
+            ..params()
+        };
+        let encoding = demuxer_encoding(&webm, &inputs).unwrap().unwrap();
+        assert_eq!(encoding.video.codec, VideoCodec::LibvpxVp9);
+        assert_eq!(encoding.audio.codec, AudioCodec::Libopus);
+
+        let explicit_copy = ProcessVideoParams {
+            encoding: Some(EncodingProfile {
+                video: VideoSettings {
+                    codec: VideoCodec::Copy,
+                    ..Default::default()
+                },
+                audio: AudioSettings {
+                    codec: AudioCodec::Copy,
+                    ..Default::default()
+                },
+            }),
+            ..webm
+        };
+        assert!(demuxer_encoding(&explicit_copy, &inputs).unwrap_err().contains("WebM"));
+    }
+
+    #[test]
+    fn test_demuxer_args_copy_by_default() {
+        let args = build_concat_demuxer_args(&params(), "/tmp/list.txt");
+        assert_eq!(args[0..6], ["-f", "concat", "-safe", "0", "-i", "/tmp/list.txt"]);
+        assert!(args.windows(2).any(|w| w == ["-c", "copy"]));
+        assert_eq!(args.last().unwrap(), "/out/joined.mp4");
+    }
+
+    #[test]
+    fn test_filter_args_normalize_to_first_input() {
+        let inputs = [
+            input("/in/a.mp4", H264_1080, Some(AAC_48K)),
+            input("/in/b.mp4", H264_720, None),
+        ];
+
+        let args = build_concat_filter_args(&params(), &inputs).unwrap();
+
+        assert_eq!(args[0..4], ["-i", "/in/a.mp4", "-i", "/in/b.mp4"]);
+        let graph_idx = args.iter().position(|x| x == "-filter_complex").unwrap();
+        let graph = &args[graph_idx + 1];
+        assert!(graph.contains("[1:v:0]scale=1920:1080:force_original_aspect_ratio=decrease"));
+        assert!(graph.contains("fps=30"));
+        assert!(graph.contains("anullsrc=channel_layout=stereo:sample_rate=48000,atrim=duration=10[a1]"));
+        assert!(graph.ends_with("[v0][a0][v1][a1]concat=n=2:v=1:a=1[outv][outa]"));
+        assert!(args.contains(&"libx264".to_string()));
+    }
+
+    #[test]
+    fn test_filter_args_without_any_audio() {
+        let inputs = [
+            input("/in/a.mp4", H264_1080, None),
+            input("/in/b.mp4", H264_720, None),
+        ];
+
+        let args = build_concat_filter_args(&params(), &inputs).unwrap();
+
+        let graph_idx = args.iter().position(|x| x == "-filter_complex").unwrap();
+        assert!(args[graph_idx + 1].ends_with("[v0][v1]concat=n=2:v=1:a=0[outv]"));
+        assert!(!args.contains(&"[outa]".to_string()));
+    }
+
+    #[test]
+    fn test_filter_args_reject_copy() {
+        let inputs = [
+            input("/in/a.mp4", H264_1080, Some(AAC_48K)),
+            input("/in/b.mp4", H264_720, None),
+        ];
+        let audio_copy = ProcessVideoParams {
+            encoding: Some(EncodingProfile {
+                audio: AudioSettings {
+                    codec: AudioCodec::Copy,
+                    ..Default::default()
+                },
+                ..Default::default()
+            }),
+            ..params()
+        };
+        assert!(build_concat_filter_args(&audio_copy, &inputs).is_err());
+
+        // Without any audio there is no filtered audio to copy
+        let silent = [
+            input("/in/a.mp4", H264_1080, None),
+            input("/in/b.mp4", H264_720, None),
+        ];
+        assert!(build_concat_filter_args(&audio_copy, &silent).is_ok());
+    }
+}
@@ -0,0 +1,247 @@
+use crate::engine::encoding::{AudioCodec, AudioSettings, EncodingProfile, VideoCodec, VideoSettings};
+use crate::engine::subtitles::SubtitleFormat;
+use std::path::Path;
+
+/// Output containers accepted by `validate_inputs`, keyed by file extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Container {
+    Mp4,
+    Mov,
+    Mkv,
+    Webm,
+    Avi,
+}
+
+pub const SUPPORTED_EXTENSIONS: &[&str] = &["mp4", "avi", "mov", "mkv", "webm"];
+
+impl Container {
+    pub fn from_extension(ext: &str) -> Option<Self> {
+        match ext.to_ascii_lowercase().as_str() {
+            "mp4" => Some(Container::Mp4),
+            "mov" => Some(Container::Mov),
+            "mkv" => Some(Container::Mkv),
+            "webm" => Some(Container::Webm),
+            "avi" => Some(Container::Avi),
+            _ => None,
+        }
+    }
+
+    pub fn from_path(path: &str) -> Option<Self> {
+        Path::new(path)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(Self::from_extension)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Container::Mp4 => "MP4",
+            Container::Mov => "MOV",
+            Container::Mkv => "MKV",
+            Container::Webm => "WebM",
+            Container::Avi => "AVI",
+        }
+    }
+
+    /// Short hint naming the codecs that work well in this container.
+    fn recommendation(self) -> &'static str {
+        match self {
+            Container::Mp4 => "H.264/HEVC/AV1 + AAC",
+            Container::Mov => "H.264/HEVC + AAC",
+            Container::Mkv => "any supported codec",
+            Container::Webm => "VP9/AV1 + Opus",
+            Container::Avi => "MPEG-4/H.264 + MP3",
+        }
+    }
+
+    pub fn supports_video(self, codec: VideoCodec) -> bool {
+        use VideoCodec::*;
+        match self {
+            Container::Mkv => true,
+            Container::Mp4 => matches!(codec, Libx264 | Libx265 | LibvpxVp9 | LibaomAv1 | Libsvtav1 | Mpeg4 | Copy),
+            Container::Mov => matches!(codec, Libx264 | Libx265 | Mpeg4 | Copy),
+            Container::Webm => matches!(codec, LibvpxVp9 | LibaomAv1 | Libsvtav1 | Copy),
+            Container::Avi => matches!(codec, Libx264 | Mpeg4 | Copy),
+        }
+    }
+
+    pub fn supports_audio(self, codec: AudioCodec) -> bool {
+        use AudioCodec::*;
+        match self {
+            Container::Mkv => true,
+            Container::Mp4 => matches!(codec, Aac | Libopus | Libmp3lame | Ac3 | Flac | Copy | None),
+            Container::Mov => matches!(codec, Aac | Libmp3lame | Ac3 | PcmS16le | Copy | None),
+            Container::Webm => matches!(codec, Libopus | Libvorbis | Copy | None),
+            Container::Avi => matches!(codec, Libmp3lame | Ac3 | PcmS16le | Copy | None),
+        }
+    }
+
+    /// Encoder for a subtitle track read from `format`, or `None` when the
+    /// container has no text subtitle support. MKV keeps the source format;
+    /// MP4/MOV and WebM each allow a single text codec.
+    pub fn subtitle_codec(self, format: SubtitleFormat) -> Option<&'static str> {
+        match self {
+            Container::Mp4 | Container::Mov => Some("mov_text"),
+            Container::Mkv => Some(match format {
+                SubtitleFormat::Srt => "srt",
+                SubtitleFormat::Ass => "ass",
+                SubtitleFormat::WebVtt => "webvtt",
+            }),
+            Container::Webm => Some("webvtt"),
+            Container::Avi => None,
+        }
+    }
+
+    /// Sensible encoder choice when the user has not picked codecs explicitly.
+    pub fn default_encoding(self) -> EncodingProfile {
+        let (video, audio) = match self {
+            Container::Mp4 | Container::Mov | Container::Mkv => (VideoCodec::Libx264, AudioCodec::Aac),
+            Container::Webm => (VideoCodec::LibvpxVp9, AudioCodec::Libopus),
+            Container::Avi => (VideoCodec::Mpeg4, AudioCodec::Libmp3lame),
+        };
+
+        EncodingProfile {
+            video: VideoSettings {
+                codec: video,
+                ..Default::default()
+            },
+            audio: AudioSettings {
+                codec: audio,
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Pre-flight check that the chosen encoders can be muxed into this container.
+    /// Stream copy is allowed here; the source codecs are only known after probing.
+    pub fn check_encoding(self, profile: &EncodingProfile) -> Result<(), String> {
+        if !self.supports_video(profile.video.codec) {
+            return Err(format!(
+                "{} is not allowed in {}; choose {}",
+                video_label(profile.video.codec),
+                self.label(),
+                self.recommendation()
+            ));
+        }
+
+        if !self.supports_audio(profile.audio.codec) {
+            return Err(format!(
+                "{} audio is not allowed in {}; choose {}",
+                audio_label(profile.audio.codec),
+                self.label(),
+                self.recommendation()
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+fn video_label(codec: VideoCodec) -> &'static str {
+    match codec {
+        VideoCodec::Libx264 => "H.264",
+        VideoCodec::Libx265 => "HEVC",
+        VideoCodec::LibvpxVp9 => "VP9",
+        VideoCodec::LibaomAv1 | VideoCodec::Libsvtav1 => "AV1",
+        VideoCodec::Mpeg4 => "MPEG-4",
+        VideoCodec::Copy => "Stream copy",
+    }
+}
+
+fn audio_label(codec: AudioCodec) -> &'static str {
+    match codec {
+        AudioCodec::Aac => "AAC",
+        AudioCodec::Libopus => "Opus",
+        AudioCodec::Libvorbis => "Vorbis",
+        AudioCodec::Libmp3lame => "MP3",
+        AudioCodec::Ac3 => "AC-3",
+        AudioCodec::Flac => "FLAC",
+        AudioCodec::PcmS16le => "PCM",
+        AudioCodec::Copy => "Stream copy",
+        AudioCodec::None => "No",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(video: VideoCodec, audio: AudioCodec) -> EncodingProfile {
+        EncodingProfile {
+            video: VideoSettings {
+                codec: video,
+                ..Default::default()
+            },
+            audio: AudioSettings {
+                codec: audio,
+                ..Default::default()
+            },
+        }
+    }
+
+    #[test]
+    fn test_from_path() {
+        assert_eq!(Container::from_path("/out/clip.mp4"), Some(Container::Mp4));
+        assert_eq!(Container::from_path("C:\\out\\clip.WEBM"), Some(Container::Webm));
+        assert_eq!(Container::from_path("/out/clip.txt"), None);
+        assert_eq!(Container::from_path("/out/clip"), None);
+    }
+
+    #[test]
+    fn test_h264_rejected_in_webm() {
+        let result = Container::Webm.check_encoding(&profile(VideoCodec::Libx264, AudioCodec::Aac));
+        assert_eq!(
+            result.unwrap_err(),
+            "H.264 is not allowed in WebM; choose VP9/AV1 + Opus"
+        );
+    }
+
+    #[test]
+    fn test_aac_rejected_in_webm() {
+        let result = Container::Webm.check_encoding(&profile(VideoCodec::LibvpxVp9, AudioCodec::Aac));
+        assert!(result.unwrap_err().starts_with("AAC audio is not allowed in WebM"));
+    }
+
+    #[test]
+    fn test_mkv_accepts_everything() {
+        for video in [VideoCodec::Libx265, VideoCodec::Libsvtav1, VideoCodec::Mpeg4] {
+            for audio in [AudioCodec::Flac, AudioCodec::PcmS16le, AudioCodec::Libvorbis] {
+                assert!(Container::Mkv.check_encoding(&profile(video, audio)).is_ok());
+            }
+        }
+    }
+
+    #[test]
+    fn test_mov_rejects_vp9() {
+        assert!(Container::Mov
+            .check_encoding(&profile(VideoCodec::LibvpxVp9, AudioCodec::Aac))
+            .is_err());
+    }
+
+    #[test]
+    fn test_defaults_are_compatible() {
+        for ext in SUPPORTED_EXTENSIONS {
+            let container = Container::from_extension(ext).unwrap();
+            let defaults = container.default_encoding();
+            assert!(container.check_encoding(&defaults).is_ok(), "{}", ext);
+            assert!(defaults.validate().is_ok(), "{}", ext);
+        }
+    }
+
+    #[test]
+    fn test_webm_defaults() {
+        let defaults = Container::Webm.default_encoding();
+        assert_eq!(defaults.video.codec, VideoCodec::LibvpxVp9);
+        assert_eq!(defaults.audio.codec, AudioCodec::Libopus);
+    }
+
+    #[test]
+    fn test_stream_copy_allowed_everywhere() {
+        for ext in SUPPORTED_EXTENSIONS {
+            let container = Container::from_extension(ext).unwrap();
+            assert!(container
+                .check_encoding(&profile(VideoCodec::Copy, AudioCodec::Copy))
+                .is_ok());
+        }
+    }
+}
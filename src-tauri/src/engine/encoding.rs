@@ -0,0 +1,693 @@
+use serde::{Deserialize, Serialize};
+
+const X264_PRESETS: &[&str] = &[
+    "ultrafast", "superfast", "veryfast", "faster", "fast", "medium", "slow", "slower",
+    "veryslow", "placebo",
+];
+const X264_TUNES: &[&str] = &[
+    "film", "animation", "grain", "stillimage", "fastdecode", "zerolatency", "psnr", "ssim",
+];
+const X265_TUNES: &[&str] = &["psnr", "ssim", "grain", "zerolatency", "fastdecode", "animation"];
+const X264_PROFILES: &[&str] = &["baseline", "main", "high", "high10", "high422", "high444"];
+const X265_PROFILES: &[&str] = &[
+    "main", "main10", "mainstillpicture", "main12", "main422-10", "main422-12", "main444-8",
+    "main444-10", "main444-12",
+];
+const VP9_PROFILES: &[&str] = &["0", "1", "2", "3"];
+const AV1_PROFILES: &[&str] = &["main", "high", "professional"];
+const PIXEL_FORMATS: &[&str] = &[
+    "yuv420p", "yuv422p", "yuv444p", "yuv420p10le", "yuv422p10le", "yuv444p10le",
+    "yuv420p12le", "yuvj420p", "nv12", "p010le", "gray",
+];
+const SAMPLE_RATES: &[u32] = &[8000, 11025, 16000, 22050, 24000, 32000, 44100, 48000, 88200, 96000];
+const OPUS_SAMPLE_RATES: &[u32] = &[8000, 12000, 16000, 24000, 48000];
+
+const MAX_VIDEO_BITRATE_KBPS: u32 = 500_000;
+const MIN_AUDIO_BITRATE_KBPS: u32 = 8;
+const MAX_AUDIO_BITRATE_KBPS: u32 = 1536;
+const MAX_AUDIO_CHANNELS: u32 = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum VideoCodec {
+    #[default]
+    #[serde(rename = "libx264")]
+    Libx264,
+    #[serde(rename = "libx265")]
+    Libx265,
+    #[serde(rename = "libvpx-vp9")]
+    LibvpxVp9,
+    #[serde(rename = "libaom-av1")]
+    LibaomAv1,
+    #[serde(rename = "libsvtav1")]
+    Libsvtav1,
+    #[serde(rename = "mpeg4")]
+    Mpeg4,
+    #[serde(rename = "copy")]
+    Copy,
+}
+
+impl VideoCodec {
+    pub fn ffmpeg_name(self) -> &'static str {
+        match self {
+            VideoCodec::Libx264 => "libx264",
+            VideoCodec::Libx265 => "libx265",
+            VideoCodec::LibvpxVp9 => "libvpx-vp9",
+            VideoCodec::LibaomAv1 => "libaom-av1",
+            VideoCodec::Libsvtav1 => "libsvtav1",
+            VideoCodec::Mpeg4 => "mpeg4",
+            VideoCodec::Copy => "copy",
+        }
+    }
+
+    /// The encoder producing the stream ffprobe calls `name`, e.g. `h264`.
+    pub fn from_source(name: &str) -> Option<Self> {
+        match name {
+            "h264" => Some(VideoCodec::Libx264),
+            "hevc" => Some(VideoCodec::Libx265),
+            "vp9" => Some(VideoCodec::LibvpxVp9),
+            "av1" => Some(VideoCodec::LibaomAv1),
+            "mpeg4" => Some(VideoCodec::Mpeg4),
+            _ => None,
+        }
+    }
+
+    /// Valid range for the constant-quality value. mpeg4 has no CRF mode, so
+    /// its range is the `-q:v` quantiser scale.
+    fn quality_range(self) -> Option<(u8, u8)> {
+        match self {
+            VideoCodec::Libx264 | VideoCodec::Libx265 => Some((0, 51)),
+            VideoCodec::LibvpxVp9 | VideoCodec::LibaomAv1 | VideoCodec::Libsvtav1 => Some((0, 63)),
+            VideoCodec::Mpeg4 => Some((1, 31)),
+            VideoCodec::Copy => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum AudioCodec {
+    #[default]
+    Aac,
+    Libopus,
+    Libvorbis,
+    Libmp3lame,
+    Ac3,
+    Flac,
+    PcmS16le,
+    Copy,
+    /// Drop audio entirely (`-an`).
+    None,
+}
+
+impl AudioCodec {
+    pub fn ffmpeg_name(self) -> &'static str {
+        match self {
+            AudioCodec::Aac => "aac",
+            AudioCodec::Libopus => "libopus",
+            AudioCodec::Libvorbis => "libvorbis",
+            AudioCodec::Libmp3lame => "libmp3lame",
+            AudioCodec::Ac3 => "ac3",
+            AudioCodec::Flac => "flac",
+            AudioCodec::PcmS16le => "pcm_s16le",
+            AudioCodec::Copy => "copy",
+            AudioCodec::None => "none",
+        }
+    }
+
+    /// The encoder producing the stream ffprobe calls `name`, e.g. `aac`.
+    pub fn from_source(name: &str) -> Option<Self> {
+        match name {
+            "aac" => Some(AudioCodec::Aac),
+            "opus" => Some(AudioCodec::Libopus),
+            "vorbis" => Some(AudioCodec::Libvorbis),
+            "mp3" => Some(AudioCodec::Libmp3lame),
+            "ac3" => Some(AudioCodec::Ac3),
+            "flac" => Some(AudioCodec::Flac),
+            "pcm_s16le" => Some(AudioCodec::PcmS16le),
+            _ => None,
+        }
+    }
+
+    fn is_lossless(self) -> bool {
+        matches!(self, AudioCodec::Flac | AudioCodec::PcmS16le)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum RateControl {
+    /// Constant quality. Lower is better.
+    Crf { value: u8 },
+    /// Average bitrate in kbit/s.
+    Bitrate { kbps: u32 },
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VideoSettings {
+    pub codec: VideoCodec,
+    pub rate_control: Option<RateControl>,
+    pub preset: Option<String>,
+    pub tune: Option<String>,
+    pub profile: Option<String>,
+    pub level: Option<String>,
+    pub pix_fmt: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AudioSettings {
+    pub codec: AudioCodec,
+    pub bitrate_kbps: Option<u32>,
+    pub channels: Option<u32>,
+    pub sample_rate: Option<u32>,
+}
+
+/// Encoder settings for an export. The default reproduces the historical
+/// `-c:v libx264 -c:a aac` behaviour with encoder defaults.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EncodingProfile {
+    pub video: VideoSettings,
+    pub audio: AudioSettings,
+}
+
+impl VideoSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        let codec = self.codec;
+
+        if codec == VideoCodec::Copy {
+            let has_options = self.rate_control.is_some()
+                || self.preset.is_some()
+                || self.tune.is_some()
+                || self.profile.is_some()
+                || self.level.is_some()
+                || self.pix_fmt.is_some();
+            if has_options {
+                return Err("Video encoder options cannot be used with stream copy".to_string());
+            }
+            return Ok(());
+        }
+
+        match self.rate_control {
+            Some(RateControl::Crf { value }) => {
+                let (min, max) = codec.quality_range().unwrap_or((0, 0));
+                if value < min || value > max {
+                    return Err(format!(
+                        "Quality {} is out of range for {} (expected {}-{})",
+                        value,
+                        codec.ffmpeg_name(),
+                        min,
+                        max
+                    ));
+                }
+            }
+            Some(RateControl::Bitrate { kbps }) if kbps == 0 || kbps > MAX_VIDEO_BITRATE_KBPS => {
+                return Err(format!(
+                    "Video bitrate must be between 1 and {} kbps",
+                    MAX_VIDEO_BITRATE_KBPS
+                ));
+            }
+            Some(RateControl::Bitrate { .. }) | None => {}
+        }
+
+        if let Some(ref preset) = self.preset {
+            let valid = match codec {
+                VideoCodec::Libx264 | VideoCodec::Libx265 => X264_PRESETS.contains(&preset.as_str()),
+                VideoCodec::Libsvtav1 => numeric_in_range(preset, 0, 13),
+                VideoCodec::LibaomAv1 => numeric_in_range(preset, 0, 8),
+                VideoCodec::LibvpxVp9 => numeric_in_range(preset, 0, 8),
+                VideoCodec::Mpeg4 | VideoCodec::Copy => false,
+            };
+            if !valid {
+                return Err(format!("Invalid preset '{}' for {}", preset, codec.ffmpeg_name()));
+            }
+        }
+
+        if let Some(ref tune) = self.tune {
+            let tunes: &[&str] = match codec {
+                VideoCodec::Libx264 => X264_TUNES,
+                VideoCodec::Libx265 => X265_TUNES,
+                _ => &[],
+            };
+            if !tunes.contains(&tune.as_str()) {
+                return Err(format!("Invalid tune '{}' for {}", tune, codec.ffmpeg_name()));
+            }
+        }
+
+        if let Some(ref profile) = self.profile {
+            let profiles: &[&str] = match codec {
+                VideoCodec::Libx264 => X264_PROFILES,
+                VideoCodec::Libx265 => X265_PROFILES,
+                VideoCodec::LibvpxVp9 => VP9_PROFILES,
+                VideoCodec::LibaomAv1 | VideoCodec::Libsvtav1 => AV1_PROFILES,
+                VideoCodec::Mpeg4 | VideoCodec::Copy => &[],
+            };
+            if !profiles.contains(&profile.as_str()) {
+                return Err(format!("Invalid profile '{}' for {}", profile, codec.ffmpeg_name()));
+            }
+        }
+
+        if let Some(ref level) = self.level {
+            if !matches!(codec, VideoCodec::Libx264 | VideoCodec::Libx265) {
+                return Err(format!("Level is not supported for {}", codec.ffmpeg_name()));
+            }
+            if !is_valid_level(level) {
+                return Err(format!("Invalid level '{}'. Expected a value such as 4.1", level));
+            }
+        }
+
+        if let Some(ref pix_fmt) = self.pix_fmt {
+            if !PIXEL_FORMATS.contains(&pix_fmt.as_str()) {
+                return Err(format!("Unsupported pixel format '{}'", pix_fmt));
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn to_args(&self) -> Vec<String> {
+        let codec = self.codec;
+        let mut args = vec!["-c:v".to_string(), codec.ffmpeg_name().to_string()];
+
+        match self.rate_control {
+            Some(RateControl::Crf { value }) => match codec {
+                VideoCodec::Mpeg4 => push_pair(&mut args, "-q:v", value),
+                // libvpx and libaom only run in constant-quality mode with -b:v 0
+                VideoCodec::LibvpxVp9 | VideoCodec::LibaomAv1 => {
+                    push_pair(&mut args, "-crf", value);
+                    push_pair(&mut args, "-b:v", 0);
+                }
+                _ => push_pair(&mut args, "-crf", value),
+            },
+            Some(RateControl::Bitrate { kbps }) => push_pair(&mut args, "-b:v", format!("{}k", kbps)),
+            None => {}
+        }
+
+        if let Some(ref preset) = self.preset {
+            match codec {
+                VideoCodec::LibvpxVp9 | VideoCodec::LibaomAv1 => push_pair(&mut args, "-cpu-used", preset),
+                _ => push_pair(&mut args, "-preset", preset),
+            }
+        }
+        if let Some(ref tune) = self.tune {
+            push_pair(&mut args, "-tune", tune);
+        }
+        if let Some(ref profile) = self.profile {
+            push_pair(&mut args, "-profile:v", profile);
+        }
+        if let Some(ref level) = self.level {
+            match codec {
+                VideoCodec::Libx265 => push_pair(&mut args, "-x265-params", format!("level-idc={}", level)),
+                _ => push_pair(&mut args, "-level:v", level),
+            }
+        }
+        if let Some(ref pix_fmt) = self.pix_fmt {
+            push_pair(&mut args, "-pix_fmt", pix_fmt);
+        }
+
+        args
+    }
+}
+
+impl AudioSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        let codec = self.codec;
+        let passthrough = matches!(codec, AudioCodec::Copy | AudioCodec::None);
+
+        if passthrough
+            && (self.bitrate_kbps.is_some() || self.channels.is_some() || self.sample_rate.is_some())
+        {
+            return Err(format!(
+                "Audio options cannot be used with audio codec '{}'",
+                codec.ffmpeg_name()
+            ));
+        }
+
+        if let Some(kbps) = self.bitrate_kbps {
+            if codec.is_lossless() {
+                return Err(format!("{} is lossless and does not take a bitrate", codec.ffmpeg_name()));
+            }
+            if !(MIN_AUDIO_BITRATE_KBPS..=MAX_AUDIO_BITRATE_KBPS).contains(&kbps) {
+                return Err(format!(
+                    "Audio bitrate must be between {} and {} kbps",
+                    MIN_AUDIO_BITRATE_KBPS, MAX_AUDIO_BITRATE_KBPS
+                ));
+            }
+        }
+
+        if let Some(channels) = self.channels {
+            if channels == 0 || channels > MAX_AUDIO_CHANNELS {
+                return Err(format!("Audio channels must be between 1 and {}", MAX_AUDIO_CHANNELS));
+            }
+        }
+
+        if let Some(rate) = self.sample_rate {
+            let rates = if codec == AudioCodec::Libopus {
+                OPUS_SAMPLE_RATES
+            } else {
+                SAMPLE_RATES
+            };
+            if !rates.contains(&rate) {
+                return Err(format!(
+                    "Unsupported sample rate {} Hz for {}",
+                    rate,
+                    codec.ffmpeg_name()
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn to_args(&self) -> Vec<String> {
+        if self.codec == AudioCodec::None {
+            return vec!["-an".to_string()];
+        }
+
+        let mut args = vec!["-c:a".to_string(), self.codec.ffmpeg_name().to_string()];
+        if let Some(kbps) = self.bitrate_kbps {
+            push_pair(&mut args, "-b:a", format!("{}k", kbps));
+        }
+        if let Some(channels) = self.channels {
+            push_pair(&mut args, "-ac", channels);
+        }
+        if let Some(rate) = self.sample_rate {
+            push_pair(&mut args, "-ar", rate);
+        }
+        args
+    }
+}
+
+impl EncodingProfile {
+    pub fn validate(&self) -> Result<(), String> {
+        self.video.validate()?;
+        self.audio.validate()
+    }
+
+    pub fn to_args(&self) -> Vec<String> {
+        let mut args = self.video.to_args();
+        args.extend(self.audio.to_args());
+        args
+    }
+}
+
+fn push_pair(args: &mut Vec<String>, flag: &str, value: impl ToString) {
+    args.push(flag.to_string());
+    args.push(value.to_string());
+}
+
+fn numeric_in_range(value: &str, min: i32, max: i32) -> bool {
+    value.parse::<i32>().map(|v| (min..=max).contains(&v)).unwrap_or(false)
+}
+
+/// Accepts H.264/H.265 style levels: `4`, `4.1`, `5.2`.
+fn is_valid_level(level: &str) -> bool {
+    let mut parts = level.split('.');
+    let major_ok = parts
+        .next()
+        .map(|major| major.len() == 1 && major.chars().all(|c| c.is_ascii_digit()))
+        .unwrap_or(false);
+    let minor_ok = parts
+        .next()
+        .map(|minor| minor.len() == 1 && minor.chars().all(|c| c.is_ascii_digit()))
+        .unwrap_or(true);
+    major_ok && minor_ok && parts.next().is_none()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn video(codec: VideoCodec) -> VideoSettings {
+        VideoSettings {
+            codec,
+            ..Default::default()
+        }
+    }
+
+    fn audio(codec: AudioCodec) -> AudioSettings {
+        AudioSettings {
+            codec,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_default_profile_matches_legacy_args() {
+        let args = EncodingProfile::default().to_args();
+        assert_eq!(args, vec!["-c:v", "libx264", "-c:a", "aac"]);
+    }
+
+    #[test]
+    fn test_deserialize_profile_from_frontend_json() {
+        let json = r#"{
+            "video": {
+                "codec": "libvpx-vp9",
+                "rate_control": { "mode": "crf", "value": 31 },
+                "preset": "4"
+            },
+            "audio": { "codec": "libopus", "bitrate_kbps": 96 }
+        }"#;
+
+        let profile: EncodingProfile = serde_json::from_str(json).unwrap();
+        assert_eq!(profile.video.codec, VideoCodec::LibvpxVp9);
+        assert_eq!(profile.video.rate_control, Some(RateControl::Crf { value: 31 }));
+        assert_eq!(profile.audio.codec, AudioCodec::Libopus);
+        assert!(profile.validate().is_ok());
+    }
+
+    #[test]
+    fn test_x264_full_args() {
+        let settings = VideoSettings {
+            codec: VideoCodec::Libx264,
+            rate_control: Some(RateControl::Crf { value: 20 }),
+            preset: Some("slow".to_string()),
+            tune: Some("film".to_string()),
+            profile: Some("high".to_string()),
+            level: Some("4.1".to_string()),
+            pix_fmt: Some("yuv420p".to_string()),
+        };
+
+        assert!(settings.validate().is_ok());
+        assert_eq!(
+            settings.to_args(),
+            vec![
+                "-c:v", "libx264", "-crf", "20", "-preset", "slow", "-tune", "film",
+                "-profile:v", "high", "-level:v", "4.1", "-pix_fmt", "yuv420p",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_vp9_crf_sets_zero_bitrate() {
+        let settings = VideoSettings {
+            rate_control: Some(RateControl::Crf { value: 33 }),
+            preset: Some("2".to_string()),
+            ..video(VideoCodec::LibvpxVp9)
+        };
+
+        assert_eq!(
+            settings.to_args(),
+            vec!["-c:v", "libvpx-vp9", "-crf", "33", "-b:v", "0", "-cpu-used", "2"]
+        );
+    }
+
+    #[test]
+    fn test_mpeg4_quality_uses_qscale() {
+        let settings = VideoSettings {
+            rate_control: Some(RateControl::Crf { value: 4 }),
+            ..video(VideoCodec::Mpeg4)
+        };
+
+        assert!(settings.validate().is_ok());
+        assert_eq!(settings.to_args(), vec!["-c:v", "mpeg4", "-q:v", "4"]);
+    }
+
+    #[test]
+    fn test_bitrate_mode_args() {
+        let settings = VideoSettings {
+            rate_control: Some(RateControl::Bitrate { kbps: 8000 }),
+            ..video(VideoCodec::Libx265)
+        };
+
+        assert_eq!(settings.to_args(), vec!["-c:v", "libx265", "-b:v", "8000k"]);
+    }
+
+    #[test]
+    fn test_x265_level_uses_x265_params() {
+        let settings = VideoSettings {
+            level: Some("5.1".to_string()),
+            ..video(VideoCodec::Libx265)
+        };
+
+        assert_eq!(settings.to_args(), vec!["-c:v", "libx265", "-x265-params", "level-idc=5.1"]);
+    }
+
+    #[test]
+    fn test_crf_out_of_range_rejected() {
+        let settings = VideoSettings {
+            rate_control: Some(RateControl::Crf { value: 52 }),
+            ..video(VideoCodec::Libx264)
+        };
+        assert!(settings.validate().unwrap_err().contains("out of range"));
+
+        let settings = VideoSettings {
+            rate_control: Some(RateControl::Crf { value: 63 }),
+            ..video(VideoCodec::Libsvtav1)
+        };
+        assert!(settings.validate().is_ok());
+    }
+
+    #[test]
+    fn test_zero_bitrate_rejected() {
+        let settings = VideoSettings {
+            rate_control: Some(RateControl::Bitrate { kbps: 0 }),
+            ..video(VideoCodec::Libx264)
+        };
+        assert!(settings.validate().is_err());
+    }
+
+    #[test]
+    fn test_preset_validated_per_codec() {
+        let x264 = VideoSettings {
+            preset: Some("veryfast".to_string()),
+            ..video(VideoCodec::Libx264)
+        };
+        assert!(x264.validate().is_ok());
+
+        let svt_named = VideoSettings {
+            preset: Some("veryfast".to_string()),
+            ..video(VideoCodec::Libsvtav1)
+        };
+        assert!(svt_named.validate().is_err());
+
+        let svt_numeric = VideoSettings {
+            preset: Some("8".to_string()),
+            ..video(VideoCodec::Libsvtav1)
+        };
+        assert!(svt_numeric.validate().is_ok());
+
+        let mpeg4 = VideoSettings {
+            preset: Some("fast".to_string()),
+            ..video(VideoCodec::Mpeg4)
+        };
+        assert!(mpeg4.validate().is_err());
+    }
+
+    #[test]
+    fn test_tune_and_profile_validated_per_codec() {
+        let tune = VideoSettings {
+            tune: Some("film".to_string()),
+            ..video(VideoCodec::Libx265)
+        };
+        assert!(tune.validate().is_err());
+
+        let profile = VideoSettings {
+            profile: Some("main10".to_string()),
+            ..video(VideoCodec::Libx265)
+        };
+        assert!(profile.validate().is_ok());
+
+        let profile = VideoSettings {
+            profile: Some("main10".to_string()),
+            ..video(VideoCodec::Libx264)
+        };
+        assert!(profile.validate().is_err());
+    }
+
+    #[test]
+    fn test_level_format() {
+        assert!(is_valid_level("4"));
+        assert!(is_valid_level("4.1"));
+        assert!(!is_valid_level("4.12"));
+        assert!(!is_valid_level("41"));
+        assert!(!is_valid_level("4.1; rm"));
+    }
+
+    #[test]
+    fn test_unknown_pixel_format_rejected() {
+        let settings = VideoSettings {
+            pix_fmt: Some("rgb24 -y".to_string()),
+            ..video(VideoCodec::Libx264)
+        };
+        assert!(settings.validate().is_err());
+    }
+
+    #[test]
+    fn test_copy_rejects_encoder_options() {
+        assert!(video(VideoCodec::Copy).validate().is_ok());
+
+        let settings = VideoSettings {
+            rate_control: Some(RateControl::Crf { value: 20 }),
+            ..video(VideoCodec::Copy)
+        };
+        assert!(settings.validate().is_err());
+    }
+
+    #[test]
+    fn test_audio_args() {
+        let settings = AudioSettings {
+            codec: AudioCodec::Aac,
+            bitrate_kbps: Some(192),
+            channels: Some(2),
+            sample_rate: Some(48000),
+        };
+
+        assert!(settings.validate().is_ok());
+        assert_eq!(
+            settings.to_args(),
+            vec!["-c:a", "aac", "-b:a", "192k", "-ac", "2", "-ar", "48000"]
+        );
+    }
+
+    #[test]
+    fn test_audio_none_drops_audio() {
+        assert_eq!(audio(AudioCodec::None).to_args(), vec!["-an"]);
+
+        let settings = AudioSettings {
+            bitrate_kbps: Some(128),
+            ..audio(AudioCodec::None)
+        };
+        assert!(settings.validate().is_err());
+    }
+
+    #[test]
+    fn test_audio_bitrate_rejected_for_lossless() {
+        let settings = AudioSettings {
+            bitrate_kbps: Some(128),
+            ..audio(AudioCodec::Flac)
+        };
+        assert!(settings.validate().is_err());
+    }
+
+    #[test]
+    fn test_audio_ranges() {
+        let channels = AudioSettings {
+            channels: Some(9),
+            ..audio(AudioCodec::Aac)
+        };
+        assert!(channels.validate().is_err());
+
+        let bitrate = AudioSettings {
+            bitrate_kbps: Some(4),
+            ..audio(AudioCodec::Aac)
+        };
+        assert!(bitrate.validate().is_err());
+    }
+
+    #[test]
+    fn test_opus_sample_rates() {
+        let settings = AudioSettings {
+            sample_rate: Some(44100),
+            ..audio(AudioCodec::Libopus)
+        };
+        assert!(settings.validate().is_err());
+
+        let settings = AudioSettings {
+            sample_rate: Some(48000),
+            ..audio(AudioCodec::Libopus)
+        };
+        assert!(settings.validate().is_ok());
+    }
+}
@@ -0,0 +1,1958 @@
+use crate::engine::concat;
+use crate::engine::container::{Container, SUPPORTED_EXTENSIONS};
+use crate::engine::encoding::{AudioCodec, AudioSettings, EncodingProfile, VideoCodec, VideoSettings};
+use crate::engine::naming::{self, CollisionPolicy};
+use crate::engine::probe::{self, StreamInfo, KEYFRAME_SEARCH_WINDOW, KEYFRAME_TOLERANCE};
+use crate::engine::progress::FfmpegProgress;
+use crate::engine::segments::{self, CutSegment, KeptRange};
+use crate::engine::subtitles::{self, SubtitleStyle, SubtitleTrack};
+use crate::engine::two_pass;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProcessVideoParams {
+    pub input_file: String,
+    pub output_file: String,
+    /// `output_file` may contain name tokens such as `{name}`. Paths from the
+    /// save dialog are used as they are.
+    #[serde(default)]
+    pub output_template: bool,
+    pub start_time: Option<f64>,
+    pub end_time: Option<f64>,
+    /// Subtitles burned into the video.
+    pub subtitle_file: Option<String>,
+    /// Overrides for how `subtitle_file` is drawn.
+    #[serde(default)]
+    pub subtitle_style: Option<SubtitleStyle>,
+    /// Subtitle files muxed as selectable tracks. Unlike `subtitle_file`
+    /// these leave the video stream alone.
+    #[serde(default)]
+    pub subtitle_tracks: Vec<SubtitleTrack>,
+    /// Encoder settings. When omitted, defaults are picked from the output container.
+    #[serde(default)]
+    pub encoding: Option<EncodingProfile>,
+    #[serde(default)]
+    pub trim_mode: TrimMode,
+    /// Cut list of ranges to keep or remove. When non-empty it replaces
+    /// `start_time`/`end_time`.
+    #[serde(default)]
+    pub segments: Vec<CutSegment>,
+    /// Further inputs joined after `input_file`, in order.
+    #[serde(default)]
+    pub concat_inputs: Vec<String>,
+    /// Target output size in megabytes. Enables a two-pass encode at the
+    /// video bitrate that fits the size.
+    #[serde(default)]
+    pub target_size_mb: Option<f64>,
+    /// Name of a saved preset to use instead of `encoding` and `target_size_mb`.
+    /// Resolved when the job is queued.
+    #[serde(default)]
+    pub preset: Option<String>,
+    /// What to do when `output_file` is already taken.
+    #[serde(default)]
+    pub collision_policy: CollisionPolicy,
+    /// 1-based position within a batch, used by the `{index}` name token.
+    #[serde(default)]
+    pub batch_index: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TrimMode {
+    /// Re-encode everything; cuts land exactly on the requested times.
+    #[default]
+    Accurate,
+    /// Stream copy with input-side seeking. Lossless and fast, but the output
+    /// starts at the keyframe at or before `start_time`.
+    Fast,
+    /// Re-encode only the partial GOPs at either end of the range and stream
+    /// copy everything between, then join the pieces. Frame accurate and
+    /// nearly as fast as `Fast` on long ranges.
+    Smart,
+}
+
+/// Quality used when re-encoding smart-cut edges, chosen to be visually
+/// indistinguishable from the copied middle section. Only codecs whose
+/// encoder can reproduce the source's profile, level and pixel format are
+/// listed; other sources are refused rather than joined into a broken file.
+const SMART_CUT_ENCODERS: &[(&str, &[&str])] = &[
+    ("h264", &["-c:v", "libx264", "-crf", "16", "-preset", "medium"]),
+    ("hevc", &["-c:v", "libx265", "-crf", "18", "-preset", "medium"]),
+];
+
+/// ffprobe profile names per codec and the encoder's `-profile:v` value.
+const SMART_CUT_PROFILES: &[(&str, &str, &str)] = &[
+    ("h264", "Constrained Baseline", "baseline"),
+    ("h264", "Baseline", "baseline"),
+    ("h264", "Main", "main"),
+    ("h264", "High", "high"),
+    ("h264", "High 10", "high10"),
+    ("h264", "High 4:2:2", "high422"),
+    ("h264", "High 4:4:4 Predictive", "high444"),
+    ("hevc", "Main", "main"),
+    ("hevc", "Main 10", "main10"),
+];
+
+/// Smart-cut pieces are written as MPEG-TS, which stores H.264/HEVC as
+/// Annex B with the parameter sets in front of every keyframe. The concat
+/// demuxer only keeps the first piece's headers, so each piece has to carry
+/// its own for the re-encoded edges and the copied middle to decode.
+const SMART_CUT_SEGMENT_EXTENSION: &str = "ts";
+
+/// Nudge applied when seeking to a keyframe for stream copy. ffprobe prints
+/// timestamps rounded to microseconds, and seeking to a value fractionally
+/// below the true keyframe would land on the previous one.
+const SMART_CUT_SEEK_EPSILON: f64 = 0.0005;
+
+fn stream_copy_profile() -> EncodingProfile {
+    EncodingProfile {
+        video: VideoSettings {
+            codec: VideoCodec::Copy,
+            ..Default::default()
+        },
+        audio: AudioSettings {
+            codec: AudioCodec::Copy,
+            ..Default::default()
+        },
+    }
+}
+
+impl ProcessVideoParams {
+    /// The explicit encoding profile, or the output container's defaults.
+    pub fn effective_encoding(&self) -> EncodingProfile {
+        if matches!(self.trim_mode, TrimMode::Fast | TrimMode::Smart) {
+            return stream_copy_profile();
+        }
+
+        self.encoding.clone().unwrap_or_else(|| {
+            Container::from_path(&self.output_file)
+                .map(Container::default_encoding)
+                .unwrap_or_default()
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProgressPayload {
+    pub job_id: String,
+    pub seconds: f64,
+    pub percent: f64,
+    pub frame: Option<u64>,
+    pub fps: Option<f64>,
+    pub bitrate_kbps: Option<f64>,
+    /// Bytes written so far.
+    pub total_size: Option<u64>,
+    /// Encoding speed as a multiple of realtime.
+    pub speed: Option<f64>,
+    pub dropped_frames: Option<u64>,
+    pub duplicated_frames: Option<u64>,
+    /// Estimated wall-clock seconds until the job finishes.
+    pub eta_seconds: Option<f64>,
+}
+
+impl ProgressPayload {
+    /// Builds the payload for a progress block from a step that starts
+    /// `offset` seconds into a job of `total` seconds.
+    pub fn from_progress(job_id: Uuid, progress: &FfmpegProgress, offset: f64, total: f64) -> Self {
+        let seconds = offset + progress.out_time.unwrap_or(0.0);
+
+        Self {
+            job_id: job_id.to_string(),
+            seconds,
+            percent: calculate_progress_percentage(seconds, total),
+            frame: progress.frame,
+            fps: progress.fps,
+            bitrate_kbps: progress.bitrate_kbps,
+            total_size: progress.total_size,
+            speed: progress.speed,
+            dropped_frames: progress.drop_frames,
+            duplicated_frames: progress.dup_frames,
+            eta_seconds: if total > 0.0 { progress.eta(total - seconds) } else { None },
+        }
+    }
+}
+
+/// How a running job is stopped when it is cancelled.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CancelMode {
+    /// Kill ffmpeg and delete the partial output.
+    #[default]
+    Abort,
+    /// Send `q` to ffmpeg so the muxer finalizes the file, and keep what was
+    /// encoded so far.
+    FinishEarly,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+    /// Not run because the output file already existed.
+    Skipped,
+    /// The app exited while the job was still running.
+    Interrupted,
+}
+
+/// How a job ended, as recorded in the job history.
+#[derive(Debug, Clone)]
+pub struct JobOutcome {
+    pub status: JobStatus,
+    pub exit_code: Option<i32>,
+    pub error: Option<String>,
+    pub stderr_tail: Vec<String>,
+    /// How a cancelled job was stopped.
+    pub cancel_mode: Option<CancelMode>,
+    /// Where the output was saved, when it was kept. Differs from the job's
+    /// `output_file` when the name was taken by then and got numbered.
+    pub output_file: Option<PathBuf>,
+}
+
+impl JobOutcome {
+    pub fn failed(error: String) -> Self {
+        Self {
+            status: JobStatus::Failed,
+            exit_code: None,
+            error: Some(error),
+            stderr_tail: Vec::new(),
+            cancel_mode: None,
+            output_file: None,
+        }
+    }
+
+    pub fn cancelled(mode: CancelMode, stderr_tail: Vec<String>) -> Self {
+        Self {
+            status: JobStatus::Cancelled,
+            exit_code: None,
+            error: None,
+            stderr_tail,
+            cancel_mode: Some(mode),
+            output_file: None,
+        }
+    }
+
+    /// Whether the job's output file should be moved into place.
+    pub fn keeps_output(&self) -> bool {
+        self.status == JobStatus::Completed || self.cancel_mode == Some(CancelMode::FinishEarly)
+    }
+}
+
+// Extracted pure functions for testing
+pub fn calculate_progress_percentage(current_seconds: f64, duration: f64) -> f64 {
+    if duration > 0.0 {
+        (current_seconds / duration * 100.0).min(100.0)
+    } else {
+        0.0
+    }
+}
+
+/// One ffmpeg invocation within a job.
+#[derive(Debug, Clone)]
+pub struct FfmpegStep {
+    pub args: Vec<String>,
+    /// Seconds of media this step processes, used to weight combined progress.
+    pub duration: f64,
+}
+
+/// The ffmpeg invocations that make up a job, run in order.
+#[derive(Debug, Default)]
+pub struct JobPlan {
+    pub steps: Vec<FfmpegStep>,
+    /// Scratch directory for intermediate files, removed when the job ends.
+    pub work_dir: Option<PathBuf>,
+}
+
+impl JobPlan {
+    fn single(args: Vec<String>, duration: f64) -> Self {
+        Self {
+            steps: vec![FfmpegStep { args, duration }],
+            work_dir: None,
+        }
+    }
+
+    pub fn total_duration(&self) -> f64 {
+        self.steps.iter().map(|step| step.duration).sum()
+    }
+}
+
+/// `clip.mp4` becomes `clip.partial-<job id prefix>.mp4` in the same
+/// directory, so the rename is atomic and ffmpeg still picks the muxer from
+/// the extension.
+pub fn partial_output_path(output: &Path, job_id: Uuid) -> PathBuf {
+    let stem = output.file_stem().map(|stem| stem.to_string_lossy()).unwrap_or_default();
+    let id = job_id.simple().to_string();
+    let tag = format!("partial-{}", &id[..8]);
+
+    let file_name = match output.extension() {
+        Some(ext) => format!("{}.{}.{}", stem, tag, ext.to_string_lossy()),
+        None => format!("{}.{}", stem, tag),
+    };
+    output.with_file_name(file_name)
+}
+
+/// Moves a finished partial file into place and returns where it landed. A
+/// file that appeared at `output` after the job was queued is replaced under
+/// `Overwrite`, sidestepped with the next free number under `Increment`, and
+/// otherwise left alone while the job fails.
+pub fn commit_output(partial: &Path, output: &Path, policy: CollisionPolicy) -> Result<PathBuf, String> {
+    let target = naming::resolve_collision(output, policy, Path::exists)?
+        .ok_or_else(|| format!("Output file already exists: {}", output.display()))?;
+
+    std::fs::rename(partial, &target).map_err(|e| format!("Failed to move output into place: {}", e))?;
+    Ok(target)
+}
+
+fn remove_partial_output(partial: &Path) {
+    match std::fs::remove_file(partial) {
+        Ok(()) => log::info!("Removed partial output {:?}", partial),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => log::warn!("Failed to remove partial output {:?}: {}", partial, e),
+    }
+}
+
+/// A job planned against a partial file next to its output, which replaces
+/// `output_file` only once every step has succeeded.
+#[derive(Debug)]
+pub struct PreparedJob {
+    pub plan: JobPlan,
+    partial: PathBuf,
+    output: PathBuf,
+    policy: CollisionPolicy,
+}
+
+impl PreparedJob {
+    pub async fn prepare(job_id: Uuid, params: &ProcessVideoParams) -> Result<Self, String> {
+        let output = PathBuf::from(&params.output_file);
+        let partial = partial_output_path(&output, job_id);
+        let partial_params = ProcessVideoParams {
+            output_file: partial.to_string_lossy().to_string(),
+            ..params.clone()
+        };
+
+        Ok(Self {
+            plan: plan_job(job_id, &partial_params).await?,
+            partial,
+            output,
+            policy: params.collision_policy,
+        })
+    }
+
+    /// Removes the scratch directory, then moves the partial file into place
+    /// or deletes it depending on how the run ended.
+    pub fn finish(self, job_id: Uuid, result: Result<JobOutcome, String>) -> Result<JobOutcome, String> {
+        if let Some(ref work_dir) = self.plan.work_dir {
+            if let Err(e) = std::fs::remove_dir_all(work_dir) {
+                log::warn!("Failed to remove work directory {:?}: {}", work_dir, e);
+            }
+        }
+
+        match result {
+            Ok(outcome) if outcome.keeps_output() => match commit_output(&self.partial, &self.output, self.policy) {
+                Ok(target) => {
+                    if target != self.output {
+                        log::info!("Output of job {} already existed, saved as {:?}", job_id, target);
+                    }
+                    Ok(JobOutcome {
+                        output_file: Some(target),
+                        ..outcome
+                    })
+                }
+                Err(e) => {
+                    remove_partial_output(&self.partial);
+                    if outcome.status == JobStatus::Completed {
+                        Ok(JobOutcome::failed(e))
+                    } else {
+                        log::warn!("Could not keep partial output of job {}: {}", job_id, e);
+                        Ok(JobOutcome::cancelled(CancelMode::Abort, outcome.stderr_tail))
+                    }
+                }
+            },
+            other => {
+                remove_partial_output(&self.partial);
+                other
+            }
+        }
+    }
+}
+
+pub async fn plan_job(job_id: Uuid, params: &ProcessVideoParams) -> Result<JobPlan, String> {
+    if !params.concat_inputs.is_empty() {
+        return concat::plan_concat(job_id, params).await;
+    }
+
+    if !params.segments.is_empty() {
+        return plan_segments(job_id, params).await;
+    }
+
+    if params.trim_mode == TrimMode::Smart {
+        return plan_smart_cut(job_id, params).await;
+    }
+
+    if params.target_size_mb.is_some() {
+        return two_pass::plan_two_pass(job_id, params).await;
+    }
+
+    let args = build_ffmpeg_args(params)?;
+
+    // Calculate total duration for progress percentage
+    let duration = match probe::probe_duration(&params.input_file).await {
+        Ok(source_duration) => output_duration(params, source_duration),
+        Err(e) => {
+            log::warn!("Could not probe duration of {}: {}", params.input_file, e);
+            (params.end_time.unwrap_or(0.0) - params.start_time.unwrap_or(0.0)).max(0.0)
+        }
+    };
+
+    Ok(JobPlan::single(args, duration))
+}
+
+/// Seconds of media the export produces from a source of `source_duration`
+/// seconds, after applying the trim range or segment list.
+pub fn output_duration(params: &ProcessVideoParams, source_duration: f64) -> f64 {
+    if !params.segments.is_empty() {
+        return segments::total_duration(&segments::kept_ranges(&params.segments, source_duration));
+    }
+
+    let start = params.start_time.unwrap_or(0.0).min(source_duration);
+    let end = params.end_time.map_or(source_duration, |end| end.min(source_duration));
+    (end - start).max(0.0)
+}
+
+pub fn validate_inputs(params: &ProcessVideoParams) -> Result<(), String> {
+    if !Path::new(&params.input_file).exists() {
+        return Err("Input file does not exist".to_string());
+    }
+
+    if !params.concat_inputs.is_empty() {
+        if let Some(missing) = params.concat_inputs.iter().find(|file| !Path::new(file).exists()) {
+            return Err(format!("Input file does not exist: {}", missing));
+        }
+
+        let trims = params.start_time.is_some() || params.end_time.is_some() || !params.segments.is_empty();
+        if trims || params.trim_mode != TrimMode::Accurate {
+            return Err("Trimming is not supported when concatenating files".to_string());
+        }
+
+        if params.subtitle_file.is_some() {
+            return Err("Subtitles cannot be burned in when concatenating files".to_string());
+        }
+
+        if !params.subtitle_tracks.is_empty() {
+            return Err("Subtitle tracks cannot be added when concatenating files".to_string());
+        }
+    }
+
+    // Validate output extension
+    let output_ext = Path::new(&params.output_file)
+        .extension()
+        .and_then(|s| s.to_str())
+        .unwrap_or("");
+
+    let container = Container::from_extension(output_ext).ok_or_else(|| {
+        format!(
+            "Invalid output extension: {}. Supported formats: {}",
+            output_ext,
+            SUPPORTED_EXTENSIONS.join(", ")
+        )
+    })?;
+
+    if let (Some(start), Some(end)) = (params.start_time, params.end_time) {
+        if end <= start {
+            return Err("End time must be after start time".to_string());
+        }
+    }
+
+    if params.trim_mode == TrimMode::Fast {
+        if params.subtitle_file.is_some() {
+            return Err("Fast trim copies streams and cannot burn in subtitles".to_string());
+        }
+
+        if params.encoding.as_ref().is_some_and(|encoding| *encoding != stream_copy_profile()) {
+            return Err("Fast trim copies streams; encoder settings cannot be used".to_string());
+        }
+    }
+
+    if !params.segments.is_empty() {
+        if params.start_time.is_some() || params.end_time.is_some() {
+            return Err("Use either a start/end time or a segment list, not both".to_string());
+        }
+
+        if params.trim_mode == TrimMode::Smart {
+            return Err("Smart cut supports a single range; use fast or accurate trim for segment lists".to_string());
+        }
+
+        if !params.subtitle_tracks.is_empty() {
+            return Err("Subtitle tracks cannot be added to a segment list cut".to_string());
+        }
+
+        segments::validate_segments(&params.segments)?;
+    }
+
+    if params.trim_mode == TrimMode::Smart {
+        if params.start_time.is_none() || params.end_time.is_none() {
+            return Err("Smart cut requires both a start and an end time".to_string());
+        }
+
+        if params.subtitle_file.is_some() {
+            return Err("Smart cut copies most of the video and cannot burn in subtitles".to_string());
+        }
+
+        if !params.subtitle_tracks.is_empty() {
+            return Err("Smart cut cannot add subtitle tracks; use fast or accurate trim".to_string());
+        }
+
+        if params.encoding.is_some() {
+            return Err("Smart cut matches the source encoding; encoder settings cannot be used".to_string());
+        }
+    }
+
+    let encoding = params.effective_encoding();
+
+    if let Some(ref sub_file) = params.subtitle_file {
+        if !Path::new(sub_file).exists() {
+            return Err("Subtitle file does not exist".to_string());
+        }
+
+        if encoding.video.codec == VideoCodec::Copy {
+            return Err("Burning in subtitles requires re-encoding the video. Choose a video codec other than copy".to_string());
+        }
+
+        if let Some(ref style) = params.subtitle_style {
+            style.validate()?;
+        }
+    }
+
+    subtitles::validate_tracks(&params.subtitle_tracks, container)?;
+
+    let copies_streams = encoding.video.codec == VideoCodec::Copy || encoding.audio.codec == AudioCodec::Copy;
+    if !params.segments.is_empty() && params.trim_mode == TrimMode::Accurate && copies_streams {
+        return Err("Accurate segment cuts re-encode the video; use fast trim to copy streams".to_string());
+    }
+
+    two_pass::validate_target_size(params, &encoding)?;
+    encoding.validate()?;
+    container.check_encoding(&encoding)?;
+
+    Ok(())
+}
+
+pub fn build_ffmpeg_args(params: &ProcessVideoParams) -> Result<Vec<String>, String> {
+    if !params.segments.is_empty() || !params.concat_inputs.is_empty() {
+        return Err("Segment lists and concat jobs are planned when the job starts".to_string());
+    }
+
+    match params.trim_mode {
+        TrimMode::Fast => return build_stream_copy_args(params),
+        TrimMode::Smart => {
+            return Err("Smart cut runs several ffmpeg passes and is planned when the job starts".to_string())
+        }
+        TrimMode::Accurate => {}
+    }
+
+    let mut args = vec!["-i".to_string(), params.input_file.clone()];
+
+    // Every input comes before -ss so the trim applies to the output as a whole
+    for track in &params.subtitle_tracks {
+        args.push("-i".to_string());
+        args.push(track.file.clone());
+    }
+
+    if let Some(start) = params.start_time {
+        args.push("-ss".to_string());
+        args.push(start.to_string());
+    }
+
+    if let Some(end) = params.end_time {
+        args.push("-to".to_string());
+        args.push(end.to_string());
+    }
+
+    if let Some(filter) = subtitles_filter(params)? {
+        args.push("-vf".to_string());
+        args.push(filter);
+    }
+
+    args.extend(params.effective_encoding().to_args());
+
+    if !params.subtitle_tracks.is_empty() {
+        // Explicit maps replace ffmpeg's default pick of one stream per type
+        args.extend(["-map", "0:v:0?", "-map", "0:a:0?"].map(String::from));
+        args.extend(subtitles::track_args(&params.subtitle_tracks, output_container(params)?, 1)?);
+    }
+
+    args.push("-y".to_string()); // Overwrite output file if exists
+    args.push(params.output_file.clone());
+
+    Ok(args)
+}
+
+/// Lossless trim: seek on the input side so ffmpeg jumps straight to the
+/// nearest keyframe, then copy packets without decoding.
+fn build_stream_copy_args(params: &ProcessVideoParams) -> Result<Vec<String>, String> {
+    let mut seek = Vec::new();
+
+    if let Some(start) = params.start_time {
+        seek.push("-ss".to_string());
+        seek.push(start.to_string());
+    }
+
+    if let Some(end) = params.end_time {
+        seek.push("-t".to_string());
+        seek.push((end - params.start_time.unwrap_or(0.0)).to_string());
+    }
+
+    let mut args = seek.clone();
+    args.extend(["-i".to_string(), params.input_file.clone()]);
+
+    // Subtitle files are seeked the same way so their cues stay in sync
+    for track in &params.subtitle_tracks {
+        args.extend(seek.iter().cloned());
+        args.extend(["-i".to_string(), track.file.clone()]);
+    }
+
+    args.extend(["-map", "0:v?", "-map", "0:a?", "-c", "copy"].map(String::from));
+
+    if !params.subtitle_tracks.is_empty() {
+        args.extend(subtitles::track_args(&params.subtitle_tracks, output_container(params)?, 1)?);
+    }
+
+    args.extend([
+        "-avoid_negative_ts".to_string(),
+        "make_zero".to_string(),
+        "-y".to_string(),
+        params.output_file.clone(),
+    ]);
+
+    Ok(args)
+}
+
+fn output_container(params: &ProcessVideoParams) -> Result<Container, String> {
+    Container::from_path(&params.output_file)
+        .ok_or_else(|| format!("Unsupported output format: {}", params.output_file))
+}
+
+/// A section of the trim range and whether it can be stream copied.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SmartCutSegment {
+    pub start: f64,
+    pub end: f64,
+    pub copy: bool,
+}
+
+/// Splits `start..end` into re-encoded edges and a stream-copied middle.
+/// `copy_start` is the first keyframe at or after `start` and `copy_end` the
+/// last keyframe at or before `end`. Without two usable keyframes the whole
+/// range is re-encoded.
+pub fn smart_cut_segments(
+    start: f64,
+    end: f64,
+    copy_start: Option<f64>,
+    copy_end: Option<f64>,
+) -> Vec<SmartCutSegment> {
+    let (k1, k2) = match (copy_start, copy_end) {
+        (Some(k1), Some(k2)) if k1 < k2 && k1 >= start - KEYFRAME_TOLERANCE && k2 <= end + KEYFRAME_TOLERANCE => {
+            (k1, k2)
+        }
+        _ => {
+            return vec![SmartCutSegment {
+                start,
+                end,
+                copy: false,
+            }]
+        }
+    };
+
+    let mut segments = Vec::new();
+    if k1 - start > KEYFRAME_TOLERANCE {
+        segments.push(SmartCutSegment {
+            start,
+            end: k1,
+            copy: false,
+        });
+    }
+    segments.push(SmartCutSegment {
+        start: k1,
+        end: k2,
+        copy: true,
+    });
+    if end - k2 > KEYFRAME_TOLERANCE {
+        segments.push(SmartCutSegment {
+            start: k2,
+            end,
+            copy: false,
+        });
+    }
+    segments
+}
+
+/// Encoder arguments that reproduce the source video stream's profile,
+/// level and pixel format, so re-encoded edges can be concatenated with
+/// copied packets. Sources whose parameters cannot all be matched are refused.
+pub fn smart_cut_encoder_args(stream: &StreamInfo) -> Result<Vec<String>, String> {
+    let codec = stream.codec_name.as_deref().unwrap_or("unknown");
+    let (_, encoder) = SMART_CUT_ENCODERS
+        .iter()
+        .find(|(name, _)| *name == codec)
+        .ok_or_else(|| format!("Smart cut does not support {} video; use fast or accurate trim instead", codec))?;
+
+    let unmatched = |what: &str| format!("Smart cut cannot match the source's {}; use fast or accurate trim instead", what);
+    let profile = stream
+        .profile
+        .as_deref()
+        .and_then(|profile| {
+            SMART_CUT_PROFILES
+                .iter()
+                .find(|(name, source, _)| *name == codec && *source == profile)
+        })
+        .map(|(_, _, profile)| *profile)
+        .ok_or_else(|| unmatched("profile"))?;
+    let level = stream.level.ok_or_else(|| unmatched("level"))?;
+    let pix_fmt = stream.pix_fmt.as_deref().ok_or_else(|| unmatched("pixel format"))?;
+
+    let mut args: Vec<String> = encoder.iter().map(|arg| arg.to_string()).collect();
+    args.extend(["-profile:v", profile, "-pix_fmt", pix_fmt].map(String::from));
+
+    // repeat-headers writes the parameter sets before every keyframe
+    if codec == "h264" {
+        args.extend(["-level:v".to_string(), codec_level(level, 10)]);
+        args.extend(["-x264-params", "repeat-headers=1"].map(String::from));
+    } else {
+        args.push("-x265-params".to_string());
+        args.push(format!("level-idc={}:repeat-headers=1", codec_level(level, 30)));
+    }
+
+    Ok(args)
+}
+
+/// Formats an ffprobe level as the encoder expects it, e.g. 41 with a scale
+/// of 10 (H.264) or 123 with a scale of 30 (HEVC) as `4.1`.
+fn codec_level(level: u32, scale: u32) -> String {
+    let (major, minor) = (level / scale, level % scale * 10 / scale);
+    if minor == 0 {
+        major.to_string()
+    } else {
+        format!("{}.{}", major, minor)
+    }
+}
+
+/// Quotes a path for an ffmpeg concat demuxer list.
+pub fn concat_list_entry(path: &Path) -> String {
+    format!("file '{}'", path.to_string_lossy().replace('\'', "'\\''"))
+}
+
+/// Builds the ffmpeg steps for a smart cut: one step per segment writing into
+/// `work_dir`, then a final step joining the video pieces with the source audio.
+pub fn build_smart_cut_steps(
+    params: &ProcessVideoParams,
+    segments: &[SmartCutSegment],
+    encoder_args: &[String],
+    work_dir: &Path,
+) -> (Vec<FfmpegStep>, String) {
+    let mut steps = Vec::new();
+    let mut list = String::new();
+
+    for (index, segment) in segments.iter().enumerate() {
+        let segment_file = work_dir.join(format!("segment_{}.{}", index, SMART_CUT_SEGMENT_EXTENSION));
+        let duration = segment.end - segment.start;
+        let seek = if segment.copy {
+            segment.start + SMART_CUT_SEEK_EPSILON
+        } else {
+            segment.start
+        };
+
+        let mut args = vec![
+            "-ss".to_string(),
+            seek.to_string(),
+            "-i".to_string(),
+            params.input_file.clone(),
+            "-t".to_string(),
+            duration.to_string(),
+            "-map".to_string(),
+            "0:v:0".to_string(),
+        ];
+        if segment.copy {
+            args.extend(["-c".to_string(), "copy".to_string()]);
+        } else {
+            args.extend(encoder_args.iter().cloned());
+        }
+        args.extend([
+            "-an".to_string(),
+            "-y".to_string(),
+            segment_file.to_string_lossy().to_string(),
+        ]);
+
+        steps.push(FfmpegStep { args, duration });
+        list.push_str(&concat_list_entry(&segment_file));
+        list.push('\n');
+    }
+
+    let start = segments.first().map(|s| s.start).unwrap_or(0.0);
+    let end = segments.last().map(|s| s.end).unwrap_or(0.0);
+    let list_file = work_dir.join("segments.txt");
+
+    steps.push(FfmpegStep {
+        args: vec![
+            "-f".to_string(),
+            "concat".to_string(),
+            "-safe".to_string(),
+            "0".to_string(),
+            "-i".to_string(),
+            list_file.to_string_lossy().to_string(),
+            "-ss".to_string(),
+            start.to_string(),
+            "-t".to_string(),
+            (end - start).to_string(),
+            "-i".to_string(),
+            params.input_file.clone(),
+            "-map".to_string(),
+            "0:v:0".to_string(),
+            "-map".to_string(),
+            "1:a?".to_string(),
+            "-c".to_string(),
+            "copy".to_string(),
+            "-avoid_negative_ts".to_string(),
+            "make_zero".to_string(),
+            "-y".to_string(),
+            params.output_file.clone(),
+        ],
+        duration: end - start,
+    });
+
+    (steps, list)
+}
+
+/// Probes the source for its video codec and the keyframes nearest the cut
+/// points, then lays out the smart-cut steps in a scratch directory.
+async fn plan_smart_cut(job_id: Uuid, params: &ProcessVideoParams) -> Result<JobPlan, String> {
+    let start = params.start_time.ok_or("Smart cut requires a start time")?;
+    let end = params.end_time.ok_or("Smart cut requires an end time")?;
+
+    let info = probe::probe_media_info(&params.input_file).await?;
+    let stream = info
+        .first_stream("video")
+        .ok_or_else(|| "Smart cut requires a video stream".to_string())?;
+    let encoder_args = smart_cut_encoder_args(stream)?;
+
+    let head = probe::probe_keyframes(&params.input_file, start, start + KEYFRAME_SEARCH_WINDOW).await?;
+    let tail = probe::probe_keyframes(&params.input_file, end - KEYFRAME_SEARCH_WINDOW, end).await?;
+    let copy_start = head.iter().copied().find(|&t| t >= start - KEYFRAME_TOLERANCE);
+    let copy_end = tail.iter().copied().rev().find(|&t| t <= end + KEYFRAME_TOLERANCE);
+
+    let segments = smart_cut_segments(start, end, copy_start, copy_end);
+    log::info!("Smart cut plan for job {}: {:?}", job_id, segments);
+
+    let work_dir = create_work_dir(job_id)?;
+    let (steps, list) = build_smart_cut_steps(params, &segments, &encoder_args, &work_dir);
+    std::fs::write(work_dir.join("segments.txt"), list)
+        .map_err(|e| format!("Failed to write concat list: {}", e))?;
+
+    Ok(JobPlan {
+        steps,
+        work_dir: Some(work_dir),
+    })
+}
+
+/// Creates a per-job scratch directory for intermediate files.
+pub fn create_work_dir(job_id: Uuid) -> Result<PathBuf, String> {
+    let work_dir = std::env::temp_dir().join(format!("ffmpeg-gui-{}", job_id));
+    std::fs::create_dir_all(&work_dir)
+        .map_err(|e| format!("Failed to create work directory: {}", e))?;
+    Ok(work_dir)
+}
+
+/// Builds a `filter_complex` that trims each kept range out of the source and
+/// joins them with `concat`. Subtitles are burned in before trimming so they
+/// stay in sync with the source timeline.
+pub fn build_segment_filter_args(
+    params: &ProcessVideoParams,
+    ranges: &[KeptRange],
+    has_audio: bool,
+) -> Result<Vec<String>, String> {
+    let encoding = params.effective_encoding();
+    let with_audio = has_audio && encoding.audio.codec != AudioCodec::None;
+    let count = ranges.len();
+
+    let mut graph = Vec::new();
+
+    let subtitles = subtitles_filter(params)?
+        .map(|filter| format!("{},", filter))
+        .unwrap_or_default();
+    let video_splits: String = (0..count).map(|i| format!("[sv{}]", i)).collect();
+    graph.push(format!("[0:v]{}split={}{}", subtitles, count, video_splits));
+
+    if with_audio {
+        let audio_splits: String = (0..count).map(|i| format!("[sa{}]", i)).collect();
+        graph.push(format!("[0:a]asplit={}{}", count, audio_splits));
+    }
+
+    let mut concat_inputs = String::new();
+    for (i, range) in ranges.iter().enumerate() {
+        graph.push(format!(
+            "[sv{}]trim=start={}:end={},setpts=PTS-STARTPTS[v{}]",
+            i, range.start, range.end, i
+        ));
+        concat_inputs.push_str(&format!("[v{}]", i));
+
+        if with_audio {
+            graph.push(format!(
+                "[sa{}]atrim=start={}:end={},asetpts=PTS-STARTPTS[a{}]",
+                i, range.start, range.end, i
+            ));
+            concat_inputs.push_str(&format!("[a{}]", i));
+        }
+    }
+
+    if with_audio {
+        graph.push(format!("{}concat=n={}:v=1:a=1[outv][outa]", concat_inputs, count));
+    } else {
+        graph.push(format!("{}concat=n={}:v=1:a=0[outv]", concat_inputs, count));
+    }
+
+    let mut args = vec![
+        "-i".to_string(),
+        params.input_file.clone(),
+        "-filter_complex".to_string(),
+        graph.join(";"),
+        "-map".to_string(),
+        "[outv]".to_string(),
+    ];
+    if with_audio {
+        args.push("-map".to_string());
+        args.push("[outa]".to_string());
+    }
+
+    args.extend(encoding.to_args());
+    args.push("-y".to_string());
+    args.push(params.output_file.clone());
+
+    Ok(args)
+}
+
+/// Concat demuxer list that reads each kept range straight from the source
+/// using `inpoint`/`outpoint`.
+pub fn build_segment_concat_list(input_file: &str, ranges: &[KeptRange]) -> String {
+    let entry = concat_list_entry(Path::new(input_file));
+    ranges
+        .iter()
+        .map(|range| format!("{}\ninpoint {}\noutpoint {}\n", entry, range.start, range.end))
+        .collect()
+}
+
+fn build_segment_copy_args(params: &ProcessVideoParams, list_file: &Path) -> Vec<String> {
+    vec![
+        "-f".to_string(),
+        "concat".to_string(),
+        "-safe".to_string(),
+        "0".to_string(),
+        "-i".to_string(),
+        list_file.to_string_lossy().to_string(),
+        "-map".to_string(),
+        "0:v?".to_string(),
+        "-map".to_string(),
+        "0:a?".to_string(),
+        "-c".to_string(),
+        "copy".to_string(),
+        "-avoid_negative_ts".to_string(),
+        "make_zero".to_string(),
+        "-y".to_string(),
+        params.output_file.clone(),
+    ]
+}
+
+/// Resolves the cut list against the probed source, then exports the kept
+/// ranges with the concat demuxer (fast trim) or trim/concat filters.
+async fn plan_segments(job_id: Uuid, params: &ProcessVideoParams) -> Result<JobPlan, String> {
+    let info = probe::probe_media_info(&params.input_file).await?;
+    let duration = info
+        .duration
+        .ok_or_else(|| "Could not determine the video duration".to_string())?;
+    let has_audio = info.first_stream("audio").is_some();
+
+    let ranges = segments::kept_ranges(&params.segments, duration);
+    if ranges.is_empty() {
+        return Err("The segment list leaves nothing to export".to_string());
+    }
+    let total = output_duration(params, duration);
+
+    if params.trim_mode != TrimMode::Fast {
+        return Ok(JobPlan::single(
+            build_segment_filter_args(params, &ranges, has_audio)?,
+            total,
+        ));
+    }
+
+    let work_dir = create_work_dir(job_id)?;
+    let list_file = work_dir.join("segments.txt");
+    std::fs::write(&list_file, build_segment_concat_list(&params.input_file, &ranges))
+        .map_err(|e| format!("Failed to write concat list: {}", e))?;
+
+    Ok(JobPlan {
+        steps: vec![FfmpegStep {
+            args: build_segment_copy_args(params, &list_file),
+            duration: total,
+        }],
+        work_dir: Some(work_dir),
+    })
+}
+
+// FFmpeg's filter syntax treats ':' as an option separator and '\' as an escape character.
+// To support Windows drive letters (e.g. C:\) and paths with spaces/quotes, we normalise
+// the path for the subtitles filter:
+//   - Replace backslashes with forward slashes so we don't need to double-escape them.
+//   - Escape drive-letter colons so they aren't interpreted as option separators.
+//   - Quote the value so the filtergraph parser keeps ',' ';' and brackets.
+fn quote_subtitle_path(path: &str) -> String {
+    quote_filter_value(&path.replace('\\', "/"))
+}
+
+/// Quotes a filter option value. ffmpeg unescapes filtergraphs twice: once
+/// when splitting the graph into filters and once when splitting a filter's
+/// options. `\`, `:` and `'` are escaped for the option level, and the result
+/// is single-quoted for the graph level, where a quote can only be written by
+/// closing the quotes, escaping it and reopening them. Backslashes are kept
+/// literal, so this also suits font names and style strings.
+fn quote_filter_value(value: &str) -> String {
+    let escaped = value.replace('\\', r"\\").replace(':', r"\:").replace('\'', r"\'");
+    format!("'{}'", escaped.replace('\'', r"'\''"))
+}
+
+/// The `subtitles` filter that burns in `subtitle_file`, with the style
+/// overrides and fonts folder when a style is set.
+fn subtitles_filter(params: &ProcessVideoParams) -> Result<Option<String>, String> {
+    let Some(ref subtitle_file) = params.subtitle_file else {
+        return Ok(None);
+    };
+
+    // Escape path for ffmpeg filter (handle Windows paths, drive-letter colons, and quotes).
+    let mut filter = format!("subtitles=filename={}", quote_subtitle_path(subtitle_file));
+
+    if let Some(ref style) = params.subtitle_style {
+        let force_style = style.force_style()?;
+        if !force_style.is_empty() {
+            filter.push_str(&format!(":force_style={}", quote_filter_value(&force_style)));
+        }
+        if let Some(fonts_dir) = style.fonts_dir() {
+            filter.push_str(&format!(":fontsdir={}", quote_subtitle_path(&fonts_dir.to_string_lossy())));
+        }
+        if let Some(ref charset) = style.charset {
+            filter.push_str(&format!(":charenc={}", charset));
+        }
+    }
+
+    Ok(Some(filter))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::encoding::{AudioCodec, AudioSettings, RateControl, VideoSettings};
+    use tempfile::{tempdir, NamedTempFile};
+    use std::io::Write;
+
+    #[test]
+    fn test_calculate_progress_percentage_normal() {
+        let percent = calculate_progress_percentage(30.0, 100.0);
+        assert_eq!(percent, 30.0);
+    }
+
+    #[test]
+    fn test_calculate_progress_percentage_zero_duration() {
+        let percent = calculate_progress_percentage(50.0, 0.0);
+        assert_eq!(percent, 0.0);
+    }
+
+    #[test]
+    fn test_calculate_progress_percentage_exceeds_100() {
+        let percent = calculate_progress_percentage(120.0, 100.0);
+        assert_eq!(percent, 100.0);
+    }
+
+    #[test]
+    fn test_calculate_progress_percentage_at_duration() {
+        let percent = calculate_progress_percentage(100.0, 100.0);
+        assert_eq!(percent, 100.0);
+    }
+
+    #[test]
+    fn test_validate_inputs_with_nonexistent_input_file() {
+        let params = ProcessVideoParams {
+            input_file: "/nonexistent/path.mp4".to_string(),
+            output_file: "/output/file.mp4".to_string(),
+            start_time: None,
+            end_time: None,
+            subtitle_file: None,
+            ..Default::default()
+        };
+
+        let result = validate_inputs(&params);
+        assert!(result.is_err());
+        assert_eq!(result.unwrap_err(), "Input file does not exist");
+    }
+
+    #[test]
+    fn test_validate_inputs_with_invalid_output_extension() {
+        let mut input = NamedTempFile::new().unwrap();
+        writeln!(input, "test data").unwrap();
+
+        let params = ProcessVideoParams {
+            input_file: input.path().to_str().unwrap().to_string(),
+            output_file: "/output/file.txt".to_string(),
+            start_time: None,
+            end_time: None,
+            subtitle_file: None,
+            ..Default::default()
+        };
+
+        let result = validate_inputs(&params);
+        assert!(result.is_err());
+        assert!(result.unwrap_err().contains("Invalid output extension"));
+    }
+
+    #[test]
+    fn test_validate_inputs_with_valid_extensions() {
+        let mut input = NamedTempFile::new().unwrap();
+        writeln!(input, "test data").unwrap();
+
+        let extensions = ["mp4", "avi", "mov", "mkv", "webm"];
+
+        for ext in extensions {
+            let params = ProcessVideoParams {
+                input_file: input.path().to_str().unwrap().to_string(),
+                output_file: format!("/output/file.{}", ext),
+                start_time: None,
+                end_time: None,
+                subtitle_file: None,
+                ..Default::default()
+            };
+
+            assert!(validate_inputs(&params).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_validate_inputs_with_missing_subtitle_file() {
+        let mut input = NamedTempFile::new().unwrap();
+        writeln!(input, "test data").unwrap();
+
+        let params = ProcessVideoParams {
+            input_file: input.path().to_str().unwrap().to_string(),
+            output_file: "/output/file.mp4".to_string(),
+            start_time: None,
+            end_time: None,
+            subtitle_file: Some("/nonexistent/subtitle.srt".to_string()),
+            ..Default::default()
+        };
+
+        let result = validate_inputs(&params);
+        assert!(result.is_err());
+        assert_eq!(result.unwrap_err(), "Subtitle file does not exist");
+    }
+
+    #[test]
+    fn test_build_ffmpeg_args_basic() {
+        let params = ProcessVideoParams {
+            input_file: "/input/video.mp4".to_string(),
+            output_file: "/output/video.mp4".to_string(),
+            start_time: None,
+            end_time: None,
+            subtitle_file: None,
+            ..Default::default()
+        };
+
+        let args = build_ffmpeg_args(&params).unwrap();
+
+        assert_eq!(args[0], "-i");
+        assert_eq!(args[1], "/input/video.mp4");
+        assert!(args.contains(&"-c:v".to_string()));
+        assert!(args.contains(&"libx264".to_string()));
+        assert!(args.contains(&"-c:a".to_string()));
+        assert!(args.contains(&"aac".to_string()));
+        assert!(args.contains(&"-y".to_string()));
+        assert_eq!(args.last().unwrap(), "/output/video.mp4");
+    }
+
+    #[test]
+    fn test_build_ffmpeg_args_with_trim() {
+        let params = ProcessVideoParams {
+            input_file: "/input/video.mp4".to_string(),
+            output_file: "/output/video.mp4".to_string(),
+            start_time: Some(10.5),
+            end_time: Some(60.0),
+            subtitle_file: None,
+            ..Default::default()
+        };
+
+        let args = build_ffmpeg_args(&params).unwrap();
+
+        let ss_idx = args.iter().position(|x| x == "-ss").unwrap();
+        let to_idx = args.iter().position(|x| x == "-to").unwrap();
+
+        assert_eq!(args[ss_idx + 1], "10.5");
+        assert_eq!(args[to_idx + 1], "60");
+    }
+
+    #[test]
+    fn test_build_ffmpeg_args_with_windows_path_escaping() {
+        let params = ProcessVideoParams {
+            input_file: "/input/video.mp4".to_string(),
+            output_file: "/output/video.mp4".to_string(),
+            start_time: None,
+            end_time: None,
+            subtitle_file: Some("C:\\Users\\Name\\subtitles.srt".to_string()),
+            ..Default::default()
+        };
+
+        let args = build_ffmpeg_args(&params).unwrap();
+
+        let vf_idx = args.iter().position(|x| x == "-vf").unwrap();
+        let filter = &args[vf_idx + 1];
+
+        // Should escape drive-letter colon and wrap as filename=
+        assert!(filter.starts_with("subtitles=filename='"));
+        assert!(filter.contains("C\\:/Users/Name/subtitles.srt"));
+    }
+
+    #[test]
+    fn test_build_ffmpeg_args_with_subtitles() {
+        let params = ProcessVideoParams {
+            input_file: "/input/video.mp4".to_string(),
+            output_file: "/output/video.mp4".to_string(),
+            start_time: None,
+            end_time: None,
+            subtitle_file: Some("/path/to/subtitle.srt".to_string()),
+            ..Default::default()
+        };
+
+        let args = build_ffmpeg_args(&params).unwrap();
+
+        assert!(args.contains(&"-vf".to_string()));
+        let vf_idx = args.iter().position(|x| x == "-vf").unwrap();
+        let filter = &args[vf_idx + 1];
+        assert!(filter.starts_with("subtitles=filename='"));
+    }
+
+    #[test]
+    fn test_build_ffmpeg_args_with_spaces_and_quotes() {
+        let params = ProcessVideoParams {
+            input_file: "/input/video.mp4".to_string(),
+            output_file: "/output/video.mp4".to_string(),
+            start_time: None,
+            end_time: None,
+            subtitle_file: Some("D:\\My Subs\\O'Connor\\show.srt".to_string()),
+            ..Default::default()
+        };
+
+        let args = build_ffmpeg_args(&params).unwrap();
+
+        let vf_idx = args.iter().position(|x| x == "-vf").unwrap();
+        let filter = &args[vf_idx + 1];
+        assert_eq!(
+            filter,
+            "subtitles=filename='D\\:/My Subs/O\\'\\''Connor/show.srt'"
+        );
+    }
+
+    #[test]
+    fn test_build_ffmpeg_args_with_subtitle_style() {
+        let params = ProcessVideoParams {
+            input_file: "/input/video.mp4".to_string(),
+            output_file: "/output/video.mp4".to_string(),
+            subtitle_file: Some("/subs/show.srt".to_string()),
+            subtitle_style: Some(SubtitleStyle {
+                font_name: Some("Font: O'Brien".to_string()),
+                font_file: Some("/fonts/obrien.ttf".to_string()),
+                font_size: Some(32),
+                alignment: Some(2),
+                charset: Some("CP1252".to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let args = build_ffmpeg_args(&params).unwrap();
+
+        let vf_idx = args.iter().position(|x| x == "-vf").unwrap();
+        assert_eq!(
+            args[vf_idx + 1],
+            "subtitles=filename='/subs/show.srt'\
+             :force_style='Fontname=Font\\: O\\'\\''Brien,Fontsize=32,Alignment=2'\
+             :fontsdir='/fonts':charenc=CP1252"
+        );
+
+        // An empty style leaves the filter as it was
+        let params = ProcessVideoParams {
+            subtitle_style: Some(SubtitleStyle::default()),
+            ..params
+        };
+        let args = build_ffmpeg_args(&params).unwrap();
+        assert!(args.contains(&"subtitles=filename='/subs/show.srt'".to_string()));
+    }
+
+    #[test]
+    fn test_build_ffmpeg_args_with_encoding_profile() {
+        let params = ProcessVideoParams {
+            input_file: "/input/video.mp4".to_string(),
+            output_file: "/output/video.mkv".to_string(),
+            encoding: Some(EncodingProfile {
+                video: VideoSettings {
+                    codec: VideoCodec::Libx265,
+                    rate_control: Some(RateControl::Crf { value: 24 }),
+                    ..Default::default()
+                },
+                audio: AudioSettings {
+                    codec: AudioCodec::Libopus,
+                    bitrate_kbps: Some(128),
+                    ..Default::default()
+                },
+            }),
+            ..Default::default()
+        };
+
+        let args = build_ffmpeg_args(&params).unwrap();
+
+        let cv_idx = args.iter().position(|x| x == "-c:v").unwrap();
+        assert_eq!(args[cv_idx + 1], "libx265");
+        let crf_idx = args.iter().position(|x| x == "-crf").unwrap();
+        assert_eq!(args[crf_idx + 1], "24");
+        let ca_idx = args.iter().position(|x| x == "-c:a").unwrap();
+        assert_eq!(args[ca_idx + 1], "libopus");
+        assert!(!args.contains(&"libx264".to_string()));
+    }
+
+    #[test]
+    fn test_validate_inputs_rejects_subtitles_with_stream_copy() {
+        let mut input = NamedTempFile::new().unwrap();
+        writeln!(input, "test data").unwrap();
+        let subtitle = NamedTempFile::new().unwrap();
+
+        let params = ProcessVideoParams {
+            input_file: input.path().to_str().unwrap().to_string(),
+            output_file: "/output/file.mp4".to_string(),
+            subtitle_file: Some(subtitle.path().to_str().unwrap().to_string()),
+            encoding: Some(EncodingProfile {
+                video: VideoSettings {
+                    codec: VideoCodec::Copy,
+                    ..Default::default()
+                },
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let result = validate_inputs(&params);
+        assert!(result.unwrap_err().contains("requires re-encoding"));
+    }
+
+    #[test]
+    fn test_validate_inputs_rejects_invalid_encoding() {
+        let mut input = NamedTempFile::new().unwrap();
+        writeln!(input, "test data").unwrap();
+
+        let params = ProcessVideoParams {
+            input_file: input.path().to_str().unwrap().to_string(),
+            output_file: "/output/file.mp4".to_string(),
+            encoding: Some(EncodingProfile {
+                video: VideoSettings {
+                    preset: Some("warp-speed".to_string()),
+                    ..Default::default()
+                },
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        assert!(validate_inputs(&params).unwrap_err().contains("Invalid preset"));
+    }
+
+    #[test]
+    fn test_build_ffmpeg_args_uses_container_defaults() {
+        let params = ProcessVideoParams {
+            input_file: "/input/video.mp4".to_string(),
+            output_file: "/output/video.webm".to_string(),
+            ..Default::default()
+        };
+
+        let args = build_ffmpeg_args(&params).unwrap();
+
+        let cv_idx = args.iter().position(|x| x == "-c:v").unwrap();
+        assert_eq!(args[cv_idx + 1], "libvpx-vp9");
+        let ca_idx = args.iter().position(|x| x == "-c:a").unwrap();
+        assert_eq!(args[ca_idx + 1], "libopus");
+    }
+
+    #[test]
+    fn test_validate_inputs_rejects_codec_not_allowed_in_container() {
+        let mut input = NamedTempFile::new().unwrap();
+        writeln!(input, "test data").unwrap();
+
+        let params = ProcessVideoParams {
+            input_file: input.path().to_str().unwrap().to_string(),
+            output_file: "/output/file.webm".to_string(),
+            encoding: Some(EncodingProfile::default()),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            validate_inputs(&params).unwrap_err(),
+            "H.264 is not allowed in WebM; choose VP9/AV1 + Opus"
+        );
+    }
+
+    #[test]
+    fn test_build_ffmpeg_args_fast_trim() {
+        let params = ProcessVideoParams {
+            input_file: "/input/video.mp4".to_string(),
+            output_file: "/output/video.mp4".to_string(),
+            start_time: Some(10.0),
+            end_time: Some(25.5),
+            trim_mode: TrimMode::Fast,
+            ..Default::default()
+        };
+
+        let args = build_ffmpeg_args(&params).unwrap();
+
+        // Seeking must happen before -i to avoid decoding
+        let ss_idx = args.iter().position(|x| x == "-ss").unwrap();
+        let i_idx = args.iter().position(|x| x == "-i").unwrap();
+        assert!(ss_idx < i_idx);
+        assert_eq!(args[ss_idx + 1], "10");
+
+        let t_idx = args.iter().position(|x| x == "-t").unwrap();
+        assert_eq!(args[t_idx + 1], "15.5");
+
+        let c_idx = args.iter().position(|x| x == "-c").unwrap();
+        assert_eq!(args[c_idx + 1], "copy");
+        assert!(!args.contains(&"libx264".to_string()));
+        assert_eq!(args.last().unwrap(), "/output/video.mp4");
+    }
+
+    #[test]
+    fn test_validate_inputs_fast_trim_rejects_subtitles() {
+        let mut input = NamedTempFile::new().unwrap();
+        writeln!(input, "test data").unwrap();
+        let subtitle = NamedTempFile::new().unwrap();
+
+        let params = ProcessVideoParams {
+            input_file: input.path().to_str().unwrap().to_string(),
+            output_file: "/output/file.mp4".to_string(),
+            subtitle_file: Some(subtitle.path().to_str().unwrap().to_string()),
+            trim_mode: TrimMode::Fast,
+            ..Default::default()
+        };
+
+        assert!(validate_inputs(&params).unwrap_err().contains("cannot burn in subtitles"));
+    }
+
+    #[test]
+    fn test_build_ffmpeg_args_with_subtitle_tracks() {
+        let params = ProcessVideoParams {
+            input_file: "/input/video.mp4".to_string(),
+            output_file: "/output/video.mp4".to_string(),
+            start_time: Some(5.0),
+            subtitle_tracks: vec![SubtitleTrack {
+                file: "/subs/en.srt".to_string(),
+                language: Some("eng".to_string()),
+                default: true,
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        let args = build_ffmpeg_args(&params).unwrap();
+
+        // The subtitle input must not be affected by the output-side -ss
+        let sub_idx = args.iter().position(|x| x == "/subs/en.srt").unwrap();
+        let ss_idx = args.iter().position(|x| x == "-ss").unwrap();
+        assert!(sub_idx < ss_idx);
+
+        let joined = args.join(" ");
+        assert!(joined.contains("-map 0:v:0? -map 0:a:0? -map 1:s:0"));
+        assert!(joined.contains("-c:s:0 mov_text -metadata:s:s:0 language=eng -disposition:s:0 default"));
+        assert!(!args.contains(&"-vf".to_string()));
+
+        let fast = ProcessVideoParams {
+            trim_mode: TrimMode::Fast,
+            end_time: Some(20.0),
+            ..params
+        };
+        let args = build_ffmpeg_args(&fast).unwrap();
+        assert_eq!(
+            args[..12].join(" "),
+            "-ss 5 -t 15 -i /input/video.mp4 -ss 5 -t 15 -i /subs/en.srt"
+        );
+        assert!(args.join(" ").contains("-c copy -map 1:s:0 -c:s:0 mov_text"));
+    }
+
+    #[test]
+    fn test_validate_inputs_subtitle_tracks_with_stream_copy() {
+        let mut input = NamedTempFile::new().unwrap();
+        writeln!(input, "test data").unwrap();
+        let dir = tempdir().unwrap();
+        let subtitle = dir.path().join("en.srt");
+        std::fs::write(&subtitle, "").unwrap();
+
+        let params = ProcessVideoParams {
+            input_file: input.path().to_str().unwrap().to_string(),
+            output_file: "/output/file.mkv".to_string(),
+            subtitle_tracks: vec![SubtitleTrack {
+                file: subtitle.to_str().unwrap().to_string(),
+                ..Default::default()
+            }],
+            trim_mode: TrimMode::Fast,
+            ..Default::default()
+        };
+        assert!(validate_inputs(&params).is_ok());
+
+        let avi = ProcessVideoParams {
+            output_file: "/output/file.avi".to_string(),
+            ..params.clone()
+        };
+        assert!(validate_inputs(&avi).unwrap_err().contains("AVI"));
+
+        let smart = ProcessVideoParams {
+            trim_mode: TrimMode::Smart,
+            start_time: Some(1.0),
+            end_time: Some(2.0),
+            ..params
+        };
+        assert!(validate_inputs(&smart).unwrap_err().contains("subtitle tracks"));
+    }
+
+    #[test]
+    fn test_validate_inputs_fast_trim_rejects_encoder_settings() {
+        let mut input = NamedTempFile::new().unwrap();
+        writeln!(input, "test data").unwrap();
+
+        let params = ProcessVideoParams {
+            input_file: input.path().to_str().unwrap().to_string(),
+            output_file: "/output/file.webm".to_string(),
+            encoding: Some(EncodingProfile::default()),
+            trim_mode: TrimMode::Fast,
+            ..Default::default()
+        };
+
+        assert!(validate_inputs(&params).unwrap_err().contains("encoder settings"));
+
+        let params = ProcessVideoParams {
+            encoding: None,
+            ..params
+        };
+        assert!(validate_inputs(&params).is_ok());
+    }
+
+    #[test]
+    fn test_validate_inputs_rejects_inverted_range() {
+        let mut input = NamedTempFile::new().unwrap();
+        writeln!(input, "test data").unwrap();
+
+        let params = ProcessVideoParams {
+            input_file: input.path().to_str().unwrap().to_string(),
+            output_file: "/output/file.mp4".to_string(),
+            start_time: Some(30.0),
+            end_time: Some(10.0),
+            ..Default::default()
+        };
+
+        assert_eq!(validate_inputs(&params).unwrap_err(), "End time must be after start time");
+    }
+
+    fn video_stream(codec: &str, profile: Option<&str>) -> StreamInfo {
+        StreamInfo {
+            index: 0,
+            codec_type: "video".to_string(),
+            codec_name: Some(codec.to_string()),
+            codec_long_name: None,
+            profile: profile.map(str::to_string),
+            level: Some(41),
+            width: Some(1920),
+            height: Some(1080),
+            frame_rate: Some(30.0),
+            pix_fmt: Some("yuv420p".to_string()),
+            sample_rate: None,
+            channels: None,
+            channel_layout: None,
+            bit_rate: None,
+            duration: None,
+            rotation: None,
+            language: None,
+            title: None,
+            is_default: true,
+            is_forced: false,
+            tags: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_smart_cut_segments_with_edges() {
+        let segments = smart_cut_segments(5.5, 60.2, Some(8.0), Some(56.0));
+
+        assert_eq!(
+            segments,
+            vec![
+                SmartCutSegment { start: 5.5, end: 8.0, copy: false },
+                SmartCutSegment { start: 8.0, end: 56.0, copy: true },
+                SmartCutSegment { start: 56.0, end: 60.2, copy: false },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_smart_cut_segments_start_on_keyframe() {
+        let segments = smart_cut_segments(8.0, 60.2, Some(8.0), Some(56.0));
+
+        assert_eq!(segments.len(), 2);
+        assert!(segments[0].copy);
+        assert_eq!(segments[0].start, 8.0);
+    }
+
+    #[test]
+    fn test_smart_cut_segments_within_single_gop() {
+        let segments = smart_cut_segments(5.5, 7.0, Some(8.0), Some(4.0));
+
+        assert_eq!(
+            segments,
+            vec![SmartCutSegment { start: 5.5, end: 7.0, copy: false }]
+        );
+    }
+
+    #[test]
+    fn test_smart_cut_segments_without_keyframes() {
+        let segments = smart_cut_segments(5.5, 60.0, None, None);
+        assert_eq!(segments.len(), 1);
+        assert!(!segments[0].copy);
+    }
+
+    #[test]
+    fn test_smart_cut_encoder_args_match_source() {
+        let args = smart_cut_encoder_args(&video_stream("h264", Some("High"))).unwrap();
+        assert_eq!(
+            args.join(" "),
+            "-c:v libx264 -crf 16 -preset medium -profile:v high -pix_fmt yuv420p \
+             -level:v 4.1 -x264-params repeat-headers=1"
+        );
+
+        let hevc = StreamInfo {
+            level: Some(153),
+            pix_fmt: Some("yuv420p10le".to_string()),
+            ..video_stream("hevc", Some("Main 10"))
+        };
+        let args = smart_cut_encoder_args(&hevc).unwrap();
+        assert_eq!(
+            args.join(" "),
+            "-c:v libx265 -crf 18 -preset medium -profile:v main10 -pix_fmt yuv420p10le \
+             -x265-params level-idc=5.1:repeat-headers=1"
+        );
+    }
+
+    #[test]
+    fn test_smart_cut_encoder_args_refuses_unmatched_sources() {
+        let result = smart_cut_encoder_args(&video_stream("prores", None));
+        assert!(result.unwrap_err().contains("does not support prores"));
+
+        // Encoders that cannot reproduce the source's parameters
+        for codec in ["av1", "vp9", "mpeg4"] {
+            assert!(smart_cut_encoder_args(&video_stream(codec, Some("Main"))).is_err());
+        }
+
+        let unknown_profile = video_stream("h264", Some("High 4:4:4 Intra"));
+        assert!(smart_cut_encoder_args(&unknown_profile).unwrap_err().contains("profile"));
+
+        let no_level = StreamInfo {
+            level: None,
+            ..video_stream("h264", Some("Main"))
+        };
+        assert!(smart_cut_encoder_args(&no_level).unwrap_err().contains("level"));
+    }
+
+    #[test]
+    fn test_build_smart_cut_steps() {
+        let params = ProcessVideoParams {
+            input_file: "/input/video.mp4".to_string(),
+            output_file: "/output/video.mp4".to_string(),
+            start_time: Some(5.5),
+            end_time: Some(60.0),
+            trim_mode: TrimMode::Smart,
+            ..Default::default()
+        };
+        let segments = smart_cut_segments(5.5, 60.0, Some(8.0), Some(56.0));
+        let encoder_args = vec!["-c:v".to_string(), "libx264".to_string()];
+
+        let (steps, list) = build_smart_cut_steps(&params, &segments, &encoder_args, Path::new("/tmp/work"));
+
+        // Three segments plus the final join
+        assert_eq!(steps.len(), 4);
+        assert!(steps[0].args.contains(&"libx264".to_string()));
+        assert!(steps[1].args.contains(&"copy".to_string()));
+        assert!(!steps[1].args.contains(&"libx264".to_string()));
+        assert!((steps[0].duration - 2.5).abs() < 1e-9);
+
+        let join = &steps[3].args;
+        assert_eq!(join[0..2], ["-f".to_string(), "concat".to_string()]);
+        assert_eq!(join.last().unwrap(), "/output/video.mp4");
+
+        assert_eq!(list.lines().count(), 3);
+        assert!(list.starts_with("file '/tmp/work/segment_0.ts'"));
+    }
+
+    #[test]
+    fn test_concat_list_entry_escapes_quotes() {
+        let entry = concat_list_entry(Path::new("/tmp/O'Connor/a.mkv"));
+        assert_eq!(entry, "file '/tmp/O'\\''Connor/a.mkv'");
+    }
+
+    #[test]
+    fn test_validate_inputs_smart_cut_requires_range() {
+        let mut input = NamedTempFile::new().unwrap();
+        writeln!(input, "test data").unwrap();
+
+        let params = ProcessVideoParams {
+            input_file: input.path().to_str().unwrap().to_string(),
+            output_file: "/output/file.mp4".to_string(),
+            start_time: Some(5.0),
+            trim_mode: TrimMode::Smart,
+            ..Default::default()
+        };
+
+        assert!(validate_inputs(&params).unwrap_err().contains("both a start and an end"));
+    }
+
+    fn segment_params(trim_mode: TrimMode) -> ProcessVideoParams {
+        ProcessVideoParams {
+            input_file: "/input/video.mp4".to_string(),
+            output_file: "/output/video.mp4".to_string(),
+            trim_mode,
+            segments: vec![CutSegment {
+                start: 10.0,
+                end: 20.0,
+                action: segments::SegmentAction::Remove,
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_build_segment_filter_args() {
+        let params = segment_params(TrimMode::Accurate);
+        let ranges = [
+            KeptRange { start: 0.0, end: 10.0 },
+            KeptRange { start: 20.0, end: 30.5 },
+        ];
+
+        let args = build_segment_filter_args(&params, &ranges, true).unwrap();
+
+        let graph_idx = args.iter().position(|x| x == "-filter_complex").unwrap();
+        let graph = &args[graph_idx + 1];
+        assert!(graph.starts_with("[0:v]split=2[sv0][sv1];[0:a]asplit=2[sa0][sa1]"));
+        assert!(graph.contains("[sv1]trim=start=20:end=30.5,setpts=PTS-STARTPTS[v1]"));
+        assert!(graph.contains("[sa0]atrim=start=0:end=10,asetpts=PTS-STARTPTS[a0]"));
+        assert!(graph.ends_with("[v0][a0][v1][a1]concat=n=2:v=1:a=1[outv][outa]"));
+        assert!(args.contains(&"[outa]".to_string()));
+        assert!(args.contains(&"libx264".to_string()));
+        assert_eq!(args.last().unwrap(), "/output/video.mp4");
+    }
+
+    #[test]
+    fn test_build_segment_filter_args_without_audio() {
+        let params = ProcessVideoParams {
+            subtitle_file: Some("/subs/a.srt".to_string()),
+            ..segment_params(TrimMode::Accurate)
+        };
+        let ranges = [KeptRange { start: 5.0, end: 10.0 }];
+
+        let args = build_segment_filter_args(&params, &ranges, false).unwrap();
+
+        let graph_idx = args.iter().position(|x| x == "-filter_complex").unwrap();
+        let graph = &args[graph_idx + 1];
+        assert!(graph.starts_with("[0:v]subtitles=filename='/subs/a.srt',split=1[sv0]"));
+        assert!(graph.ends_with("[v0]concat=n=1:v=1:a=0[outv]"));
+        assert!(!args.contains(&"[outa]".to_string()));
+    }
+
+    #[test]
+    fn test_build_segment_concat_list() {
+        let ranges = [
+            KeptRange { start: 0.0, end: 10.0 },
+            KeptRange { start: 20.0, end: 30.0 },
+        ];
+
+        let list = build_segment_concat_list("/input/video.mp4", &ranges);
+
+        assert_eq!(
+            list,
+            "file '/input/video.mp4'\ninpoint 0\noutpoint 10\nfile '/input/video.mp4'\ninpoint 20\noutpoint 30\n"
+        );
+    }
+
+    #[test]
+    fn test_build_ffmpeg_args_rejects_segment_list() {
+        assert!(build_ffmpeg_args(&segment_params(TrimMode::Accurate)).is_err());
+    }
+
+    #[test]
+    fn test_validate_inputs_segments() {
+        let mut input = NamedTempFile::new().unwrap();
+        writeln!(input, "test data").unwrap();
+        let input_file = input.path().to_str().unwrap().to_string();
+
+        let with_range = ProcessVideoParams {
+            input_file: input_file.clone(),
+            start_time: Some(1.0),
+            ..segment_params(TrimMode::Accurate)
+        };
+        assert!(validate_inputs(&with_range).unwrap_err().contains("not both"));
+
+        let smart = ProcessVideoParams {
+            input_file: input_file.clone(),
+            ..segment_params(TrimMode::Smart)
+        };
+        assert!(validate_inputs(&smart).unwrap_err().contains("single range"));
+
+        let fast = ProcessVideoParams {
+            input_file,
+            ..segment_params(TrimMode::Fast)
+        };
+        assert!(validate_inputs(&fast).is_ok());
+    }
+
+    #[test]
+    fn test_validate_inputs_concat() {
+        let mut input = NamedTempFile::new().unwrap();
+        writeln!(input, "test data").unwrap();
+        let input_file = input.path().to_str().unwrap().to_string();
+
+        let missing = ProcessVideoParams {
+            input_file: input_file.clone(),
+            output_file: "/output/joined.mp4".to_string(),
+            concat_inputs: vec!["/nonexistent/b.mp4".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(
+            validate_inputs(&missing).unwrap_err(),
+            "Input file does not exist: /nonexistent/b.mp4"
+        );
+
+        let trimmed = ProcessVideoParams {
+            concat_inputs: vec![input_file.clone()],
+            start_time: Some(1.0),
+            ..missing
+        };
+        assert!(validate_inputs(&trimmed).unwrap_err().contains("Trimming is not supported"));
+    }
+
+    #[test]
+    fn test_progress_payload_offsets_multi_step_jobs() {
+        let progress = FfmpegProgress {
+            out_time: Some(10.0),
+            speed: Some(2.0),
+            frame: Some(300),
+            ..Default::default()
+        };
+
+        let payload = ProgressPayload::from_progress(Uuid::nil(), &progress, 20.0, 60.0);
+
+        assert_eq!(payload.seconds, 30.0);
+        assert_eq!(payload.percent, 50.0);
+        assert_eq!(payload.frame, Some(300));
+        assert_eq!(payload.eta_seconds, Some(15.0));
+    }
+
+    #[test]
+    fn test_progress_payload_without_duration_has_no_eta() {
+        let progress = FfmpegProgress {
+            out_time: Some(10.0),
+            speed: Some(2.0),
+            ..Default::default()
+        };
+
+        let payload = ProgressPayload::from_progress(Uuid::nil(), &progress, 0.0, 0.0);
+
+        assert_eq!(payload.percent, 0.0);
+        assert_eq!(payload.eta_seconds, None);
+    }
+
+    #[test]
+    fn test_output_duration_without_end_time() {
+        let mut params = ProcessVideoParams {
+            input_file: "/input/video.mp4".to_string(),
+            output_file: "/output/video.mp4".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(output_duration(&params, 120.0), 120.0);
+
+        params.start_time = Some(30.0);
+        assert_eq!(output_duration(&params, 120.0), 90.0);
+
+        params.end_time = Some(200.0);
+        assert_eq!(output_duration(&params, 120.0), 90.0);
+    }
+
+    #[test]
+    fn test_output_duration_with_segments() {
+        let params = segment_params(TrimMode::Fast);
+        assert_eq!(output_duration(&params, 100.0), 90.0);
+    }
+
+    #[test]
+    fn test_build_ffmpeg_args_with_start_time_only() {
+        let params = ProcessVideoParams {
+            input_file: "/input/video.mp4".to_string(),
+            output_file: "/output/video.mp4".to_string(),
+            start_time: Some(12.0),
+            ..Default::default()
+        };
+
+        let args = build_ffmpeg_args(&params).unwrap();
+
+        let ss_idx = args.iter().position(|x| x == "-ss").unwrap();
+        assert_eq!(args[ss_idx + 1], "12");
+        assert!(!args.contains(&"-to".to_string()));
+    }
+
+    #[test]
+    fn test_partial_output_path_keeps_directory_and_extension() {
+        let job_id = Uuid::parse_str("1a2b3c4d-0000-0000-0000-000000000000").unwrap();
+        assert_eq!(
+            partial_output_path(Path::new("/videos/clip.final.mp4"), job_id),
+            PathBuf::from("/videos/clip.final.partial-1a2b3c4d.mp4")
+        );
+    }
+
+    #[test]
+    fn test_commit_output_respects_collision_policy() {
+        let dir = tempdir().unwrap();
+        let partial = dir.path().join("clip.partial-1a2b3c4d.mp4");
+        let output = dir.path().join("clip.mp4");
+        std::fs::write(&output, "old").unwrap();
+
+        std::fs::write(&partial, "new").unwrap();
+        assert!(commit_output(&partial, &output, CollisionPolicy::Fail).is_err());
+        assert_eq!(std::fs::read_to_string(&output).unwrap(), "old");
+
+        assert!(commit_output(&partial, &output, CollisionPolicy::Skip).is_err());
+        assert_eq!(std::fs::read_to_string(&output).unwrap(), "old");
+
+        assert_eq!(
+            commit_output(&partial, &output, CollisionPolicy::Increment).unwrap(),
+            dir.path().join("clip_1.mp4")
+        );
+        assert_eq!(std::fs::read_to_string(dir.path().join("clip_1.mp4")).unwrap(), "new");
+        assert_eq!(std::fs::read_to_string(&output).unwrap(), "old");
+
+        std::fs::write(&partial, "newer").unwrap();
+        assert_eq!(commit_output(&partial, &output, CollisionPolicy::Overwrite).unwrap(), output);
+        assert_eq!(std::fs::read_to_string(&output).unwrap(), "newer");
+        assert!(!partial.exists());
+    }
+
+    #[test]
+    fn test_only_completed_or_finished_early_jobs_keep_output() {
+        assert!(!JobOutcome::failed("boom".to_string()).keeps_output());
+        assert!(!JobOutcome::cancelled(CancelMode::Abort, Vec::new()).keeps_output());
+        assert!(JobOutcome::cancelled(CancelMode::FinishEarly, Vec::new()).keeps_output());
+    }
+}
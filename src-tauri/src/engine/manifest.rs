@@ -0,0 +1,351 @@
+use crate::engine::job::ProcessVideoParams;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::path::Path;
+
+/// Separates the files in a CSV `concat_inputs` cell.
+pub const CSV_LIST_SEPARATOR: char = ';';
+
+/// CSV columns whose header starts with this are notes and not read.
+pub const CSV_IGNORED_COLUMN_PREFIX: char = '#';
+
+/// Short CSV headers accepted in place of the field names.
+const CSV_COLUMN_ALIASES: &[(&str, &str)] = &[
+    ("input", "input_file"),
+    ("output", "output_file"),
+    ("start", "start_time"),
+    ("end", "end_time"),
+    ("subtitles", "subtitle_file"),
+    ("subtitle", "subtitle_file"),
+];
+
+/// A batch of `process_video` jobs. Each job only lists the fields that differ
+/// from `defaults`.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct Manifest {
+    #[serde(default)]
+    pub defaults: Map<String, Value>,
+    pub jobs: Vec<Map<String, Value>>,
+    /// The line each job starts on, for formats that report one.
+    #[serde(skip)]
+    pub lines: Vec<usize>,
+}
+
+/// Manifests may also be a bare list of jobs.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ManifestFile {
+    Jobs(Vec<Map<String, Value>>),
+    Manifest(Manifest),
+}
+
+impl From<ManifestFile> for Manifest {
+    fn from(file: ManifestFile) -> Self {
+        match file {
+            ManifestFile::Jobs(jobs) => Manifest {
+                jobs,
+                ..Manifest::default()
+            },
+            ManifestFile::Manifest(manifest) => manifest,
+        }
+    }
+}
+
+/// Why one job of a manifest was not queued.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RowError {
+    /// The job's line in a CSV manifest, otherwise its 1-based position
+    /// among the jobs.
+    pub row: usize,
+    pub error: String,
+}
+
+impl Manifest {
+    /// How errors refer to the job at `index`.
+    pub fn row(&self, index: usize) -> usize {
+        self.lines.get(index).copied().unwrap_or(index + 1)
+    }
+
+    /// Builds each job from `base`, then the manifest defaults, then the job's
+    /// own fields, each replacing the one before field by field. Jobs without
+    /// a `batch_index` are numbered by their row, and output names are
+    /// templates unless a job sets `output_template` to false.
+    pub fn expand(&self, base: &Map<String, Value>) -> Vec<Result<ProcessVideoParams, String>> {
+        self.jobs
+            .iter()
+            .enumerate()
+            .map(|(index, job)| {
+                let mut fields = base.clone();
+                fields.extend(self.defaults.clone());
+                fields.extend(job.clone());
+                fields.entry("batch_index").or_insert_with(|| Value::from(index + 1));
+                fields.entry("output_template").or_insert(Value::Bool(true));
+
+                normalize_fields(&mut fields)?;
+                serde_json::from_value(Value::Object(fields)).map_err(|e| format!("Invalid job: {}", e))
+            })
+            .collect()
+    }
+}
+
+/// Parses numbers written as text, as spreadsheets export them. Trim times
+/// may also be timecodes.
+fn normalize_fields(fields: &mut Map<String, Value>) -> Result<(), String> {
+    for (name, value) in fields.iter_mut() {
+        let Value::String(text) = value else {
+            continue;
+        };
+
+        let parsed = match name.as_str() {
+            "start_time" | "end_time" => Value::from(parse_timecode(text)?),
+            "target_size_mb" => Value::from(
+                text.trim()
+                    .parse::<f64>()
+                    .map_err(|_| format!("Invalid target_size_mb: {}", text))?,
+            ),
+            "batch_index" => Value::from(
+                text.trim()
+                    .parse::<u64>()
+                    .map_err(|_| format!("Invalid batch_index: {}", text))?,
+            ),
+            "output_template" => Value::from(
+                text.trim()
+                    .to_lowercase()
+                    .parse::<bool>()
+                    .map_err(|_| format!("Invalid output_template: {}", text))?,
+            ),
+            _ => continue,
+        };
+        *value = parsed;
+    }
+    Ok(())
+}
+
+/// Accepts plain seconds or `[HH:]MM:SS[.ms]`.
+pub fn parse_timecode(value: &str) -> Result<f64, String> {
+    let invalid = || format!("Invalid time: {}", value);
+    let parts: Vec<&str> = value.split(':').collect();
+    if parts.len() > 3 {
+        return Err(invalid());
+    }
+
+    let mut seconds = 0.0;
+    for part in parts {
+        let part: f64 = part.trim().parse().map_err(|_| invalid())?;
+        if !part.is_finite() || part < 0.0 {
+            return Err(invalid());
+        }
+        seconds = seconds * 60.0 + part;
+    }
+    Ok(seconds)
+}
+
+pub fn parse_json_manifest(contents: &str) -> Result<Manifest, String> {
+    serde_json::from_str::<ManifestFile>(contents)
+        .map(Manifest::from)
+        .map_err(|e| format!("Failed to parse manifest: {}", e))
+}
+
+pub fn parse_yaml_manifest(contents: &str) -> Result<Manifest, String> {
+    serde_yaml::from_str::<ManifestFile>(contents)
+        .map(Manifest::from)
+        .map_err(|e| format!("Failed to parse manifest: {}", e))
+}
+
+/// One job per row. Header names are field names, matched without regard to
+/// case, spaces or dashes; empty cells are left to the defaults. Any other
+/// header is rejected unless it starts with `CSV_IGNORED_COLUMN_PREFIX`.
+pub fn parse_csv_manifest(contents: &str) -> Result<Manifest, String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .flexible(true)
+        .from_reader(contents.as_bytes());
+
+    let columns: Vec<String> = reader
+        .headers()
+        .map_err(|e| format!("Failed to read CSV header: {}", e))?
+        .iter()
+        .map(csv_field_name)
+        .collect::<Result<_, _>>()?;
+
+    let mut manifest = Manifest::default();
+    for record in reader.records() {
+        let record = record.map_err(|e| format!("Failed to read CSV: {}", e))?;
+        if record.iter().all(str::is_empty) {
+            continue;
+        }
+
+        let mut job = Map::new();
+        for (column, cell) in columns.iter().zip(record.iter()) {
+            if column.is_empty() || cell.is_empty() {
+                continue;
+            }
+
+            let value = if column == "concat_inputs" {
+                cell.split(CSV_LIST_SEPARATOR)
+                    .map(str::trim)
+                    .filter(|file| !file.is_empty())
+                    .map(Value::from)
+                    .collect()
+            } else {
+                Value::from(cell)
+            };
+            job.insert(column.clone(), value);
+        }
+        manifest.jobs.push(job);
+        manifest
+            .lines
+            .push(record.position().map_or(manifest.jobs.len() + 1, |position| position.line() as usize));
+    }
+
+    Ok(manifest)
+}
+
+/// The field a CSV header names, or an empty name for columns to skip.
+fn csv_field_name(header: &str) -> Result<String, String> {
+    let header = header.trim();
+    if header.is_empty() || header.starts_with(CSV_IGNORED_COLUMN_PREFIX) {
+        return Ok(String::new());
+    }
+
+    let name = header.to_lowercase().replace([' ', '-'], "_");
+    let name = CSV_COLUMN_ALIASES
+        .iter()
+        .find(|(alias, _)| *alias == name)
+        .map_or(name, |(_, field)| field.to_string());
+
+    let Ok(Value::Object(fields)) = serde_json::to_value(ProcessVideoParams::default()) else {
+        return Err("Failed to list job fields".to_string());
+    };
+    if !fields.contains_key(&name) {
+        return Err(format!(
+            "Unknown CSV column \"{}\". Start the header with {} to keep it as a note",
+            header, CSV_IGNORED_COLUMN_PREFIX
+        ));
+    }
+    Ok(name)
+}
+
+/// Reads a manifest, picking the format from the extension: `.json`,
+/// `.yaml`/`.yml` or `.csv`.
+pub fn read_manifest(path: &Path) -> Result<Manifest, String> {
+    let contents = std::fs::read_to_string(path).map_err(|e| format!("Failed to read manifest: {}", e))?;
+    let extension = path
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+    let manifest = match extension.as_str() {
+        "json" => parse_json_manifest(&contents)?,
+        "yaml" | "yml" => parse_yaml_manifest(&contents)?,
+        "csv" => parse_csv_manifest(&contents)?,
+        _ => return Err("Unsupported manifest format. Use .json, .yaml or .csv".to_string()),
+    };
+
+    if manifest.jobs.is_empty() {
+        return Err("Manifest contains no jobs".to_string());
+    }
+    Ok(manifest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::job::TrimMode;
+    use serde_json::json;
+
+    fn fields(value: Value) -> Map<String, Value> {
+        value.as_object().cloned().unwrap()
+    }
+
+    #[test]
+    fn test_expand_layers_defaults_and_numbers_rows() {
+        let manifest = parse_json_manifest(
+            r#"{
+                "defaults": { "trim_mode": "fast", "output_file": "/out/{name}.mp4" },
+                "jobs": [
+                    { "input_file": "/a.mp4", "end_time": 10 },
+                    { "input_file": "/b.mp4", "trim_mode": "accurate", "batch_index": 7 }
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        let base = fields(json!({ "preset": "YouTube", "trim_mode": "smart" }));
+        let jobs: Vec<ProcessVideoParams> = manifest.expand(&base).into_iter().map(Result::unwrap).collect();
+
+        assert_eq!(jobs[0].trim_mode, TrimMode::Fast);
+        assert_eq!(jobs[0].output_file, "/out/{name}.mp4");
+        assert_eq!(jobs[0].preset.as_deref(), Some("YouTube"));
+        assert_eq!(jobs[0].end_time, Some(10.0));
+        assert_eq!(jobs[0].batch_index, Some(1));
+        assert_eq!(jobs[1].trim_mode, TrimMode::Accurate);
+        assert_eq!(jobs[1].batch_index, Some(7));
+    }
+
+    #[test]
+    fn test_expand_reports_each_bad_row() {
+        let manifest = parse_json_manifest(
+            r#"[
+                { "input_file": "/a.mp4", "output_file": "/a_out.mp4" },
+                { "output_file": "/b_out.mp4" },
+                { "input_file": "/c.mp4", "output_file": "/c_out.mp4", "start_time": "1:xx" }
+            ]"#,
+        )
+        .unwrap();
+
+        let jobs = manifest.expand(&Map::new());
+        assert!(jobs[0].is_ok());
+        assert!(jobs[1].as_ref().unwrap_err().contains("input_file"));
+        assert_eq!(jobs[2].as_ref().unwrap_err(), "Invalid time: 1:xx");
+    }
+
+    #[test]
+    fn test_parse_yaml_manifest() {
+        let manifest = parse_yaml_manifest(
+            "defaults:\n  output_file: /out/{name}_{index}.mkv\njobs:\n  - input_file: /a.mp4\n    start_time: '00:01:30'\n",
+        )
+        .unwrap();
+
+        let job = manifest.expand(&Map::new()).remove(0).unwrap();
+        assert_eq!(job.input_file, "/a.mp4");
+        assert_eq!(job.output_file, "/out/{name}_{index}.mkv");
+        assert_eq!(job.start_time, Some(90.0));
+    }
+
+    #[test]
+    fn test_parse_csv_manifest_maps_columns() {
+        let csv = "Input,Output,Start,End,Target Size MB,concat-inputs,# Notes\n\
+                   /a.mp4,/a_out.mp4,00:00:05.5,1:00,,,first take\n\
+                   ,,,,,,\n\
+                   /b.mp4,/b_out.mp4,,,8,/c.mp4; /d.mp4,\n";
+
+        let manifest = parse_csv_manifest(csv).unwrap();
+        assert_eq!(manifest.jobs.len(), 2);
+        assert_eq!(manifest.row(1), 4);
+
+        let jobs: Vec<ProcessVideoParams> = manifest.expand(&Map::new()).into_iter().map(Result::unwrap).collect();
+        assert_eq!(jobs[0].start_time, Some(5.5));
+        assert_eq!(jobs[0].end_time, Some(60.0));
+        assert_eq!(jobs[0].target_size_mb, None);
+        assert_eq!(jobs[1].target_size_mb, Some(8.0));
+        assert_eq!(jobs[1].concat_inputs, vec!["/c.mp4", "/d.mp4"]);
+        assert_eq!(jobs[1].batch_index, Some(2));
+    }
+
+    #[test]
+    fn test_parse_csv_manifest_rejects_unknown_columns() {
+        let error = parse_csv_manifest("input,output,notes\n/a.mp4,/b.mp4,first take\n").unwrap_err();
+        assert!(error.contains("\"notes\""), "{}", error);
+    }
+
+    #[test]
+    fn test_parse_timecode() {
+        assert_eq!(parse_timecode("12.5"), Ok(12.5));
+        assert_eq!(parse_timecode("1:02"), Ok(62.0));
+        assert_eq!(parse_timecode("01:00:01.25"), Ok(3601.25));
+        assert!(parse_timecode("1:2:3:4").is_err());
+        assert!(parse_timecode("-5").is_err());
+        assert!(parse_timecode("").is_err());
+    }
+}
@@ -0,0 +1,18 @@
+pub mod binaries;
+pub mod concat;
+pub mod container;
+pub mod encoding;
+pub mod job;
+pub mod manifest;
+pub mod naming;
+pub mod paths;
+pub mod presets;
+pub mod probe;
+pub mod progress;
+pub mod runner;
+pub mod segments;
+pub mod settings;
+pub mod storage;
+pub mod subtitles;
+pub mod two_pass;
+pub mod watch;
@@ -0,0 +1,320 @@
+use crate::engine::encoding::VideoCodec;
+use crate::engine::job::ProcessVideoParams;
+use crate::engine::probe;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Tokens that may appear in an output file name.
+pub const NAME_TOKENS: &[&str] = &["name", "start", "end", "codec", "height", "date", "index"];
+
+/// What to do when the output file already exists, or another queued job
+/// already writes to it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CollisionPolicy {
+    /// Replace the existing file. The default, since the save dialog already
+    /// asks before overwriting.
+    #[default]
+    Overwrite,
+    /// Append `_1`, `_2`, ... to the file name until it is free.
+    Increment,
+    /// Leave the existing file alone and do not run the job.
+    Skip,
+    /// Reject the job.
+    Fail,
+}
+
+/// Values substituted for the name tokens.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NameContext {
+    /// Input file name without its extension.
+    pub name: String,
+    pub start: f64,
+    pub end: Option<f64>,
+    /// Output video codec, e.g. `libx264`, or the source codec when copying.
+    pub codec: String,
+    /// Display height of the source video.
+    pub height: Option<u32>,
+    pub date: String,
+    /// 1-based position of the job within a batch.
+    pub index: usize,
+}
+
+/// Formats seconds as `HH-MM-SS`, which is safe in file names on every platform.
+pub fn format_time_token(seconds: f64) -> String {
+    let total = seconds.max(0.0) as u64;
+    format!("{:02}-{:02}-{:02}", total / 3600, (total % 3600) / 60, total % 60)
+}
+
+/// Splits `template` into literal text, each followed by the token after it.
+/// `{{` and `}}` stand for literal braces.
+fn parse_template(template: &str) -> Result<Vec<(String, Option<&str>)>, String> {
+    let mut pieces = Vec::new();
+    let mut literal = String::new();
+    let mut rest = template;
+
+    while let Some(brace) = rest.find(['{', '}']) {
+        literal.push_str(&rest[..brace]);
+        let brace_char = &rest[brace..brace + 1];
+        let after = &rest[brace + 1..];
+
+        if after.starts_with(brace_char) || brace_char == "}" {
+            literal.push_str(brace_char);
+            rest = if after.starts_with(brace_char) { &after[1..] } else { after };
+            continue;
+        }
+
+        let close = after.find('}').ok_or_else(|| {
+            format!("Unclosed {{ in output name \"{}\". Write {{{{ for a literal brace", template)
+        })?;
+        let token = &after[..close];
+        if !NAME_TOKENS.contains(&token) {
+            return Err(format!(
+                "Unknown token {{{}}} in output name. Available tokens: {}",
+                token,
+                NAME_TOKENS.iter().map(|t| format!("{{{}}}", t)).collect::<Vec<_>>().join(", ")
+            ));
+        }
+        pieces.push((std::mem::take(&mut literal), Some(token)));
+        rest = &after[close + 1..];
+    }
+
+    literal.push_str(rest);
+    pieces.push((literal, None));
+    Ok(pieces)
+}
+
+/// Names of the tokens used in `template`, in order of appearance.
+pub fn template_tokens(template: &str) -> Result<Vec<&str>, String> {
+    Ok(parse_template(template)?.into_iter().filter_map(|(_, token)| token).collect())
+}
+
+pub fn render_template(template: &str, ctx: &NameContext) -> Result<String, String> {
+    let mut rendered = String::new();
+
+    for (literal, token) in parse_template(template)? {
+        rendered.push_str(&literal);
+        let value = match token {
+            None => continue,
+            Some("name") => ctx.name.clone(),
+            Some("start") => format_time_token(ctx.start),
+            Some("end") => format_time_token(
+                ctx.end.ok_or("The {end} token needs a known end time or duration")?,
+            ),
+            Some("codec") => ctx.codec.clone(),
+            Some("height") => ctx
+                .height
+                .ok_or("The {height} token needs an input with a video stream")?
+                .to_string(),
+            Some("date") => ctx.date.clone(),
+            Some("index") => ctx.index.to_string(),
+            Some(_) => unreachable!("parse_template only returns known tokens"),
+        };
+        rendered.push_str(&value);
+    }
+
+    if rendered.trim().is_empty() {
+        return Err("Output name is empty".to_string());
+    }
+
+    Ok(rendered)
+}
+
+/// Renders the tokens in the file name of `output_file`. The directory part is
+/// used as is.
+pub fn render_output_path(output_file: &str, ctx: &NameContext) -> Result<PathBuf, String> {
+    let path = Path::new(output_file);
+    let file_name = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| format!("Invalid output file: {}", output_file))?;
+
+    Ok(path.with_file_name(render_template(file_name, ctx)?))
+}
+
+/// `clip.mp4` becomes `clip_<n>.mp4`.
+pub fn numbered_path(path: &Path, n: usize) -> PathBuf {
+    let stem = path.file_stem().map(|stem| stem.to_string_lossy()).unwrap_or_default();
+    let file_name = match path.extension() {
+        Some(ext) => format!("{}_{}.{}", stem, n, ext.to_string_lossy()),
+        None => format!("{}_{}", stem, n),
+    };
+    path.with_file_name(file_name)
+}
+
+/// Applies `policy` to `path`. Returns `None` when the job should be skipped.
+pub fn resolve_collision(
+    path: &Path,
+    policy: CollisionPolicy,
+    is_taken: impl Fn(&Path) -> bool,
+) -> Result<Option<PathBuf>, String> {
+    if !is_taken(path) {
+        return Ok(Some(path.to_path_buf()));
+    }
+
+    match policy {
+        CollisionPolicy::Overwrite => Ok(Some(path.to_path_buf())),
+        CollisionPolicy::Skip => Ok(None),
+        CollisionPolicy::Fail => Err(format!("Output file already exists: {}", path.display())),
+        CollisionPolicy::Increment => Ok((1..)
+            .map(|n| numbered_path(path, n))
+            .find(|candidate| !is_taken(candidate))),
+    }
+}
+
+/// Gathers the token values for `params`. The input is only probed when the
+/// template needs something ffprobe reports.
+async fn name_context(params: &ProcessVideoParams, tokens: &[&str]) -> NameContext {
+    let encoding = params.effective_encoding();
+    let copies_video = encoding.video.codec == VideoCodec::Copy;
+
+    let needs_probe = tokens.contains(&"height")
+        || (tokens.contains(&"end") && params.end_time.is_none())
+        || (tokens.contains(&"codec") && copies_video);
+
+    let info = if needs_probe {
+        match probe::probe_media_info(&params.input_file).await {
+            Ok(info) => Some(info),
+            Err(e) => {
+                log::warn!("Could not probe {} for output naming: {}", params.input_file, e);
+                None
+            }
+        }
+    } else {
+        None
+    };
+    let video_stream = info.as_ref().and_then(|info| info.first_stream("video"));
+
+    let codec = match video_stream.and_then(|stream| stream.codec_name.clone()) {
+        Some(source_codec) if copies_video => source_codec,
+        _ => encoding.video.codec.ffmpeg_name().to_string(),
+    };
+
+    // Portrait phone videos are stored landscape with a 90 degree rotation
+    let height = video_stream.and_then(|stream| {
+        let rotated = stream.rotation.is_some_and(|rotation| (rotation.abs() - 90.0).abs() < 1.0);
+        if rotated {
+            stream.width
+        } else {
+            stream.height
+        }
+    });
+
+    NameContext {
+        name: Path::new(&params.input_file)
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        start: params.start_time.unwrap_or(0.0),
+        end: params.end_time.or_else(|| info.as_ref().and_then(|info| info.duration)),
+        codec,
+        height,
+        date: chrono::Local::now().format("%Y-%m-%d").to_string(),
+        index: params.batch_index.unwrap_or(1),
+    }
+}
+
+/// Renders the name tokens in `params.output_file` when it is a template.
+/// Other paths, such as those picked in the save dialog, and templates
+/// without tokens are returned unchanged.
+pub async fn render_output(params: &ProcessVideoParams) -> Result<PathBuf, String> {
+    if !params.output_template {
+        return Ok(PathBuf::from(&params.output_file));
+    }
+
+    let file_name = Path::new(&params.output_file)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or("");
+    let tokens = template_tokens(file_name)?;
+
+    if tokens.is_empty() {
+        return Ok(PathBuf::from(&params.output_file));
+    }
+
+    let ctx = name_context(params, &tokens).await;
+    render_output_path(&params.output_file, &ctx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context() -> NameContext {
+        NameContext {
+            name: "holiday".to_string(),
+            start: 65.4,
+            end: Some(3725.0),
+            codec: "libx264".to_string(),
+            height: Some(1080),
+            date: "2024-05-01".to_string(),
+            index: 3,
+        }
+    }
+
+    #[test]
+    fn test_render_template() {
+        assert_eq!(
+            render_template("{name}_{start}-{end}_{codec}_{height}p_{date}_{index}", &context()).unwrap(),
+            "holiday_00-01-05-01-02-05_libx264_1080p_2024-05-01_3"
+        );
+        assert_eq!(render_template("{name}_{name}", &context()).unwrap(), "holiday_holiday");
+        assert_eq!(render_template("plain", &context()).unwrap(), "plain");
+        assert_eq!(render_template("{{{name}}} {{x}", &context()).unwrap(), "{holiday} {x}");
+        assert_eq!(render_template("clip}", &context()).unwrap(), "clip}");
+    }
+
+    #[test]
+    fn test_render_output_keeps_plain_paths() {
+        for output_file in ["/out/clip {final}.mp4", "/out/clip{.mp4"] {
+            let params = ProcessVideoParams {
+                input_file: "/in/clip.mp4".to_string(),
+                output_file: output_file.to_string(),
+                ..Default::default()
+            };
+            assert_eq!(tokio_test::block_on(render_output(&params)).unwrap(), PathBuf::from(output_file));
+        }
+    }
+
+    #[test]
+    fn test_render_template_errors() {
+        assert!(render_template("{title}", &context()).unwrap_err().contains("Unknown token {title}"));
+        assert!(render_template("{name", &context()).unwrap_err().contains("Unclosed"));
+
+        let no_video = NameContext {
+            height: None,
+            ..context()
+        };
+        assert!(render_template("{height}p", &no_video).is_err());
+    }
+
+    #[test]
+    fn test_render_output_path_keeps_directory() {
+        let rendered = render_output_path("/videos/{date}/{name}_{index}.mkv", &context()).unwrap();
+        assert_eq!(rendered, PathBuf::from("/videos/{date}/holiday_3.mkv"));
+    }
+
+    #[test]
+    fn test_resolve_collision() {
+        let taken = [PathBuf::from("/out/clip.mp4"), PathBuf::from("/out/clip_1.mp4")];
+        let is_taken = |path: &Path| taken.iter().any(|t| t == path);
+        let path = Path::new("/out/clip.mp4");
+
+        assert_eq!(
+            resolve_collision(path, CollisionPolicy::Increment, is_taken).unwrap(),
+            Some(PathBuf::from("/out/clip_2.mp4"))
+        );
+        assert_eq!(
+            resolve_collision(path, CollisionPolicy::Overwrite, is_taken).unwrap(),
+            Some(path.to_path_buf())
+        );
+        assert_eq!(resolve_collision(path, CollisionPolicy::Skip, is_taken).unwrap(), None);
+        assert!(resolve_collision(path, CollisionPolicy::Fail, is_taken).is_err());
+
+        let free = Path::new("/out/other.mp4");
+        assert_eq!(
+            resolve_collision(free, CollisionPolicy::Fail, is_taken).unwrap(),
+            Some(free.to_path_buf())
+        );
+    }
+}
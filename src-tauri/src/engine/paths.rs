@@ -0,0 +1,36 @@
+use std::path::PathBuf;
+
+/// Must match `identifier` in tauri.conf.json, which names the app config
+/// directory.
+pub const APP_IDENTIFIER: &str = "com.ffmpeg-gui.app";
+
+/// The directory Tauri's `app_config_dir` resolves to, for code running
+/// outside the app, such as the CLI.
+pub fn app_config_dir() -> Result<PathBuf, String> {
+    platform_config_dir()
+        .map(|dir| dir.join(APP_IDENTIFIER))
+        .ok_or_else(|| "Could not determine the user config directory".to_string())
+}
+
+#[cfg(windows)]
+fn platform_config_dir() -> Option<PathBuf> {
+    env_dir("APPDATA")
+}
+
+#[cfg(target_os = "macos")]
+fn platform_config_dir() -> Option<PathBuf> {
+    env_dir("HOME").map(|home| home.join("Library").join("Application Support"))
+}
+
+#[cfg(not(any(windows, target_os = "macos")))]
+fn platform_config_dir() -> Option<PathBuf> {
+    env_dir("XDG_CONFIG_HOME").or_else(|| env_dir("HOME").map(|home| home.join(".config")))
+}
+
+/// An absolute path from the environment. Relative values are ignored, as the
+/// XDG spec requires.
+fn env_dir(name: &str) -> Option<PathBuf> {
+    std::env::var_os(name)
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+}
@@ -0,0 +1,308 @@
+use crate::engine::job::ProcessVideoParams;
+use crate::engine::storage;
+use crate::engine::two_pass;
+use crate::engine::container::Container;
+use crate::engine::encoding::{AudioCodec, AudioSettings, EncodingProfile, RateControl, VideoCodec, VideoSettings};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// User presets are stored under this name in the app config directory.
+pub const PRESETS_FILE_NAME: &str = "presets.json";
+
+const MAX_PRESET_NAME_LEN: usize = 64;
+
+/// Named encoder settings that can stand in for inline options in `process_video`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Preset {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Output extension the preset is made for, e.g. `mp4`.
+    pub extension: String,
+    /// `None` uses the container defaults.
+    #[serde(default)]
+    pub encoding: Option<EncodingProfile>,
+    #[serde(default)]
+    pub target_size_mb: Option<f64>,
+    /// Set on the presets shipped with the app, which cannot be edited.
+    #[serde(default, skip_deserializing)]
+    pub built_in: bool,
+}
+
+impl Preset {
+    /// Checks the preset the same way `validate_inputs` checks a job that uses it.
+    pub fn validate(&self) -> Result<(), String> {
+        let name = self.name.trim();
+        if name.is_empty() {
+            return Err("Preset name cannot be empty".to_string());
+        }
+        if name.len() > MAX_PRESET_NAME_LEN {
+            return Err(format!("Preset name must be at most {} characters", MAX_PRESET_NAME_LEN));
+        }
+
+        let container = Container::from_extension(&self.extension)
+            .ok_or_else(|| format!("Preset \"{}\" has an unsupported extension: {}", name, self.extension))?;
+
+        let params = self.apply(ProcessVideoParams {
+            output_file: format!("output.{}", self.extension),
+            ..Default::default()
+        });
+        let encoding = params.effective_encoding();
+
+        two_pass::validate_target_size(&params, &encoding)
+            .and_then(|_| encoding.validate())
+            .and_then(|_| container.check_encoding(&encoding))
+            .map_err(|e| format!("Preset \"{}\": {}", name, e))
+    }
+
+    /// Fills in the preset's options and clears `params.preset`.
+    pub fn apply(&self, params: ProcessVideoParams) -> ProcessVideoParams {
+        ProcessVideoParams {
+            preset: None,
+            encoding: self.encoding.clone(),
+            target_size_mb: self.target_size_mb,
+            ..params
+        }
+    }
+
+    pub fn matches_name(&self, name: &str) -> bool {
+        self.name.trim().eq_ignore_ascii_case(name.trim())
+    }
+}
+
+/// Read-only presets shipped with the app.
+pub fn built_in_presets() -> Vec<Preset> {
+    vec![
+        Preset {
+            name: "YouTube".to_string(),
+            description: Some("H.264 high profile and 192 kbps AAC, as YouTube recommends for uploads".to_string()),
+            extension: "mp4".to_string(),
+            encoding: Some(EncodingProfile {
+                video: VideoSettings {
+                    codec: VideoCodec::Libx264,
+                    rate_control: Some(RateControl::Crf { value: 18 }),
+                    preset: Some("slow".to_string()),
+                    profile: Some("high".to_string()),
+                    pix_fmt: Some("yuv420p".to_string()),
+                    ..Default::default()
+                },
+                audio: AudioSettings {
+                    codec: AudioCodec::Aac,
+                    bitrate_kbps: Some(192),
+                    sample_rate: Some(48000),
+                    ..Default::default()
+                },
+            }),
+            target_size_mb: None,
+            built_in: true,
+        },
+        Preset {
+            name: "Discord (25 MB)".to_string(),
+            description: Some("Two-pass H.264 sized to fit Discord's 25 MB upload limit".to_string()),
+            extension: "mp4".to_string(),
+            encoding: Some(EncodingProfile {
+                video: VideoSettings {
+                    codec: VideoCodec::Libx264,
+                    preset: Some("medium".to_string()),
+                    pix_fmt: Some("yuv420p".to_string()),
+                    ..Default::default()
+                },
+                audio: AudioSettings {
+                    codec: AudioCodec::Aac,
+                    bitrate_kbps: Some(96),
+                    ..Default::default()
+                },
+            }),
+            target_size_mb: Some(25.0),
+            built_in: true,
+        },
+        Preset {
+            name: "Archive".to_string(),
+            description: Some("Near-lossless 10-bit HEVC with FLAC audio in MKV".to_string()),
+            extension: "mkv".to_string(),
+            encoding: Some(EncodingProfile {
+                video: VideoSettings {
+                    codec: VideoCodec::Libx265,
+                    rate_control: Some(RateControl::Crf { value: 12 }),
+                    preset: Some("slow".to_string()),
+                    pix_fmt: Some("yuv420p10le".to_string()),
+                    ..Default::default()
+                },
+                audio: AudioSettings {
+                    codec: AudioCodec::Flac,
+                    ..Default::default()
+                },
+            }),
+            target_size_mb: None,
+            built_in: true,
+        },
+    ]
+}
+
+/// Finds a preset by name, built-in presets first. Names are case-insensitive.
+pub fn find_preset(user_presets: &[Preset], name: &str) -> Option<Preset> {
+    built_in_presets()
+        .into_iter()
+        .chain(user_presets.iter().cloned())
+        .find(|preset| preset.matches_name(name))
+}
+
+/// Swaps a preset name in `params` for the preset's options. Jobs without a
+/// preset are returned unchanged.
+pub fn resolve_preset(user_presets: &[Preset], params: ProcessVideoParams) -> Result<ProcessVideoParams, String> {
+    let Some(ref name) = params.preset else {
+        return Ok(params);
+    };
+
+    if params.encoding.is_some() || params.target_size_mb.is_some() {
+        return Err("Use either a preset or inline encoding options, not both".to_string());
+    }
+
+    let preset = find_preset(user_presets, name).ok_or_else(|| format!("Preset \"{}\" not found", name))?;
+    Ok(preset.apply(params))
+}
+
+/// Adds `preset` to `user_presets`, replacing a user preset of the same name.
+pub fn upsert_preset(user_presets: &mut Vec<Preset>, preset: Preset) -> Result<(), String> {
+    preset.validate()?;
+
+    if built_in_presets().iter().any(|built_in| built_in.matches_name(&preset.name)) {
+        return Err(format!("\"{}\" is a built-in preset and cannot be changed", preset.name.trim()));
+    }
+
+    let preset = Preset {
+        name: preset.name.trim().to_string(),
+        built_in: false,
+        ..preset
+    };
+
+    match user_presets.iter_mut().find(|existing| existing.matches_name(&preset.name)) {
+        Some(existing) => *existing = preset,
+        None => user_presets.push(preset),
+    }
+    Ok(())
+}
+
+/// Reads a JSON array of presets. A missing file means no presets.
+pub fn read_presets(path: &Path) -> Result<Vec<Preset>, String> {
+    let contents = match std::fs::read(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("Failed to read presets: {}", e)),
+    };
+
+    serde_json::from_slice(&contents).map_err(|e| format!("Failed to parse presets: {}", e))
+}
+
+pub fn write_presets(path: &Path, presets: &[Preset]) -> Result<(), String> {
+    let presets: Vec<Preset> = presets
+        .iter()
+        .map(|preset| Preset {
+            built_in: false,
+            ..preset.clone()
+        })
+        .collect();
+    storage::write_json_atomic(path, &presets).map_err(|e| format!("Failed to save presets: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn preset(name: &str) -> Preset {
+        Preset {
+            name: name.to_string(),
+            description: None,
+            extension: "webm".to_string(),
+            encoding: Some(EncodingProfile {
+                video: VideoSettings {
+                    codec: VideoCodec::LibvpxVp9,
+                    rate_control: Some(RateControl::Crf { value: 32 }),
+                    ..Default::default()
+                },
+                audio: AudioSettings {
+                    codec: AudioCodec::Libopus,
+                    ..Default::default()
+                },
+            }),
+            target_size_mb: None,
+            built_in: false,
+        }
+    }
+
+    #[test]
+    fn test_built_in_presets_are_valid() {
+        for preset in built_in_presets() {
+            assert!(preset.validate().is_ok(), "{} is invalid", preset.name);
+            assert!(preset.built_in);
+        }
+    }
+
+    #[test]
+    fn test_validate_checks_container_and_target_size() {
+        assert!(preset("Web").validate().is_ok());
+
+        let wrong_container = Preset {
+            extension: "avi".to_string(),
+            ..preset("Web")
+        };
+        assert!(wrong_container.validate().unwrap_err().starts_with("Preset \"Web\":"));
+
+        let target_with_crf = Preset {
+            target_size_mb: Some(8.0),
+            ..preset("Web")
+        };
+        assert!(target_with_crf.validate().is_err());
+
+        assert!(preset(" ").validate().is_err());
+    }
+
+    #[test]
+    fn test_apply_replaces_encoding_and_clears_name() {
+        let params = ProcessVideoParams {
+            input_file: "/in.mp4".to_string(),
+            output_file: "/out.webm".to_string(),
+            start_time: Some(5.0),
+            preset: Some("web".to_string()),
+            ..Default::default()
+        };
+
+        let applied = preset("Web").apply(params);
+        assert_eq!(applied.preset, None);
+        assert_eq!(applied.start_time, Some(5.0));
+        assert_eq!(applied.encoding, preset("Web").encoding);
+    }
+
+    #[test]
+    fn test_upsert_and_find() {
+        let mut presets = Vec::new();
+        upsert_preset(&mut presets, preset(" Web ")).unwrap();
+        assert_eq!(presets[0].name, "Web");
+
+        let mut updated = preset("WEB");
+        updated.description = Some("Smaller".to_string());
+        upsert_preset(&mut presets, updated).unwrap();
+        assert_eq!(presets.len(), 1);
+        assert_eq!(presets[0].description.as_deref(), Some("Smaller"));
+
+        assert!(upsert_preset(&mut presets, preset("youtube")).unwrap_err().contains("built-in"));
+        assert!(find_preset(&presets, "youtube").unwrap().built_in);
+        assert_eq!(find_preset(&presets, "web").unwrap().name, "WEB");
+        assert!(find_preset(&presets, "missing").is_none());
+    }
+
+    #[test]
+    fn test_roundtrip_drops_built_in_flag() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("presets.json");
+        assert!(read_presets(&path).unwrap().is_empty());
+
+        let mut shared = preset("Web");
+        shared.built_in = true;
+        write_presets(&path, &[shared]).unwrap();
+
+        let read = read_presets(&path).unwrap();
+        assert_eq!(read, vec![preset("Web")]);
+    }
+}
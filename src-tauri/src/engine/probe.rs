@@ -0,0 +1,700 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use crate::engine::binaries;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProbeFormat {
+    pub duration: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProbeOutput {
+    pub format: ProbeFormat,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawProbeOutput {
+    format: Option<RawFormat>,
+    #[serde(default)]
+    streams: Vec<RawStream>,
+    #[serde(default)]
+    chapters: Vec<RawChapter>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawFormat {
+    format_name: Option<String>,
+    format_long_name: Option<String>,
+    start_time: Option<String>,
+    duration: Option<String>,
+    size: Option<String>,
+    bit_rate: Option<String>,
+    #[serde(default)]
+    tags: HashMap<String, String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct RawDisposition {
+    #[serde(default)]
+    default: i32,
+    #[serde(default)]
+    forced: i32,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawSideData {
+    rotation: Option<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawStream {
+    index: u32,
+    codec_type: Option<String>,
+    codec_name: Option<String>,
+    codec_long_name: Option<String>,
+    profile: Option<String>,
+    level: Option<i64>,
+    width: Option<u32>,
+    height: Option<u32>,
+    pix_fmt: Option<String>,
+    r_frame_rate: Option<String>,
+    avg_frame_rate: Option<String>,
+    sample_rate: Option<String>,
+    channels: Option<u32>,
+    channel_layout: Option<String>,
+    bit_rate: Option<String>,
+    duration: Option<String>,
+    #[serde(default)]
+    disposition: RawDisposition,
+    #[serde(default)]
+    tags: HashMap<String, String>,
+    #[serde(default)]
+    side_data_list: Vec<RawSideData>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawChapter {
+    id: i64,
+    start_time: Option<String>,
+    end_time: Option<String>,
+    #[serde(default)]
+    tags: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StreamInfo {
+    pub index: u32,
+    /// `video`, `audio`, `subtitle`, `data` or `attachment`.
+    pub codec_type: String,
+    pub codec_name: Option<String>,
+    pub codec_long_name: Option<String>,
+    pub profile: Option<String>,
+    /// Codec level as ffprobe reports it: 41 for H.264 level 4.1, or 123
+    /// (30 times the level) for HEVC level 4.1.
+    pub level: Option<u32>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub frame_rate: Option<f64>,
+    pub pix_fmt: Option<String>,
+    pub sample_rate: Option<u32>,
+    pub channels: Option<u32>,
+    pub channel_layout: Option<String>,
+    pub bit_rate: Option<u64>,
+    pub duration: Option<f64>,
+    /// Display rotation in degrees, from the display matrix or the legacy `rotate` tag.
+    pub rotation: Option<f64>,
+    pub language: Option<String>,
+    pub title: Option<String>,
+    pub is_default: bool,
+    pub is_forced: bool,
+    pub tags: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChapterInfo {
+    pub id: i64,
+    pub start_time: f64,
+    pub end_time: f64,
+    pub title: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MediaInfo {
+    pub format_name: String,
+    pub format_long_name: Option<String>,
+    pub start_time: Option<f64>,
+    pub duration: Option<f64>,
+    pub size: Option<u64>,
+    pub bit_rate: Option<u64>,
+    pub tags: HashMap<String, String>,
+    pub streams: Vec<StreamInfo>,
+    pub chapters: Vec<ChapterInfo>,
+}
+
+impl MediaInfo {
+    /// The first stream of the given `codec_type`, e.g. `"video"`.
+    pub fn first_stream(&self, codec_type: &str) -> Option<&StreamInfo> {
+        self.streams.iter().find(|stream| stream.codec_type == codec_type)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct RawPacketList {
+    #[serde(default)]
+    packets: Vec<RawPacket>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawPacket {
+    pts_time: Option<String>,
+    flags: Option<String>,
+}
+
+/// How far either side of a cut point to look for keyframes, in seconds.
+pub const KEYFRAME_SEARCH_WINDOW: f64 = 30.0;
+/// Cut points closer than this to a keyframe count as landing on it.
+pub const KEYFRAME_TOLERANCE: f64 = 0.001;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct KeyframeSnap {
+    pub requested: f64,
+    pub keyframe_before: Option<f64>,
+    pub keyframe_after: Option<f64>,
+    /// True when `requested` already sits on a keyframe.
+    pub on_keyframe: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TrimKeyframes {
+    pub start: Option<KeyframeSnap>,
+    pub end: Option<KeyframeSnap>,
+    /// Where a stream-copy trim will actually begin: the keyframe at or before
+    /// the requested start.
+    pub copy_start: Option<f64>,
+    /// False when a stream-copy trim would include footage before the requested start.
+    pub frame_accurate: bool,
+}
+
+/// Extracts sorted keyframe timestamps from `ffprobe -show_entries packet=pts_time,flags`.
+pub fn parse_keyframe_times(json: &[u8]) -> Result<Vec<f64>, String> {
+    let raw: RawPacketList = serde_json::from_slice(json)
+        .map_err(|e| format!("Failed to parse ffprobe output: {}", e))?;
+
+    let mut times: Vec<f64> = raw
+        .packets
+        .iter()
+        .filter(|packet| packet.flags.as_deref().is_some_and(|flags| flags.starts_with('K')))
+        .filter_map(|packet| parse_number(&packet.pts_time))
+        .collect();
+
+    times.sort_by(|a, b| a.total_cmp(b));
+    times.dedup();
+    Ok(times)
+}
+
+/// Finds the keyframes bracketing `requested` in a sorted keyframe list.
+pub fn snap_to_keyframes(requested: f64, keyframes: &[f64]) -> KeyframeSnap {
+    let keyframe_before = keyframes
+        .iter()
+        .copied()
+        .rev()
+        .find(|&t| t <= requested + KEYFRAME_TOLERANCE);
+    let keyframe_after = keyframes
+        .iter()
+        .copied()
+        .find(|&t| t >= requested - KEYFRAME_TOLERANCE);
+
+    let on_keyframe = keyframe_before.is_some_and(|t| (t - requested).abs() <= KEYFRAME_TOLERANCE);
+
+    KeyframeSnap {
+        requested,
+        keyframe_before,
+        keyframe_after,
+        on_keyframe,
+    }
+}
+
+/// Lists keyframe timestamps of the first video stream between `from` and `to`.
+pub async fn probe_keyframes(file_path: &str, from: f64, to: f64) -> Result<Vec<f64>, String> {
+    let interval = format!("{}%{}", from.max(0.0), to);
+    let stdout = run_ffprobe(&[
+        "-select_streams", "v:0",
+        "-show_entries", "packet=pts_time,flags",
+        "-read_intervals", &interval,
+        file_path,
+    ])
+    .await?;
+
+    parse_keyframe_times(&stdout)
+}
+
+async fn snap_cut_point(file_path: &str, requested: f64) -> Result<KeyframeSnap, String> {
+    let keyframes = probe_keyframes(
+        file_path,
+        requested - KEYFRAME_SEARCH_WINDOW,
+        requested + KEYFRAME_SEARCH_WINDOW,
+    )
+    .await?;
+    Ok(snap_to_keyframes(requested, &keyframes))
+}
+
+/// Finds the keyframes around a trim range, showing where a stream-copy cut
+/// will really land.
+pub async fn trim_keyframes(
+    file_path: &str,
+    start_time: Option<f64>,
+    end_time: Option<f64>,
+) -> Result<TrimKeyframes, String> {
+    if !Path::new(file_path).exists() {
+        return Err("File does not exist".to_string());
+    }
+
+    let start = match start_time {
+        Some(t) if t > 0.0 => Some(snap_cut_point(file_path, t).await?),
+        _ => None,
+    };
+    let end = match end_time {
+        Some(t) => Some(snap_cut_point(file_path, t).await?),
+        None => None,
+    };
+
+    let copy_start = start
+        .as_ref()
+        .map(|snap| snap.keyframe_before.unwrap_or(0.0));
+    let frame_accurate = start.as_ref().map_or(true, |snap| snap.on_keyframe);
+
+    Ok(TrimKeyframes {
+        start,
+        end,
+        copy_start,
+        frame_accurate,
+    })
+}
+
+/// Parses ffprobe rational rates such as `30000/1001`. Returns `None` for `0/0`.
+pub fn parse_frame_rate(rate: &str) -> Option<f64> {
+    let value = match rate.split_once('/') {
+        Some((num, den)) => {
+            let num: f64 = num.trim().parse().ok()?;
+            let den: f64 = den.trim().parse().ok()?;
+            if den == 0.0 {
+                return None;
+            }
+            num / den
+        }
+        None => rate.trim().parse().ok()?,
+    };
+
+    (value > 0.0 && value.is_finite()).then_some(value)
+}
+
+fn parse_number<T: std::str::FromStr>(value: &Option<String>) -> Option<T> {
+    value.as_deref().and_then(|v| v.trim().parse().ok())
+}
+
+fn convert_stream(raw: RawStream) -> StreamInfo {
+    // Prefer avg_frame_rate; r_frame_rate is the container's time base guess
+    // and is often wildly off for variable frame rate recordings.
+    let frame_rate = raw
+        .avg_frame_rate
+        .as_deref()
+        .and_then(parse_frame_rate)
+        .or_else(|| raw.r_frame_rate.as_deref().and_then(parse_frame_rate));
+
+    let rotation = raw
+        .side_data_list
+        .iter()
+        .find_map(|side_data| side_data.rotation)
+        .or_else(|| raw.tags.get("rotate").and_then(|r| r.parse().ok()));
+
+    StreamInfo {
+        index: raw.index,
+        codec_type: raw.codec_type.unwrap_or_else(|| "unknown".to_string()),
+        codec_name: raw.codec_name,
+        codec_long_name: raw.codec_long_name,
+        profile: raw.profile,
+        // ffprobe prints -99 when the codec has no level
+        level: raw.level.and_then(|level| u32::try_from(level).ok()).filter(|&level| level > 0),
+        width: raw.width,
+        height: raw.height,
+        frame_rate,
+        pix_fmt: raw.pix_fmt,
+        sample_rate: parse_number(&raw.sample_rate),
+        channels: raw.channels,
+        channel_layout: raw.channel_layout,
+        bit_rate: parse_number(&raw.bit_rate),
+        duration: parse_number(&raw.duration),
+        rotation,
+        language: raw.tags.get("language").cloned(),
+        title: raw.tags.get("title").cloned(),
+        is_default: raw.disposition.default != 0,
+        is_forced: raw.disposition.forced != 0,
+        tags: raw.tags,
+    }
+}
+
+/// Converts the JSON printed by `ffprobe -show_format -show_streams -show_chapters`.
+pub fn parse_media_info(json: &[u8]) -> Result<MediaInfo, String> {
+    let raw: RawProbeOutput = serde_json::from_slice(json)
+        .map_err(|e| format!("Failed to parse ffprobe output: {}", e))?;
+
+    let format = raw
+        .format
+        .ok_or_else(|| "ffprobe output has no format section".to_string())?;
+
+    let chapters = raw
+        .chapters
+        .into_iter()
+        .map(|chapter| ChapterInfo {
+            id: chapter.id,
+            start_time: parse_number(&chapter.start_time).unwrap_or(0.0),
+            end_time: parse_number(&chapter.end_time).unwrap_or(0.0),
+            title: chapter.tags.get("title").cloned(),
+        })
+        .collect();
+
+    Ok(MediaInfo {
+        format_name: format.format_name.unwrap_or_default(),
+        format_long_name: format.format_long_name,
+        start_time: parse_number(&format.start_time),
+        duration: parse_number(&format.duration),
+        size: parse_number(&format.size),
+        bit_rate: parse_number(&format.bit_rate),
+        tags: format.tags,
+        streams: raw.streams.into_iter().map(convert_stream).collect(),
+        chapters,
+    })
+}
+
+/// Runs ffprobe with JSON output and returns its stdout.
+async fn run_ffprobe(args: &[&str]) -> Result<Vec<u8>, String> {
+    let output = binaries::ffprobe_command()
+        .args(["-v", "quiet", "-print_format", "json"])
+        .args(args)
+        .output()
+        .await
+        .map_err(|e| {
+            log::error!("Failed to spawn ffprobe: {}", e);
+            format!("Failed to spawn ffprobe: {}. Make sure ffprobe is installed and in PATH.", e)
+        })?;
+
+    if !output.status.success() {
+        log::error!("ffprobe failed with exit code: {:?}", output.status.code());
+        return Err(format!("ffprobe failed with exit code: {:?}", output.status.code()));
+    }
+
+    Ok(output.stdout)
+}
+
+/// Probes format, streams and chapters of a media file.
+pub async fn probe_media_info(file_path: &str) -> Result<MediaInfo, String> {
+    if !Path::new(file_path).exists() {
+        return Err("File does not exist".to_string());
+    }
+
+    let stdout = run_ffprobe(&["-show_format", "-show_streams", "-show_chapters", file_path]).await?;
+    parse_media_info(&stdout)
+}
+
+/// Container duration in seconds as reported by ffprobe.
+pub async fn probe_duration(file_path: &str) -> Result<f64, String> {
+    // Spawn ffprobe
+    let stdout = run_ffprobe(&["-show_format", file_path]).await?;
+
+    log::debug!("ffprobe output: {}", String::from_utf8_lossy(&stdout));
+
+    // Parse JSON output
+    let probe_data: ProbeOutput = serde_json::from_slice(&stdout)
+        .map_err(|e| {
+            log::error!("Failed to parse ffprobe output: {}", e);
+            format!("Failed to parse ffprobe output: {}", e)
+        })?;
+
+    let duration = probe_data.format.duration.parse::<f64>()
+        .map_err(|e| {
+            log::error!("Failed to parse duration: {}", e);
+            format!("Failed to parse duration: {}", e)
+        })?;
+
+    log::info!("Successfully got duration: {} seconds", duration);
+    Ok(duration)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_probe_output_parsing_valid() {
+        let json_str = json!({
+            "format": {
+                "duration": "123.45"
+            }
+        })
+        .to_string();
+
+        let probe_data: ProbeOutput = serde_json::from_str(&json_str).unwrap();
+        let duration = probe_data.format.duration.parse::<f64>().unwrap();
+
+        assert_eq!(duration, 123.45);
+    }
+
+    #[test]
+    fn test_probe_output_parsing_integer_duration() {
+        let json_str = json!({
+            "format": {
+                "duration": "60"
+            }
+        })
+        .to_string();
+
+        let probe_data: ProbeOutput = serde_json::from_str(&json_str).unwrap();
+        let duration = probe_data.format.duration.parse::<f64>().unwrap();
+
+        assert_eq!(duration, 60.0);
+    }
+
+    #[test]
+    fn test_probe_output_parsing_invalid_duration() {
+        let json_str = json!({
+            "format": {
+                "duration": "not_a_number"
+            }
+        })
+        .to_string();
+
+        let probe_data: ProbeOutput = serde_json::from_str(&json_str).unwrap();
+        let result = probe_data.format.duration.parse::<f64>();
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_probe_output_parsing_zero_duration() {
+        let json_str = json!({
+            "format": {
+                "duration": "0"
+            }
+        })
+        .to_string();
+
+        let probe_data: ProbeOutput = serde_json::from_str(&json_str).unwrap();
+        let duration = probe_data.format.duration.parse::<f64>().unwrap();
+
+        assert_eq!(duration, 0.0);
+    }
+
+    #[test]
+    fn test_probe_output_parsing_large_duration() {
+        let json_str = json!({
+            "format": {
+                "duration": "7200.5"
+            }
+        })
+        .to_string();
+
+        let probe_data: ProbeOutput = serde_json::from_str(&json_str).unwrap();
+        let duration = probe_data.format.duration.parse::<f64>().unwrap();
+
+        assert_eq!(duration, 7200.5);
+    }
+
+    fn sample_probe_json() -> String {
+        json!({
+            "streams": [
+                {
+                    "index": 0,
+                    "codec_name": "h264",
+                    "codec_long_name": "H.264 / AVC / MPEG-4 AVC / MPEG-4 part 10",
+                    "profile": "High",
+                    "level": 41,
+                    "codec_type": "video",
+                    "width": 1920,
+                    "height": 1080,
+                    "pix_fmt": "yuv420p",
+                    "r_frame_rate": "30000/1001",
+                    "avg_frame_rate": "30000/1001",
+                    "bit_rate": "4500000",
+                    "duration": "120.120000",
+                    "disposition": { "default": 1, "forced": 0 },
+                    "tags": { "language": "und", "handler_name": "VideoHandler" },
+                    "side_data_list": [
+                        { "side_data_type": "Display Matrix", "rotation": -90 }
+                    ]
+                },
+                {
+                    "index": 1,
+                    "codec_name": "aac",
+                    "codec_type": "audio",
+                    "sample_rate": "48000",
+                    "channels": 2,
+                    "channel_layout": "stereo",
+                    "r_frame_rate": "0/0",
+                    "avg_frame_rate": "0/0",
+                    "bit_rate": "128000",
+                    "disposition": { "default": 1, "forced": 0 },
+                    "tags": { "language": "eng", "title": "Stereo" }
+                },
+                {
+                    "index": 2,
+                    "codec_name": "mov_text",
+                    "codec_type": "subtitle",
+                    "disposition": { "default": 0, "forced": 1 },
+                    "tags": { "language": "fra" }
+                }
+            ],
+            "chapters": [
+                {
+                    "id": 0,
+                    "time_base": "1/1000",
+                    "start_time": "0.000000",
+                    "end_time": "60.000000",
+                    "tags": { "title": "Intro" }
+                }
+            ],
+            "format": {
+                "filename": "video.mp4",
+                "nb_streams": 3,
+                "format_name": "mov,mp4,m4a,3gp,3g2,mj2",
+                "format_long_name": "QuickTime / MOV",
+                "start_time": "0.000000",
+                "duration": "120.120000",
+                "size": "68000000",
+                "bit_rate": "4528000",
+                "tags": { "major_brand": "isom", "title": "Holiday" }
+            }
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn test_parse_media_info_format() {
+        let info = parse_media_info(sample_probe_json().as_bytes()).unwrap();
+
+        assert_eq!(info.format_name, "mov,mp4,m4a,3gp,3g2,mj2");
+        assert_eq!(info.duration, Some(120.12));
+        assert_eq!(info.size, Some(68_000_000));
+        assert_eq!(info.bit_rate, Some(4_528_000));
+        assert_eq!(info.tags.get("title").map(String::as_str), Some("Holiday"));
+        assert_eq!(info.streams.len(), 3);
+    }
+
+    #[test]
+    fn test_parse_media_info_video_stream() {
+        let info = parse_media_info(sample_probe_json().as_bytes()).unwrap();
+        let video = &info.streams[0];
+
+        assert_eq!(video.codec_name.as_deref(), Some("h264"));
+        assert_eq!(video.profile.as_deref(), Some("High"));
+        assert_eq!(video.level, Some(41));
+        assert_eq!((video.width, video.height), (Some(1920), Some(1080)));
+        assert!((video.frame_rate.unwrap() - 29.97).abs() < 0.01);
+        assert_eq!(video.pix_fmt.as_deref(), Some("yuv420p"));
+        assert_eq!(video.rotation, Some(-90.0));
+        assert_eq!(video.bit_rate, Some(4_500_000));
+        assert!(video.is_default);
+    }
+
+    #[test]
+    fn test_parse_media_info_audio_stream() {
+        let info = parse_media_info(sample_probe_json().as_bytes()).unwrap();
+        let audio = &info.streams[1];
+
+        assert_eq!(audio.sample_rate, Some(48000));
+        assert_eq!(audio.channels, Some(2));
+        assert_eq!(audio.channel_layout.as_deref(), Some("stereo"));
+        assert_eq!(audio.language.as_deref(), Some("eng"));
+        assert_eq!(audio.title.as_deref(), Some("Stereo"));
+        assert!(audio.frame_rate.is_none());
+    }
+
+    #[test]
+    fn test_parse_media_info_subtitles_and_chapters() {
+        let info = parse_media_info(sample_probe_json().as_bytes()).unwrap();
+        let subtitle = &info.streams[2];
+
+        assert_eq!(subtitle.language.as_deref(), Some("fra"));
+        assert!(subtitle.is_forced);
+        assert!(!subtitle.is_default);
+
+        assert_eq!(info.chapters.len(), 1);
+        assert_eq!(info.chapters[0].end_time, 60.0);
+        assert_eq!(info.chapters[0].title.as_deref(), Some("Intro"));
+    }
+
+    #[test]
+    fn test_parse_media_info_legacy_rotate_tag() {
+        let json_str = json!({
+            "format": { "format_name": "mov,mp4,m4a,3gp,3g2,mj2" },
+            "streams": [
+                { "index": 0, "codec_type": "video", "tags": { "rotate": "90" } }
+            ]
+        })
+        .to_string();
+
+        let info = parse_media_info(json_str.as_bytes()).unwrap();
+        assert_eq!(info.streams[0].rotation, Some(90.0));
+        assert!(info.chapters.is_empty());
+    }
+
+    #[test]
+    fn test_parse_media_info_missing_format() {
+        let result = parse_media_info(b"{}");
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_parse_frame_rate() {
+        assert_eq!(parse_frame_rate("25/1"), Some(25.0));
+        assert_eq!(parse_frame_rate("24"), Some(24.0));
+        assert!(parse_frame_rate("0/0").is_none());
+        assert!(parse_frame_rate("abc").is_none());
+    }
+
+    #[test]
+    fn test_parse_keyframe_times_filters_non_keyframes() {
+        let json_str = json!({
+            "packets": [
+                { "pts_time": "4.004000", "flags": "K__" },
+                { "pts_time": "4.037367", "flags": "___" },
+                { "pts_time": "0.000000", "flags": "K_" },
+                { "pts_time": "8.008000", "flags": "K__" },
+                { "flags": "K__" }
+            ]
+        })
+        .to_string();
+
+        let times = parse_keyframe_times(json_str.as_bytes()).unwrap();
+        assert_eq!(times, vec![0.0, 4.004, 8.008]);
+    }
+
+    #[test]
+    fn test_parse_keyframe_times_empty() {
+        assert!(parse_keyframe_times(b"{}").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_snap_to_keyframes_between() {
+        let snap = snap_to_keyframes(5.5, &[0.0, 4.0, 8.0]);
+        assert_eq!(snap.keyframe_before, Some(4.0));
+        assert_eq!(snap.keyframe_after, Some(8.0));
+        assert!(!snap.on_keyframe);
+    }
+
+    #[test]
+    fn test_snap_to_keyframes_on_keyframe() {
+        let snap = snap_to_keyframes(4.0005, &[0.0, 4.0, 8.0]);
+        assert_eq!(snap.keyframe_before, Some(4.0));
+        assert_eq!(snap.keyframe_after, Some(4.0));
+        assert!(snap.on_keyframe);
+    }
+
+    #[test]
+    fn test_snap_to_keyframes_past_last_keyframe() {
+        let snap = snap_to_keyframes(12.0, &[0.0, 4.0, 8.0]);
+        assert_eq!(snap.keyframe_before, Some(8.0));
+        assert!(snap.keyframe_after.is_none());
+    }
+}
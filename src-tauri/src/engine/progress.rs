@@ -0,0 +1,233 @@
+use std::time::{Duration, Instant};
+
+/// One progress report from ffmpeg.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FfmpegProgress {
+    /// Output timestamp in seconds.
+    pub out_time: Option<f64>,
+    pub frame: Option<u64>,
+    pub fps: Option<f64>,
+    pub bitrate_kbps: Option<f64>,
+    /// Bytes written so far.
+    pub total_size: Option<u64>,
+    /// Encoding speed as a multiple of realtime.
+    pub speed: Option<f64>,
+    pub dup_frames: Option<u64>,
+    pub drop_frames: Option<u64>,
+}
+
+impl FfmpegProgress {
+    /// Wall-clock seconds left to process `remaining` seconds of media at the
+    /// current speed.
+    pub fn eta(&self, remaining: f64) -> Option<f64> {
+        match self.speed {
+            Some(speed) if speed > 0.0 => Some((remaining / speed).max(0.0)),
+            _ => None,
+        }
+    }
+
+    /// Replaces ffmpeg's speed, which counts time the process spent stopped,
+    /// with one based on the `active` time it has actually been running.
+    pub fn with_active_time(self, active: Duration) -> Self {
+        let active = active.as_secs_f64();
+        let speed = match self.out_time {
+            Some(out_time) if active > 0.0 => Some(out_time / active),
+            _ => self.speed,
+        };
+        Self { speed, ..self }
+    }
+}
+
+/// Running time of an ffmpeg process, excluding the time it was paused.
+#[derive(Debug, Clone, Copy)]
+pub struct ActiveClock {
+    started: Instant,
+    paused_total: Duration,
+    paused_at: Option<Instant>,
+}
+
+impl ActiveClock {
+    pub fn start(now: Instant) -> Self {
+        Self {
+            started: now,
+            paused_total: Duration::ZERO,
+            paused_at: None,
+        }
+    }
+
+    pub fn pause(&mut self, now: Instant) {
+        if self.paused_at.is_none() {
+            self.paused_at = Some(now);
+        }
+    }
+
+    pub fn resume(&mut self, now: Instant) {
+        if let Some(paused_at) = self.paused_at.take() {
+            self.paused_total += now.saturating_duration_since(paused_at);
+        }
+    }
+
+    pub fn active(&self, now: Instant) -> Duration {
+        let until = self.paused_at.unwrap_or(now);
+        until.saturating_duration_since(self.started).saturating_sub(self.paused_total)
+    }
+}
+
+/// Parser for the output of `-progress pipe:1`. Each block is a run of
+/// `key=value` lines terminated by `progress=continue` or `progress=end`;
+/// values ffmpeg cannot compute yet are reported as `N/A`.
+#[derive(Debug, Default)]
+pub struct ProgressParser {
+    current: FfmpegProgress,
+}
+
+impl ProgressParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feeds one line of ffmpeg's progress output. Returns the block once its
+    /// terminating `progress=` line has been seen.
+    pub fn feed_line(&mut self, line: &str) -> Option<FfmpegProgress> {
+        let (key, value) = line.trim().split_once('=')?;
+        let value = value.trim();
+
+        match key.trim() {
+            "frame" => self.current.frame = value.parse().ok(),
+            "fps" => self.current.fps = value.parse().ok(),
+            "bitrate" => self.current.bitrate_kbps = value.trim_end_matches("kbits/s").trim().parse().ok(),
+            "total_size" => self.current.total_size = value.parse().ok(),
+            "dup_frames" => self.current.dup_frames = value.parse().ok(),
+            "drop_frames" => self.current.drop_frames = value.parse().ok(),
+            "speed" => self.current.speed = value.trim_end_matches('x').trim().parse().ok(),
+            // Both are microseconds; `out_time_ms` is a historical misnomer
+            "out_time_us" | "out_time_ms" => {
+                if let Ok(micros) = value.parse::<i64>() {
+                    self.current.out_time = Some((micros.max(0) as f64) / 1_000_000.0);
+                }
+            }
+            "out_time" if self.current.out_time.is_none() => self.current.out_time = parse_clock(value),
+            "progress" => return Some(std::mem::take(&mut self.current)),
+            _ => {}
+        }
+
+        None
+    }
+}
+
+/// Parses `HH:MM:SS.micros`, clamping negative times to zero.
+fn parse_clock(value: &str) -> Option<f64> {
+    let negative = value.starts_with('-');
+    let mut parts = value.trim_start_matches('-').split(':');
+    let hours: f64 = parts.next()?.parse().ok()?;
+    let minutes: f64 = parts.next()?.parse().ok()?;
+    let seconds: f64 = parts.next()?.parse().ok()?;
+
+    if negative {
+        return Some(0.0);
+    }
+    Some(hours * 3600.0 + minutes * 60.0 + seconds)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feed_all(parser: &mut ProgressParser, text: &str) -> Vec<FfmpegProgress> {
+        text.lines().filter_map(|line| parser.feed_line(line)).collect()
+    }
+
+    #[test]
+    fn test_parses_full_block() {
+        let mut parser = ProgressParser::new();
+        let blocks = feed_all(
+            &mut parser,
+            "frame=240\nfps=59.94\nstream_0_0_q=28.0\nbitrate=1523.4kbits/s\ntotal_size=1572864\n\
+             out_time_us=8008000\nout_time_ms=8008000\nout_time=00:00:08.008000\ndup_frames=2\n\
+             drop_frames=1\nspeed=2.5x\nprogress=continue\n",
+        );
+
+        assert_eq!(
+            blocks,
+            vec![FfmpegProgress {
+                out_time: Some(8.008),
+                frame: Some(240),
+                fps: Some(59.94),
+                bitrate_kbps: Some(1523.4),
+                total_size: Some(1572864),
+                speed: Some(2.5),
+                dup_frames: Some(2),
+                drop_frames: Some(1),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_not_available_values_are_none() {
+        let mut parser = ProgressParser::new();
+        let blocks = feed_all(
+            &mut parser,
+            "frame=0\nbitrate=N/A\ntotal_size=N/A\nout_time_us=N/A\nout_time=N/A\nspeed=N/A\nprogress=continue\n",
+        );
+
+        assert_eq!(blocks[0].frame, Some(0));
+        assert_eq!(blocks[0].bitrate_kbps, None);
+        assert_eq!(blocks[0].out_time, None);
+        assert_eq!(blocks[0].speed, None);
+    }
+
+    #[test]
+    fn test_negative_timestamps_clamp_to_zero() {
+        let mut parser = ProgressParser::new();
+        let blocks = feed_all(&mut parser, "out_time_us=-23220\nprogress=continue\nout_time=-00:00:00.023220\nprogress=continue\n");
+
+        assert_eq!(blocks[0].out_time, Some(0.0));
+        assert_eq!(blocks[1].out_time, Some(0.0));
+    }
+
+    #[test]
+    fn test_blocks_do_not_leak_into_each_other() {
+        let mut parser = ProgressParser::new();
+        let blocks = feed_all(&mut parser, "frame=10\nspeed=1x\nprogress=continue\nframe=20\nprogress=end\n");
+
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[1].frame, Some(20));
+        assert_eq!(blocks[1].speed, None);
+    }
+
+    #[test]
+    fn test_eta_uses_speed() {
+        let progress = FfmpegProgress {
+            speed: Some(2.0),
+            ..Default::default()
+        };
+        assert_eq!(progress.eta(30.0), Some(15.0));
+        assert_eq!(FfmpegProgress::default().eta(30.0), None);
+    }
+
+    #[test]
+    fn test_active_clock_excludes_pauses() {
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+        let mut clock = ActiveClock::start(start);
+
+        clock.pause(at(10));
+        assert_eq!(clock.active(at(40)), Duration::from_secs(10));
+
+        clock.resume(at(40));
+        assert_eq!(clock.active(at(50)), Duration::from_secs(20));
+    }
+
+    #[test]
+    fn test_speed_from_active_time() {
+        let progress = FfmpegProgress {
+            out_time: Some(30.0),
+            speed: Some(0.5),
+            ..Default::default()
+        }
+        .with_active_time(Duration::from_secs(10));
+
+        assert_eq!(progress.speed, Some(3.0));
+        assert_eq!(progress.eta(60.0), Some(20.0));
+    }
+}
@@ -0,0 +1,192 @@
+use crate::engine::binaries;
+use crate::engine::job::{JobOutcome, JobStatus};
+use crate::engine::progress::{FfmpegProgress, ProgressParser};
+use std::collections::VecDeque;
+use std::process::ExitStatus;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, BufReader, Lines};
+use tokio::process::{Child, ChildStderr, ChildStdout};
+use uuid::Uuid;
+
+/// Lines of ffmpeg's stderr kept for error reports.
+const STDERR_TAIL_LINES: usize = 50;
+
+/// A running ffmpeg step with its output pipes taken.
+pub struct SpawnedStep {
+    pub child: Child,
+    /// Carries the `-progress` reports.
+    pub stdout: ChildStdout,
+    pub stderr: ChildStderr,
+}
+
+/// Spawns ffmpeg for one step of a job, with machine-readable progress on
+/// stdout and stdin open so the step can be asked to finish early.
+pub fn spawn_step(args: &[String]) -> Result<SpawnedStep, String> {
+    let mut command = binaries::ffmpeg_command();
+    command
+        .args(["-progress", "pipe:1", "-nostats"])
+        .args(args)
+        .stdin(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped());
+
+    // Hide console window on Windows
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        command.creation_flags(CREATE_NO_WINDOW);
+    }
+
+    let mut child = command
+        .spawn()
+        .map_err(|e| format!("Failed to spawn ffmpeg: {}. Make sure ffmpeg is installed and in PATH.", e))?;
+
+    let stdout = child.stdout.take()
+        .ok_or_else(|| "Failed to capture ffmpeg stdout".to_string())?;
+    let stderr = child.stderr.take()
+        .ok_or_else(|| "Failed to capture ffmpeg stderr".to_string())?;
+
+    Ok(SpawnedStep { child, stdout, stderr })
+}
+
+/// Yields each complete `-progress` block from ffmpeg's stdout.
+pub struct ProgressReader<R> {
+    lines: Lines<BufReader<R>>,
+    parser: ProgressParser,
+}
+
+impl<R: AsyncRead + Unpin> ProgressReader<R> {
+    pub fn new(stdout: R) -> Self {
+        Self {
+            lines: BufReader::new(stdout).lines(),
+            parser: ProgressParser::new(),
+        }
+    }
+
+    /// The next progress block, or `None` once ffmpeg closes stdout.
+    pub async fn next(&mut self) -> Option<FfmpegProgress> {
+        while let Ok(Some(line)) = self.lines.next_line().await {
+            if let Some(progress) = self.parser.feed_line(&line) {
+                return Some(progress);
+            }
+        }
+        None
+    }
+}
+
+/// Logs ffmpeg's stderr and returns its last lines for error reports.
+pub async fn collect_stderr_tail(stderr: impl AsyncRead + Unpin, job_id: Uuid) -> Vec<String> {
+    let mut reader = BufReader::new(stderr);
+    let mut buf = [0u8; 2048];
+    let mut pending = String::new();
+    let mut stderr_tail: VecDeque<String> = VecDeque::with_capacity(STDERR_TAIL_LINES);
+
+    while let Ok(bytes_read) = reader.read(&mut buf).await {
+        if bytes_read == 0 {
+            break;
+        }
+
+        pending.push_str(&String::from_utf8_lossy(&buf[..bytes_read]));
+
+        while let Some(pos) = pending.find(['\r', '\n']) {
+            let (segment, rest) = pending.split_at(pos);
+            let trimmed = segment.trim();
+            if !trimmed.is_empty() {
+                log::debug!("ffmpeg stderr [{}]: {}", job_id, trimmed);
+
+                if stderr_tail.len() == STDERR_TAIL_LINES {
+                    stderr_tail.pop_front();
+                }
+                stderr_tail.push_back(trimmed.to_string());
+            }
+
+            // Drop the delimiter(s) and continue parsing the remainder
+            let mut rest_iter = rest.chars();
+            let _first = rest_iter.next();
+            let remaining_rest: String = rest_iter.collect();
+            let mut rest_clean = remaining_rest;
+            // Remove an additional delimiter if present (handles \r\n)
+            if rest_clean.starts_with('\n') || rest_clean.starts_with('\r') {
+                rest_clean = rest_clean[1..].to_string();
+            }
+            pending = rest_clean;
+        }
+    }
+
+    stderr_tail.into_iter().collect()
+}
+
+/// Turns the result of waiting on ffmpeg into the step's outcome.
+pub fn exit_outcome(status: std::io::Result<ExitStatus>, stderr_tail: Vec<String>) -> JobOutcome {
+    match status {
+        Ok(status) => {
+            if status.success() {
+                JobOutcome {
+                    status: JobStatus::Completed,
+                    exit_code: status.code(),
+                    error: None,
+                    stderr_tail,
+                    cancel_mode: None,
+                    output_file: None,
+                }
+            } else {
+                let stderr_text = if stderr_tail.is_empty() {
+                    "No stderr captured".to_string()
+                } else {
+                    stderr_tail.join("\n")
+                };
+
+                let error = format!(
+                    "FFmpeg exited with code {:?}. Stderr:\n{}",
+                    status.code(),
+                    stderr_text
+                );
+
+                JobOutcome {
+                    status: JobStatus::Failed,
+                    exit_code: status.code(),
+                    error: Some(error),
+                    stderr_tail,
+                    cancel_mode: None,
+                    output_file: None,
+                }
+            }
+        }
+        Err(e) => {
+            let error = format!("Process error: {}", e);
+
+            JobOutcome {
+                stderr_tail,
+                ..JobOutcome::failed(error)
+            }
+        }
+    }
+}
+
+/// Stops or continues ffmpeg with SIGSTOP/SIGCONT.
+#[cfg(unix)]
+pub fn set_stopped(child: &Child, stopped: bool) -> Result<(), String> {
+    let pid = child.id().ok_or_else(|| "ffmpeg has already exited".to_string())?;
+    let signal = if stopped { libc::SIGSTOP } else { libc::SIGCONT };
+
+    // SAFETY: kill(2) only sends a signal; `pid` is our own child, which has
+    // not been reaped yet since `id()` returned it
+    if unsafe { libc::kill(pid as libc::pid_t, signal) } != 0 {
+        return Err(format!("Failed to signal ffmpeg: {}", std::io::Error::last_os_error()));
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn set_stopped(_child: &Child, _stopped: bool) -> Result<(), String> {
+    check_pause_supported()
+}
+
+/// Fails on platforms where `set_stopped` cannot pause ffmpeg.
+pub fn check_pause_supported() -> Result<(), String> {
+    if cfg!(unix) {
+        Ok(())
+    } else {
+        Err("Pausing jobs is only supported on macOS and Linux".to_string())
+    }
+}
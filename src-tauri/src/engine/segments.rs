@@ -0,0 +1,177 @@
+use serde::{Deserialize, Serialize};
+
+/// Ranges shorter than this (in seconds) are dropped from a cut list.
+const MIN_RANGE_LENGTH: f64 = 0.001;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SegmentAction {
+    Keep,
+    Remove,
+}
+
+/// One entry of a cut list, in seconds of the source timeline.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CutSegment {
+    pub start: f64,
+    pub end: f64,
+    pub action: SegmentAction,
+}
+
+/// A stretch of the source that ends up in the output.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KeptRange {
+    pub start: f64,
+    pub end: f64,
+}
+
+impl KeptRange {
+    pub fn duration(&self) -> f64 {
+        self.end - self.start
+    }
+}
+
+pub fn validate_segments(segments: &[CutSegment]) -> Result<(), String> {
+    for (index, segment) in segments.iter().enumerate() {
+        if segment.start < 0.0 {
+            return Err(format!("Segment {} starts before the beginning of the video", index + 1));
+        }
+
+        if segment.end <= segment.start {
+            return Err(format!("Segment {} must end after it starts", index + 1));
+        }
+    }
+
+    Ok(())
+}
+
+/// Sorts ranges and merges any that overlap or touch.
+fn merge_ranges(mut ranges: Vec<KeptRange>) -> Vec<KeptRange> {
+    ranges.sort_by(|a, b| a.start.total_cmp(&b.start));
+
+    let mut merged: Vec<KeptRange> = Vec::with_capacity(ranges.len());
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+            _ => merged.push(range),
+        }
+    }
+    merged
+}
+
+/// Resolves a cut list into the ordered ranges to export. Keep segments select
+/// what is exported (the whole video when there are none); remove segments are
+/// then cut out of that selection. Everything is clamped to `duration`.
+pub fn kept_ranges(segments: &[CutSegment], duration: f64) -> Vec<KeptRange> {
+    let ranges_for = |action: SegmentAction| {
+        merge_ranges(
+            segments
+                .iter()
+                .filter(|segment| segment.action == action)
+                .map(|segment| KeptRange {
+                    start: segment.start.max(0.0),
+                    end: segment.end.min(duration),
+                })
+                .filter(|range| range.duration() > 0.0)
+                .collect(),
+        )
+    };
+
+    let mut kept = ranges_for(SegmentAction::Keep);
+    if kept.is_empty() && !segments.iter().any(|s| s.action == SegmentAction::Keep) {
+        kept.push(KeptRange {
+            start: 0.0,
+            end: duration,
+        });
+    }
+
+    for removed in ranges_for(SegmentAction::Remove) {
+        kept = kept
+            .into_iter()
+            .flat_map(|range| {
+                let before = KeptRange {
+                    start: range.start,
+                    end: range.end.min(removed.start),
+                };
+                let after = KeptRange {
+                    start: range.start.max(removed.end),
+                    end: range.end,
+                };
+                [before, after]
+            })
+            .filter(|range| range.duration() > MIN_RANGE_LENGTH)
+            .collect();
+    }
+
+    kept
+}
+
+pub fn total_duration(ranges: &[KeptRange]) -> f64 {
+    ranges.iter().map(KeptRange::duration).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keep(start: f64, end: f64) -> CutSegment {
+        CutSegment {
+            start,
+            end,
+            action: SegmentAction::Keep,
+        }
+    }
+
+    fn remove(start: f64, end: f64) -> CutSegment {
+        CutSegment {
+            start,
+            end,
+            action: SegmentAction::Remove,
+        }
+    }
+
+    fn range(start: f64, end: f64) -> KeptRange {
+        KeptRange { start, end }
+    }
+
+    #[test]
+    fn test_remove_only_keeps_the_rest() {
+        let ranges = kept_ranges(&[remove(10.0, 20.0), remove(50.0, 60.0)], 100.0);
+        assert_eq!(ranges, vec![range(0.0, 10.0), range(20.0, 50.0), range(60.0, 100.0)]);
+        assert_eq!(total_duration(&ranges), 80.0);
+    }
+
+    #[test]
+    fn test_keep_segments_are_sorted_and_merged() {
+        let ranges = kept_ranges(&[keep(40.0, 50.0), keep(5.0, 15.0), keep(12.0, 20.0)], 100.0);
+        assert_eq!(ranges, vec![range(5.0, 20.0), range(40.0, 50.0)]);
+    }
+
+    #[test]
+    fn test_remove_inside_keep_splits_it() {
+        let ranges = kept_ranges(&[keep(0.0, 30.0), remove(10.0, 12.0)], 100.0);
+        assert_eq!(ranges, vec![range(0.0, 10.0), range(12.0, 30.0)]);
+    }
+
+    #[test]
+    fn test_ranges_clamped_to_duration() {
+        let ranges = kept_ranges(&[keep(90.0, 120.0)], 100.0);
+        assert_eq!(ranges, vec![range(90.0, 100.0)]);
+    }
+
+    #[test]
+    fn test_keeps_entirely_past_the_end_leave_nothing() {
+        assert!(kept_ranges(&[keep(120.0, 130.0)], 100.0).is_empty());
+        assert!(kept_ranges(&[remove(0.0, 100.0)], 100.0).is_empty());
+    }
+
+    #[test]
+    fn test_validate_segments() {
+        assert!(validate_segments(&[keep(0.0, 1.0), remove(5.0, 6.0)]).is_ok());
+        assert_eq!(
+            validate_segments(&[keep(0.0, 1.0), remove(6.0, 5.0)]).unwrap_err(),
+            "Segment 2 must end after it starts"
+        );
+        assert!(validate_segments(&[keep(-1.0, 1.0)]).is_err());
+    }
+}
@@ -0,0 +1,354 @@
+use crate::engine::binaries::BinaryPaths;
+use crate::engine::container::Container;
+use crate::engine::encoding::EncodingProfile;
+use crate::engine::job::{ProcessVideoParams, TrimMode};
+use crate::engine::naming;
+use crate::engine::storage;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+pub const DEFAULT_MAX_CONCURRENT_JOBS: usize = 1;
+pub const MAX_CONCURRENT_JOBS_LIMIT: usize = 16;
+
+/// Bumped whenever the stored layout changes; each bump adds a step to `migrate`.
+pub const CURRENT_SCHEMA_VERSION: u32 = 1;
+
+/// Settings are stored under this name in the app config directory.
+pub const SETTINGS_FILE_NAME: &str = "settings.json";
+
+pub const DEFAULT_FILENAME_TEMPLATE: &str = "{name}_edited";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogLevel {
+    Error,
+    Warn,
+    Info,
+    #[default]
+    Debug,
+    Trace,
+}
+
+impl LogLevel {
+    pub fn to_filter(self) -> log::LevelFilter {
+        match self {
+            LogLevel::Error => log::LevelFilter::Error,
+            LogLevel::Warn => log::LevelFilter::Warn,
+            LogLevel::Info => log::LevelFilter::Info,
+            LogLevel::Debug => log::LevelFilter::Debug,
+            LogLevel::Trace => log::LevelFilter::Trace,
+        }
+    }
+}
+
+/// User preferences, stored as `settings.json` in the app config directory.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub schema_version: u32,
+    /// Folder new exports are written to when a job has no output file. `None`
+    /// writes them next to the input.
+    pub default_output_dir: Option<String>,
+    /// Encoder settings for re-encoding jobs that name no encoding or preset.
+    /// `None`, or an output container that cannot hold these codecs, uses the
+    /// container's defaults.
+    pub default_encoding: Option<EncodingProfile>,
+    /// Output file name, without extension, for jobs that have no output file.
+    pub filename_template: String,
+    pub max_concurrent_jobs: usize,
+    pub binaries: BinaryPaths,
+    pub log_level: LogLevel,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            schema_version: CURRENT_SCHEMA_VERSION,
+            default_output_dir: None,
+            default_encoding: None,
+            filename_template: DEFAULT_FILENAME_TEMPLATE.to_string(),
+            max_concurrent_jobs: DEFAULT_MAX_CONCURRENT_JOBS,
+            binaries: BinaryPaths::default(),
+            log_level: LogLevel::default(),
+        }
+    }
+}
+
+impl Settings {
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(ref dir) = self.default_output_dir {
+            if !Path::new(dir).is_dir() {
+                return Err(format!("Default output folder {} does not exist", dir));
+            }
+        }
+
+        if let Some(ref encoding) = self.default_encoding {
+            encoding.validate()?;
+        }
+
+        if self.filename_template.trim().is_empty() {
+            return Err("Filename template cannot be empty".to_string());
+        }
+        naming::template_tokens(&self.filename_template)?;
+
+        if self.max_concurrent_jobs == 0 || self.max_concurrent_jobs > MAX_CONCURRENT_JOBS_LIMIT {
+            return Err(format!(
+                "Concurrency must be between 1 and {}",
+                MAX_CONCURRENT_JOBS_LIMIT
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+impl Settings {
+    /// `filename_template` in `default_output_dir`, or next to `input_file`
+    /// when there is no default folder. The name tokens are rendered when the
+    /// job is queued. `extension` defaults to the input's.
+    pub fn default_output(&self, input_file: &str, extension: Option<String>) -> Result<String, String> {
+        let input = Path::new(input_file);
+        let dir = match self.default_output_dir {
+            Some(ref dir) => PathBuf::from(dir),
+            None => input.parent().map(Path::to_path_buf).unwrap_or_default(),
+        };
+
+        let extension = extension
+            .or_else(|| input.extension().map(|ext| ext.to_string_lossy().to_string()))
+            .ok_or_else(|| "Cannot pick an output extension; choose an output file".to_string())?;
+
+        Ok(dir
+            .join(format!("{}.{}", self.filename_template, extension))
+            .to_string_lossy()
+            .to_string())
+    }
+
+    /// Fills in `default_encoding` for a job that re-encodes without its own
+    /// encoding, preset or target size. Stream-copying trims and concat joins,
+    /// which copy matching inputs, are left alone.
+    pub fn apply_default_encoding(&self, params: ProcessVideoParams) -> ProcessVideoParams {
+        let Some(ref encoding) = self.default_encoding else {
+            return params;
+        };
+
+        let applies = params.encoding.is_none()
+            && params.preset.is_none()
+            && params.target_size_mb.is_none()
+            && params.trim_mode == TrimMode::Accurate
+            && params.concat_inputs.is_empty()
+            && Container::from_path(&params.output_file).is_some_and(|container| container.check_encoding(encoding).is_ok());
+
+        if !applies {
+            return params;
+        }
+        ProcessVideoParams {
+            encoding: Some(encoding.clone()),
+            ..params
+        }
+    }
+}
+
+/// Upgrades stored settings to `CURRENT_SCHEMA_VERSION`, one version at a time.
+/// Files without a `schema_version` are version 0.
+pub fn migrate(mut value: Value) -> Result<Value, String> {
+    let Some(object) = value.as_object_mut() else {
+        return Err("Settings file is not a JSON object".to_string());
+    };
+
+    let mut version = object
+        .get("schema_version")
+        .and_then(Value::as_u64)
+        .unwrap_or(0) as u32;
+
+    if version > CURRENT_SCHEMA_VERSION {
+        return Err(format!(
+            "Settings were written by a newer version of the app (schema {})",
+            version
+        ));
+    }
+
+    while version < CURRENT_SCHEMA_VERSION {
+        match version {
+            // v0 only held binary paths at the top level
+            0 => {
+                let mut binaries = serde_json::Map::new();
+                for key in ["ffmpeg_path", "ffprobe_path"] {
+                    if let Some(path) = object.remove(key) {
+                        binaries.insert(key.to_string(), path);
+                    }
+                }
+                object.insert("binaries".to_string(), Value::Object(binaries));
+            }
+            _ => unreachable!("every schema version below the current one has a migration"),
+        }
+        version += 1;
+        object.insert("schema_version".to_string(), Value::from(version));
+    }
+
+    Ok(value)
+}
+
+/// Reads settings from `path`, migrating older layouts. A missing file gives
+/// the defaults; an unreadable one is reported so it is not silently replaced.
+pub fn read_settings(path: &Path) -> Result<Settings, String> {
+    let contents = match std::fs::read(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Settings::default()),
+        Err(e) => return Err(format!("Failed to read settings: {}", e)),
+    };
+
+    let value: Value =
+        serde_json::from_slice(&contents).map_err(|e| format!("Failed to parse settings: {}", e))?;
+    serde_json::from_value(migrate(value)?).map_err(|e| format!("Failed to parse settings: {}", e))
+}
+
+pub fn write_settings(path: &Path, settings: &Settings) -> Result<(), String> {
+    storage::write_json_atomic(path, settings).map_err(|e| format!("Failed to save settings: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::encoding::{VideoCodec, VideoSettings};
+    use serde_json::json;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_missing_file_gives_defaults() {
+        let dir = tempdir().unwrap();
+        let settings = read_settings(&dir.path().join("settings.json")).unwrap();
+        assert_eq!(settings, Settings::default());
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("config").join("settings.json");
+        let settings = Settings {
+            filename_template: "{name}_{date}".to_string(),
+            max_concurrent_jobs: 3,
+            log_level: LogLevel::Info,
+            ..Default::default()
+        };
+
+        write_settings(&path, &settings).unwrap();
+        assert_eq!(read_settings(&path).unwrap(), settings);
+    }
+
+    #[test]
+    fn test_migrates_v0_binary_paths() {
+        let migrated = migrate(json!({
+            "ffmpeg_path": "/opt/ffmpeg/bin/ffmpeg",
+            "ffprobe_path": null
+        }))
+        .unwrap();
+
+        assert_eq!(migrated["schema_version"], json!(CURRENT_SCHEMA_VERSION));
+        assert_eq!(migrated["binaries"]["ffmpeg_path"], json!("/opt/ffmpeg/bin/ffmpeg"));
+        assert!(migrated.get("ffmpeg_path").is_none());
+
+        let settings: Settings = serde_json::from_value(migrated).unwrap();
+        assert_eq!(settings.binaries.ffmpeg_path.as_deref(), Some("/opt/ffmpeg/bin/ffmpeg"));
+        assert_eq!(settings.filename_template, DEFAULT_FILENAME_TEMPLATE);
+    }
+
+    #[test]
+    fn test_rejects_newer_schema() {
+        let result = migrate(json!({ "schema_version": CURRENT_SCHEMA_VERSION + 1 }));
+        assert!(result.unwrap_err().contains("newer version"));
+    }
+
+    #[test]
+    fn test_corrupt_file_is_an_error() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("settings.json");
+        std::fs::write(&path, "{ not json").unwrap();
+        assert!(read_settings(&path).is_err());
+    }
+
+    #[test]
+    fn test_apply_default_encoding() {
+        let x265 = EncodingProfile {
+            video: VideoSettings {
+                codec: VideoCodec::Libx265,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let settings = Settings {
+            default_encoding: Some(x265.clone()),
+            ..Default::default()
+        };
+        let job = ProcessVideoParams {
+            input_file: "/in/clip.mov".to_string(),
+            output_file: "/out/clip.mp4".to_string(),
+            ..Default::default()
+        };
+
+        assert_eq!(settings.apply_default_encoding(job.clone()).encoding, Some(x265));
+
+        // Jobs that chose their encoding, copy streams or cannot hold HEVC keep their own
+        let skipped = [
+            ProcessVideoParams {
+                preset: Some("YouTube".to_string()),
+                ..job.clone()
+            },
+            ProcessVideoParams {
+                trim_mode: TrimMode::Fast,
+                ..job.clone()
+            },
+            ProcessVideoParams {
+                output_file: "/out/clip.webm".to_string(),
+                ..job.clone()
+            },
+        ];
+        for params in skipped {
+            assert_eq!(settings.apply_default_encoding(params).encoding, None);
+        }
+
+        assert_eq!(Settings::default().apply_default_encoding(job).encoding, None);
+    }
+
+    #[test]
+    fn test_default_output() {
+        let settings = Settings::default();
+        assert_eq!(
+            settings.default_output("/in/clip.mov", None).unwrap(),
+            Path::new("/in").join("{name}_edited.mov").to_string_lossy()
+        );
+
+        let settings = Settings {
+            default_output_dir: Some("/exports".to_string()),
+            ..settings
+        };
+        assert_eq!(
+            settings.default_output("/in/clip.mov", Some("mp4".to_string())).unwrap(),
+            Path::new("/exports").join("{name}_edited.mp4").to_string_lossy()
+        );
+        assert!(settings.default_output("/in/clip", None).is_err());
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(Settings::default().validate().is_ok());
+
+        let no_template = Settings {
+            filename_template: "  ".to_string(),
+            ..Default::default()
+        };
+        assert!(no_template.validate().is_err());
+
+        let unknown_token = Settings {
+            filename_template: "{name}_{resolution}".to_string(),
+            ..Default::default()
+        };
+        assert!(unknown_token.validate().is_err());
+
+        let too_many_jobs = Settings {
+            max_concurrent_jobs: MAX_CONCURRENT_JOBS_LIMIT + 1,
+            ..Default::default()
+        };
+        assert!(too_many_jobs.validate().is_err());
+    }
+}
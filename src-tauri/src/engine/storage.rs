@@ -0,0 +1,43 @@
+use serde::Serialize;
+use std::io;
+use std::path::Path;
+use uuid::Uuid;
+
+/// Writes `value` as pretty JSON via a temp file and rename, so a crash
+/// mid-write never leaves a truncated file behind. Each write gets its own
+/// temp file, so concurrent saves of the same file cannot interleave.
+/// Missing parent directories are created.
+pub fn write_json_atomic<T: Serialize + ?Sized>(path: &Path, value: &T) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let json = serde_json::to_vec_pretty(value).map_err(io::Error::other)?;
+
+    let mut tmp_name = path.file_name().unwrap_or_default().to_os_string();
+    tmp_name.push(format!(".{}.tmp", Uuid::new_v4().simple()));
+    let tmp_path = path.with_file_name(tmp_name);
+
+    std::fs::write(&tmp_path, json)?;
+    std::fs::rename(&tmp_path, path).inspect_err(|_| {
+        let _ = std::fs::remove_file(&tmp_path);
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_write_json_atomic_replaces_file_and_leaves_no_temp() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("config").join("items.json");
+
+        write_json_atomic(&path, &[1, 2]).unwrap();
+        write_json_atomic(&path, &[3]).unwrap();
+
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "[\n  3\n]");
+        assert_eq!(std::fs::read_dir(path.parent().unwrap()).unwrap().count(), 1);
+    }
+}
@@ -0,0 +1,433 @@
+use crate::engine::container::Container;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// A subtitle file muxed into the output as a selectable track, leaving the
+/// video untouched. `ProcessVideoParams::subtitle_file` burns text in instead.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SubtitleTrack {
+    pub file: String,
+    /// ISO 639-2 code such as `eng` or `fra`.
+    #[serde(default)]
+    pub language: Option<String>,
+    /// Name players show in their subtitle menu.
+    #[serde(default)]
+    pub title: Option<String>,
+    /// Shown without the viewer picking a track.
+    #[serde(default)]
+    pub default: bool,
+    /// Only carries text that must always be shown, e.g. translated signs.
+    #[serde(default)]
+    pub forced: bool,
+}
+
+/// Text subtitle formats read from disk, keyed by file extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubtitleFormat {
+    Srt,
+    Ass,
+    WebVtt,
+}
+
+impl SubtitleFormat {
+    pub fn from_path(path: &str) -> Option<Self> {
+        let extension = Path::new(path).extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "srt" => Some(SubtitleFormat::Srt),
+            "ass" | "ssa" => Some(SubtitleFormat::Ass),
+            "vtt" => Some(SubtitleFormat::WebVtt),
+            _ => None,
+        }
+    }
+}
+
+impl SubtitleTrack {
+    fn format(&self) -> Result<SubtitleFormat, String> {
+        SubtitleFormat::from_path(&self.file)
+            .ok_or_else(|| format!("Unsupported subtitle file: {}. Use .srt, .ass, .ssa or .vtt", self.file))
+    }
+
+    /// The subtitle encoder that writes this track into `container`.
+    pub fn codec(&self, container: Container) -> Result<&'static str, String> {
+        let format = self.format()?;
+        container
+            .subtitle_codec(format)
+            .ok_or_else(|| format!("{} files cannot carry subtitle tracks; burn them in instead", container.label()))
+    }
+
+    /// Value for `-disposition`. `0` clears the flags a muxer might set on its own.
+    fn disposition(&self) -> &'static str {
+        match (self.default, self.forced) {
+            (true, true) => "default+forced",
+            (true, false) => "default",
+            (false, true) => "forced",
+            (false, false) => "0",
+        }
+    }
+}
+
+/// How burned-in subtitles are drawn. Unset fields keep the style from the
+/// subtitle file, or libass defaults for formats without styles.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SubtitleStyle {
+    /// Font family, e.g. `Arial`. With `font_file`, the family that file provides.
+    #[serde(default)]
+    pub font_name: Option<String>,
+    /// A font that is not installed. Its folder is searched for fonts.
+    #[serde(default)]
+    pub font_file: Option<String>,
+    /// Folder of fonts that are not installed.
+    #[serde(default)]
+    pub fonts_dir: Option<String>,
+    #[serde(default)]
+    pub font_size: Option<u32>,
+    /// `#RRGGBB`, or `#RRGGBBAA` where `AA` is the opacity.
+    #[serde(default)]
+    pub primary_color: Option<String>,
+    #[serde(default)]
+    pub outline_color: Option<String>,
+    /// Outline thickness in pixels.
+    #[serde(default)]
+    pub outline_width: Option<f64>,
+    /// Shadow offset in pixels.
+    #[serde(default)]
+    pub shadow: Option<f64>,
+    /// Position on a numeric keypad: 1 is bottom left, 5 the centre, 9 top right.
+    #[serde(default)]
+    pub alignment: Option<u8>,
+    #[serde(default)]
+    pub margin_left: Option<u32>,
+    #[serde(default)]
+    pub margin_right: Option<u32>,
+    #[serde(default)]
+    pub margin_vertical: Option<u32>,
+    /// Text encoding of the subtitle file, e.g. `CP1252`, when it is not UTF-8.
+    #[serde(default)]
+    pub charset: Option<String>,
+}
+
+impl SubtitleStyle {
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(ref font_name) = self.font_name {
+            // force_style separates its fields with ',' and '='
+            if font_name.trim().is_empty() || font_name.contains([',', '=']) {
+                return Err(format!("Invalid font name: {}", font_name));
+            }
+        }
+
+        if let Some(ref font_file) = self.font_file {
+            if !Path::new(font_file).is_file() {
+                return Err(format!("Font file does not exist: {}", font_file));
+            }
+            if self.fonts_dir.is_some() {
+                return Err("Use either a font file or a fonts folder, not both".to_string());
+            }
+        }
+
+        if let Some(ref fonts_dir) = self.fonts_dir {
+            if !Path::new(fonts_dir).is_dir() {
+                return Err(format!("Fonts folder does not exist: {}", fonts_dir));
+            }
+        }
+
+        if self.font_size == Some(0) {
+            return Err("Font size must be greater than zero".to_string());
+        }
+
+        for color in [&self.primary_color, &self.outline_color].into_iter().flatten() {
+            ass_color(color)?;
+        }
+
+        for (name, value) in [("Outline width", self.outline_width), ("Shadow", self.shadow)] {
+            if value.is_some_and(|value| !value.is_finite() || value < 0.0) {
+                return Err(format!("{} cannot be negative", name));
+            }
+        }
+
+        if self.alignment.is_some_and(|alignment| !(1..=9).contains(&alignment)) {
+            return Err("Alignment must be between 1 and 9".to_string());
+        }
+
+        if let Some(ref charset) = self.charset {
+            let valid = !charset.is_empty()
+                && charset.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+            if !valid {
+                return Err(format!("Invalid subtitle charset: {}", charset));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The style as an ASS `force_style` override, e.g.
+    /// `Fontname=Arial,Fontsize=24`. Empty when nothing is set.
+    pub fn force_style(&self) -> Result<String, String> {
+        let mut fields = Vec::new();
+
+        if let Some(ref font_name) = self.font_name {
+            fields.push(format!("Fontname={}", font_name.trim()));
+        }
+        if let Some(size) = self.font_size {
+            fields.push(format!("Fontsize={}", size));
+        }
+        if let Some(ref color) = self.primary_color {
+            fields.push(format!("PrimaryColour={}", ass_color(color)?));
+        }
+        if let Some(ref color) = self.outline_color {
+            fields.push(format!("OutlineColour={}", ass_color(color)?));
+        }
+        if let Some(width) = self.outline_width {
+            fields.push(format!("Outline={}", width));
+        }
+        if let Some(shadow) = self.shadow {
+            fields.push(format!("Shadow={}", shadow));
+        }
+        if let Some(alignment) = self.alignment {
+            fields.push(format!("Alignment={}", alignment));
+        }
+        if let Some(margin) = self.margin_left {
+            fields.push(format!("MarginL={}", margin));
+        }
+        if let Some(margin) = self.margin_right {
+            fields.push(format!("MarginR={}", margin));
+        }
+        if let Some(margin) = self.margin_vertical {
+            fields.push(format!("MarginV={}", margin));
+        }
+
+        Ok(fields.join(","))
+    }
+
+    /// Folder libass should load extra fonts from.
+    pub fn fonts_dir(&self) -> Option<PathBuf> {
+        match (&self.fonts_dir, &self.font_file) {
+            (Some(dir), _) => Some(PathBuf::from(dir)),
+            (None, Some(file)) => Path::new(file)
+                .parent()
+                .filter(|dir| !dir.as_os_str().is_empty())
+                .map(Path::to_path_buf),
+            (None, None) => None,
+        }
+    }
+}
+
+/// Converts `#RRGGBB[AA]` to ASS's `&HAABBGGRR`, where alpha counts
+/// transparency rather than opacity.
+fn ass_color(color: &str) -> Result<String, String> {
+    let invalid = || format!("Invalid colour \"{}\". Use #RRGGBB or #RRGGBBAA", color);
+    let hex = color.strip_prefix('#').ok_or_else(invalid)?;
+    if !matches!(hex.len(), 6 | 8) || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(invalid());
+    }
+
+    let channel = |index: usize| u8::from_str_radix(&hex[index..index + 2], 16).map_err(|_| invalid());
+    let (red, green, blue) = (channel(0)?, channel(2)?, channel(4)?);
+    let opacity = if hex.len() == 8 { channel(6)? } else { 0xff };
+
+    Ok(format!("&H{:02X}{:02X}{:02X}{:02X}", 0xff - opacity, blue, green, red))
+}
+
+fn is_language_code(code: &str) -> bool {
+    code.len() == 3 && code.chars().all(|c| c.is_ascii_lowercase())
+}
+
+pub fn validate_tracks(tracks: &[SubtitleTrack], container: Container) -> Result<(), String> {
+    for track in tracks {
+        if !Path::new(&track.file).exists() {
+            return Err(format!("Subtitle file does not exist: {}", track.file));
+        }
+
+        track.codec(container)?;
+
+        if let Some(ref language) = track.language {
+            if !is_language_code(language) {
+                return Err(format!(
+                    "Invalid subtitle language \"{}\". Use a three-letter ISO 639-2 code such as eng",
+                    language
+                ));
+            }
+        }
+    }
+
+    if tracks.iter().filter(|track| track.default).count() > 1 {
+        return Err("Only one subtitle track can be the default".to_string());
+    }
+
+    Ok(())
+}
+
+/// Output options for `tracks`, read from the inputs numbered from
+/// `first_input` on. The caller adds the `-i` for each track and maps the
+/// source's own streams.
+pub fn track_args(tracks: &[SubtitleTrack], container: Container, first_input: usize) -> Result<Vec<String>, String> {
+    let mut maps = Vec::new();
+    let mut options = Vec::new();
+
+    for (index, track) in tracks.iter().enumerate() {
+        maps.push("-map".to_string());
+        maps.push(format!("{}:s:0", first_input + index));
+
+        options.push(format!("-c:s:{}", index));
+        options.push(track.codec(container)?.to_string());
+
+        if let Some(ref language) = track.language {
+            options.push(format!("-metadata:s:s:{}", index));
+            options.push(format!("language={}", language));
+        }
+        if let Some(ref title) = track.title {
+            options.push(format!("-metadata:s:s:{}", index));
+            options.push(format!("title={}", title));
+        }
+
+        options.push(format!("-disposition:s:{}", index));
+        options.push(track.disposition().to_string());
+    }
+
+    maps.extend(options);
+    Ok(maps)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn track(file: &str) -> SubtitleTrack {
+        SubtitleTrack {
+            file: file.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_codec_per_container() {
+        let srt = track("/subs/en.srt");
+        let ass = track("/subs/styled.ASS");
+        let vtt = track("/subs/fr.vtt");
+
+        assert_eq!(srt.codec(Container::Mp4), Ok("mov_text"));
+        assert_eq!(ass.codec(Container::Mov), Ok("mov_text"));
+        assert_eq!(srt.codec(Container::Mkv), Ok("srt"));
+        assert_eq!(ass.codec(Container::Mkv), Ok("ass"));
+        assert_eq!(vtt.codec(Container::Mkv), Ok("webvtt"));
+        assert_eq!(srt.codec(Container::Webm), Ok("webvtt"));
+        assert!(srt.codec(Container::Avi).is_err());
+        assert!(track("/subs/en.sub").codec(Container::Mkv).is_err());
+    }
+
+    #[test]
+    fn test_track_args_number_outputs_from_zero() {
+        let tracks = vec![
+            SubtitleTrack {
+                language: Some("eng".to_string()),
+                title: Some("English".to_string()),
+                default: true,
+                ..track("/en.srt")
+            },
+            SubtitleTrack {
+                language: Some("fra".to_string()),
+                forced: true,
+                ..track("/fr.ass")
+            },
+        ];
+
+        let args = track_args(&tracks, Container::Mkv, 1).unwrap();
+        assert_eq!(
+            args.join(" "),
+            "-map 1:s:0 -map 2:s:0 \
+             -c:s:0 srt -metadata:s:s:0 language=eng -metadata:s:s:0 title=English -disposition:s:0 default \
+             -c:s:1 ass -metadata:s:s:1 language=fra -disposition:s:1 forced"
+        );
+    }
+
+    #[test]
+    fn test_validate_tracks() {
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("en.srt");
+        std::fs::write(&file, "1\n00:00:01,000 --> 00:00:02,000\nHi\n").unwrap();
+        let en = track(file.to_str().unwrap());
+
+        assert!(validate_tracks(std::slice::from_ref(&en), Container::Mp4).is_ok());
+        assert!(validate_tracks(&[track("/missing.srt")], Container::Mp4).is_err());
+        assert!(validate_tracks(std::slice::from_ref(&en), Container::Avi).is_err());
+
+        let bad_language = SubtitleTrack {
+            language: Some("en".to_string()),
+            ..en.clone()
+        };
+        assert!(validate_tracks(&[bad_language], Container::Mkv).unwrap_err().contains("ISO 639-2"));
+
+        let default = SubtitleTrack {
+            default: true,
+            ..en
+        };
+        assert!(validate_tracks(&[default.clone(), default], Container::Mkv).is_err());
+    }
+
+    #[test]
+    fn test_force_style_fields() {
+        let style = SubtitleStyle {
+            font_name: Some("Noto Sans".to_string()),
+            font_size: Some(28),
+            primary_color: Some("#FFCC00".to_string()),
+            outline_color: Some("#00000080".to_string()),
+            outline_width: Some(1.5),
+            alignment: Some(8),
+            margin_vertical: Some(40),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            style.force_style().unwrap(),
+            "Fontname=Noto Sans,Fontsize=28,PrimaryColour=&H0000CCFF,OutlineColour=&H7F000000,\
+             Outline=1.5,Alignment=8,MarginV=40"
+        );
+        assert_eq!(SubtitleStyle::default().force_style().unwrap(), "");
+    }
+
+    #[test]
+    fn test_style_validation() {
+        let dir = tempdir().unwrap();
+        let font = dir.path().join("Custom.ttf");
+        std::fs::write(&font, b"font").unwrap();
+
+        let style = SubtitleStyle {
+            font_file: Some(font.to_string_lossy().to_string()),
+            font_name: Some("Custom".to_string()),
+            charset: Some("CP1252".to_string()),
+            ..Default::default()
+        };
+        assert!(style.validate().is_ok());
+        assert_eq!(style.fonts_dir(), Some(dir.path().to_path_buf()));
+
+        let invalid = [
+            SubtitleStyle {
+                font_name: Some("Arial,Bold".to_string()),
+                ..Default::default()
+            },
+            SubtitleStyle {
+                primary_color: Some("white".to_string()),
+                ..Default::default()
+            },
+            SubtitleStyle {
+                alignment: Some(10),
+                ..Default::default()
+            },
+            SubtitleStyle {
+                shadow: Some(-1.0),
+                ..Default::default()
+            },
+            SubtitleStyle {
+                fonts_dir: Some(dir.path().join("missing").to_string_lossy().to_string()),
+                ..Default::default()
+            },
+            SubtitleStyle {
+                charset: Some("utf 8'".to_string()),
+                ..Default::default()
+            },
+        ];
+        for style in invalid {
+            assert!(style.validate().is_err(), "{:?}", style);
+        }
+    }
+}
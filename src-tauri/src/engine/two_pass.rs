@@ -0,0 +1,271 @@
+use crate::engine::encoding::{AudioCodec, EncodingProfile, RateControl, VideoCodec};
+use crate::engine::job::{self, FfmpegStep, JobPlan, ProcessVideoParams, TrimMode};
+use crate::engine::probe::{self, MediaInfo};
+use std::path::Path;
+use uuid::Uuid;
+
+const BYTES_PER_MEGABYTE: f64 = 1_000_000.0;
+
+/// Share of the target kept free for container overhead and rate-control overshoot.
+const SIZE_MARGIN: f64 = 0.03;
+
+/// Below this the picture falls apart, so the target is rejected instead.
+const MIN_VIDEO_BITRATE_KBPS: u32 = 50;
+
+/// Audio bitrate pinned for lossy encoders when none is set, so the video
+/// budget can be computed exactly.
+const DEFAULT_AUDIO_BITRATE_KBPS: u32 = 128;
+
+/// Assumed bitrate of copied audio whose bitrate ffprobe does not report.
+const FALLBACK_COPY_AUDIO_KBPS: u32 = 192;
+
+/// Video bitrate that makes `duration` seconds of output with `audio_kbps`
+/// of audio fit in `target_mb` megabytes.
+pub fn target_video_bitrate(target_mb: f64, duration: f64, audio_kbps: u32) -> Result<u32, String> {
+    if duration <= 0.0 {
+        return Err("Cannot size an export without knowing its duration".to_string());
+    }
+
+    let total_kbps = target_mb * BYTES_PER_MEGABYTE * 8.0 / 1000.0 / duration * (1.0 - SIZE_MARGIN);
+    let video_kbps = total_kbps - audio_kbps as f64;
+
+    if video_kbps < MIN_VIDEO_BITRATE_KBPS as f64 {
+        let needed_kbps = (MIN_VIDEO_BITRATE_KBPS + audio_kbps) as f64 / (1.0 - SIZE_MARGIN);
+        let needed_mb = needed_kbps * 1000.0 / 8.0 * duration / BYTES_PER_MEGABYTE;
+        return Err(format!(
+            "{} MB is too small for {:.0} seconds of video; choose at least {:.1} MB",
+            target_mb, duration, needed_mb
+        ));
+    }
+
+    Ok(video_kbps.floor() as u32)
+}
+
+/// Audio bitrate the export will use. Lossy encoders without an explicit
+/// bitrate get a default written into `profile`.
+fn audio_budget(profile: &mut EncodingProfile, source: &MediaInfo) -> Result<u32, String> {
+    match profile.audio.codec {
+        AudioCodec::None => Ok(0),
+        AudioCodec::Flac | AudioCodec::PcmS16le => {
+            Err("Lossless audio cannot be used with a target file size".to_string())
+        }
+        AudioCodec::Copy => Ok(source
+            .first_stream("audio")
+            .map(|stream| {
+                stream
+                    .bit_rate
+                    .map(|bps| (bps / 1000) as u32)
+                    .unwrap_or(FALLBACK_COPY_AUDIO_KBPS)
+            })
+            .unwrap_or(0)),
+        _ => {
+            if source.first_stream("audio").is_none() {
+                return Ok(0);
+            }
+            Ok(*profile.audio.bitrate_kbps.get_or_insert(DEFAULT_AUDIO_BITRATE_KBPS))
+        }
+    }
+}
+
+/// Arguments selecting pass `pass` with stats stored under `passlog`.
+fn pass_args(codec: VideoCodec, pass: u8, passlog: &Path) -> Result<Vec<String>, String> {
+    let passlog = passlog.to_string_lossy().to_string();
+
+    match codec {
+        VideoCodec::Libx264 | VideoCodec::LibvpxVp9 | VideoCodec::LibaomAv1 | VideoCodec::Mpeg4 => Ok(vec![
+            "-pass".to_string(),
+            pass.to_string(),
+            "-passlogfile".to_string(),
+            passlog,
+        ]),
+        // The libx265 wrapper ignores -pass; the encoder takes it via its own params
+        VideoCodec::Libx265 => Ok(vec![
+            "-x265-params".to_string(),
+            format!("pass={}:stats='{}'", pass, passlog.replace('\'', "\\'")),
+        ]),
+        VideoCodec::Libsvtav1 | VideoCodec::Copy => Err(format!(
+            "{} does not support two-pass encoding; choose H.264, HEVC, VP9 or AV1 (libaom)",
+            codec.ffmpeg_name()
+        )),
+    }
+}
+
+/// Checks that a job with a target size can be run as a two-pass encode.
+pub fn validate_target_size(params: &ProcessVideoParams, encoding: &EncodingProfile) -> Result<(), String> {
+    let Some(target_mb) = params.target_size_mb else {
+        return Ok(());
+    };
+
+    if !target_mb.is_finite() || target_mb <= 0.0 {
+        return Err("Target size must be greater than zero".to_string());
+    }
+
+    if params.trim_mode != TrimMode::Accurate || !params.segments.is_empty() || !params.concat_inputs.is_empty() {
+        return Err("A target size can only be used with accurate trim on a single range".to_string());
+    }
+
+    if encoding.video.rate_control.is_some() {
+        return Err("A target size sets the video bitrate; remove the quality or bitrate setting".to_string());
+    }
+
+    if matches!(encoding.audio.codec, AudioCodec::Flac | AudioCodec::PcmS16le) {
+        return Err("Lossless audio cannot be used with a target file size".to_string());
+    }
+
+    pass_args(encoding.video.codec, 1, Path::new("")).map(|_| ())
+}
+
+/// Turns single-pass arguments ending in `-y <output>` into an analysis pass
+/// that discards its output and a second pass that writes the file.
+pub fn build_two_pass_steps(
+    args: &[String],
+    codec: VideoCodec,
+    passlog: &Path,
+    duration: f64,
+) -> Result<Vec<FfmpegStep>, String> {
+    if args.len() < 2 || args[args.len() - 2] != "-y" {
+        return Err("Unexpected ffmpeg arguments for a two-pass encode".to_string());
+    }
+    let (encode_args, output_args) = args.split_at(args.len() - 2);
+
+    let mut first = encode_args.to_vec();
+    first.extend(pass_args(codec, 1, passlog)?);
+    first.extend(["-an", "-f", "null", "-y", "-"].map(String::from));
+
+    let mut second = encode_args.to_vec();
+    second.extend(pass_args(codec, 2, passlog)?);
+    second.extend(output_args.iter().cloned());
+
+    Ok(vec![
+        FfmpegStep {
+            args: first,
+            duration,
+        },
+        FfmpegStep {
+            args: second,
+            duration,
+        },
+    ])
+}
+
+/// Probes the source, derives the video bitrate that hits the requested size
+/// and plans both passes with their stats files in a scratch directory.
+pub async fn plan_two_pass(job_id: Uuid, params: &ProcessVideoParams) -> Result<JobPlan, String> {
+    let target_mb = params
+        .target_size_mb
+        .ok_or_else(|| "Two-pass encoding requires a target size".to_string())?;
+
+    let info = probe::probe_media_info(&params.input_file).await?;
+    let source_duration = info
+        .duration
+        .ok_or_else(|| "Could not determine the video duration".to_string())?;
+    let duration = job::output_duration(params, source_duration);
+
+    let mut profile = params.effective_encoding();
+    let audio_kbps = audio_budget(&mut profile, &info)?;
+    let video_kbps = target_video_bitrate(target_mb, duration, audio_kbps)?;
+    profile.video.rate_control = Some(RateControl::Bitrate { kbps: video_kbps });
+
+    log::info!(
+        "Two-pass job {}: {} MB over {:.1}s gives {} kbps video + {} kbps audio",
+        job_id,
+        target_mb,
+        duration,
+        video_kbps,
+        audio_kbps
+    );
+
+    let sized = ProcessVideoParams {
+        encoding: Some(profile.clone()),
+        target_size_mb: None,
+        ..params.clone()
+    };
+    let args = job::build_ffmpeg_args(&sized)?;
+
+    let work_dir = job::create_work_dir(job_id)?;
+    let steps = build_two_pass_steps(&args, profile.video.codec, &work_dir.join("passlog"), duration)?;
+
+    Ok(JobPlan {
+        steps,
+        work_dir: Some(work_dir),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args() -> Vec<String> {
+        ["-i", "/in.mp4", "-c:v", "libx264", "-b:v", "1000k", "-c:a", "aac", "-y", "/out.mp4"]
+            .map(String::from)
+            .to_vec()
+    }
+
+    #[test]
+    fn test_target_video_bitrate() {
+        // 25 MB over 100 s is 2000 kbps in total before the margin
+        let kbps = target_video_bitrate(25.0, 100.0, 128).unwrap();
+        assert_eq!(kbps, 1812);
+    }
+
+    #[test]
+    fn test_target_video_bitrate_too_small() {
+        let err = target_video_bitrate(1.0, 600.0, 128).unwrap_err();
+        assert!(err.starts_with("1 MB is too small for 600 seconds of video"));
+    }
+
+    #[test]
+    fn test_two_pass_steps() {
+        let steps = build_two_pass_steps(&args(), VideoCodec::Libx264, Path::new("/tmp/job/passlog"), 60.0).unwrap();
+
+        assert_eq!(steps.len(), 2);
+        let first = &steps[0].args;
+        assert!(first.windows(2).any(|w| w == ["-pass", "1"]));
+        assert!(first.windows(2).any(|w| w == ["-passlogfile", "/tmp/job/passlog"]));
+        assert_eq!(first[first.len() - 5..], ["-an", "-f", "null", "-y", "-"]);
+
+        let second = &steps[1].args;
+        assert!(second.windows(2).any(|w| w == ["-pass", "2"]));
+        assert_eq!(second[second.len() - 2..], ["-y", "/out.mp4"]);
+        assert_eq!(steps[1].duration, 60.0);
+    }
+
+    #[test]
+    fn test_two_pass_steps_x265_uses_encoder_params() {
+        let steps = build_two_pass_steps(&args(), VideoCodec::Libx265, Path::new("/tmp/job/passlog"), 60.0).unwrap();
+        assert!(steps[1].args.contains(&"pass=2:stats='/tmp/job/passlog'".to_string()));
+    }
+
+    #[test]
+    fn test_two_pass_rejects_svtav1() {
+        let result = build_two_pass_steps(&args(), VideoCodec::Libsvtav1, Path::new("/tmp/passlog"), 60.0);
+        assert!(result.unwrap_err().contains("does not support two-pass"));
+    }
+
+    #[test]
+    fn test_validate_target_size() {
+        let params = ProcessVideoParams {
+            input_file: "/in.mp4".to_string(),
+            output_file: "/out.mp4".to_string(),
+            target_size_mb: Some(25.0),
+            ..Default::default()
+        };
+        let mut encoding = params.effective_encoding();
+        assert!(validate_target_size(&params, &encoding).is_ok());
+
+        encoding.video.rate_control = Some(RateControl::Crf { value: 23 });
+        assert!(validate_target_size(&params, &encoding).unwrap_err().contains("remove the quality"));
+
+        let fast = ProcessVideoParams {
+            trim_mode: TrimMode::Fast,
+            ..params.clone()
+        };
+        assert!(validate_target_size(&fast, &fast.effective_encoding()).is_err());
+
+        let zero = ProcessVideoParams {
+            target_size_mb: Some(0.0),
+            ..params
+        };
+        assert!(validate_target_size(&zero, &zero.effective_encoding()).is_err());
+    }
+}
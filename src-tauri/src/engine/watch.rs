@@ -0,0 +1,290 @@
+use crate::engine::naming::{self, CollisionPolicy};
+use crate::engine::storage;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+use uuid::Uuid;
+
+/// Watch rules are stored under this name in the app config directory.
+pub const WATCH_RULES_FILE_NAME: &str = "watch_rules.json";
+
+/// Files picked up from watched folders, the same set the open dialog offers.
+pub const WATCH_EXTENSIONS: &[&str] = &["mp4", "avi", "mov", "mkv", "webm", "flv"];
+
+/// How long a file's size and modification time must stay unchanged before
+/// it is treated as fully written.
+pub const STABLE_FOR: Duration = Duration::from_secs(5);
+
+pub const DEFAULT_WATCH_TEMPLATE: &str = "{name}";
+
+fn default_template() -> String {
+    DEFAULT_WATCH_TEMPLATE.to_string()
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// Processes new media files dropped into `input_dir` with a preset.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WatchRule {
+    #[serde(default = "Uuid::new_v4")]
+    pub id: Uuid,
+    pub input_dir: String,
+    pub output_dir: String,
+    /// Preset applied to every file. Its extension names the output.
+    pub preset: String,
+    /// Sources are moved here once their export succeeds. `None` leaves them
+    /// in place.
+    #[serde(default)]
+    pub archive_dir: Option<String>,
+    /// Output file name without the extension. May use the name tokens.
+    #[serde(default = "default_template")]
+    pub filename_template: String,
+    #[serde(default)]
+    pub collision_policy: CollisionPolicy,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// Files in `input_dir` that were already queued, or were there before
+    /// the rule was created.
+    #[serde(default)]
+    pub seen: BTreeSet<String>,
+}
+
+impl WatchRule {
+    /// Checks the folders and name template. The preset is checked by the
+    /// caller, which knows the user's presets.
+    pub fn validate(&self) -> Result<(), String> {
+        let input_dir = Path::new(&self.input_dir);
+        if !input_dir.is_absolute() || !input_dir.is_dir() {
+            return Err(format!("Watch folder does not exist: {}", self.input_dir));
+        }
+
+        if !Path::new(&self.output_dir).is_absolute() {
+            return Err("Output folder must be an absolute path".to_string());
+        }
+        // Outputs written next to their sources would be picked up again
+        if Path::new(&self.output_dir) == input_dir {
+            return Err("Output folder must differ from the watch folder".to_string());
+        }
+
+        if let Some(ref archive_dir) = self.archive_dir {
+            if !Path::new(archive_dir).is_absolute() {
+                return Err("Archive folder must be an absolute path".to_string());
+            }
+            if Path::new(archive_dir) == input_dir {
+                return Err("Archive folder must differ from the watch folder".to_string());
+            }
+        }
+
+        if self.filename_template.trim().is_empty() {
+            return Err("Output name template cannot be empty".to_string());
+        }
+        naming::template_tokens(&self.filename_template).map(|_| ())
+    }
+
+    /// Whether a file in the watch folder should be processed, once stable.
+    pub fn wants(&self, file_name: &str) -> bool {
+        let extension = Path::new(file_name)
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+
+        !file_name.starts_with('.')
+            && WATCH_EXTENSIONS.contains(&extension.as_str())
+            && !self.seen.contains(file_name)
+    }
+
+    /// The output path for the rule's preset extension, with the name tokens
+    /// still to be rendered.
+    pub fn output_file(&self, extension: &str) -> String {
+        Path::new(&self.output_dir)
+            .join(format!("{}.{}", self.filename_template.trim(), extension))
+            .to_string_lossy()
+            .to_string()
+    }
+}
+
+/// Names of the files directly inside `dir`.
+pub fn list_files(dir: &Path) -> Result<Vec<String>, String> {
+    let entries = std::fs::read_dir(dir).map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?;
+
+    let mut names: Vec<String> = entries
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_ok_and(|kind| kind.is_file()))
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .collect();
+    names.sort();
+    Ok(names)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct FileSnapshot {
+    size: u64,
+    modified: Option<SystemTime>,
+}
+
+/// Waits for files to stop growing before they are processed.
+#[derive(Debug, Default)]
+pub struct StabilityTracker {
+    files: HashMap<PathBuf, (FileSnapshot, Instant)>,
+}
+
+impl StabilityTracker {
+    /// Records what `path` looks like at `now`. Returns true once a non-empty
+    /// file has stayed unchanged for `STABLE_FOR`.
+    pub fn observe(&mut self, path: &Path, size: u64, modified: Option<SystemTime>, now: Instant) -> bool {
+        let snapshot = FileSnapshot { size, modified };
+
+        match self.files.get(path) {
+            Some((previous, since)) if *previous == snapshot => {
+                size > 0 && now.saturating_duration_since(*since) >= STABLE_FOR
+            }
+            _ => {
+                self.files.insert(path.to_path_buf(), (snapshot, now));
+                false
+            }
+        }
+    }
+
+    pub fn forget(&mut self, path: &Path) {
+        self.files.remove(path);
+    }
+
+    /// Drops files that are no longer being waited on.
+    pub fn retain(&mut self, mut keep: impl FnMut(&Path) -> bool) {
+        self.files.retain(|path, _| keep(path));
+    }
+}
+
+/// Moves a processed source into `archive_dir`, numbering the name if the
+/// archive already has a file by that name. Returns the new path.
+pub fn archive_source(source: &Path, archive_dir: &Path) -> Result<PathBuf, String> {
+    std::fs::create_dir_all(archive_dir).map_err(|e| format!("Failed to create archive folder: {}", e))?;
+
+    let file_name = source
+        .file_name()
+        .ok_or_else(|| format!("Not a file: {}", source.display()))?;
+    let target = naming::resolve_collision(&archive_dir.join(file_name), CollisionPolicy::Increment, Path::exists)?
+        .ok_or_else(|| "No free name in the archive folder".to_string())?;
+
+    // rename fails across file systems; copy and remove instead
+    if std::fs::rename(source, &target).is_err() {
+        std::fs::copy(source, &target).map_err(|e| format!("Failed to archive {}: {}", source.display(), e))?;
+        std::fs::remove_file(source).map_err(|e| format!("Failed to remove {}: {}", source.display(), e))?;
+    }
+    Ok(target)
+}
+
+pub fn read_watch_rules(path: &Path) -> Result<Vec<WatchRule>, String> {
+    let contents = match std::fs::read(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("Failed to read watch rules: {}", e)),
+    };
+
+    serde_json::from_slice(&contents).map_err(|e| format!("Failed to parse watch rules: {}", e))
+}
+
+pub fn write_watch_rules(path: &Path, rules: &[WatchRule]) -> Result<(), String> {
+    storage::write_json_atomic(path, rules).map_err(|e| format!("Failed to save watch rules: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn rule(input_dir: &Path, output_dir: &Path) -> WatchRule {
+        serde_json::from_value(serde_json::json!({
+            "input_dir": input_dir,
+            "output_dir": output_dir,
+            "preset": "YouTube",
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_rule_defaults_and_validation() {
+        let dir = tempdir().unwrap();
+        let rule = rule(dir.path(), &dir.path().join("out"));
+        assert!(rule.enabled);
+        assert_eq!(rule.filename_template, DEFAULT_WATCH_TEMPLATE);
+        assert!(rule.validate().is_ok());
+
+        let same_output = WatchRule {
+            output_dir: rule.input_dir.clone(),
+            ..rule.clone()
+        };
+        assert!(same_output.validate().is_err());
+
+        let missing_input = WatchRule {
+            input_dir: dir.path().join("missing").to_string_lossy().to_string(),
+            ..rule.clone()
+        };
+        assert!(missing_input.validate().is_err());
+
+        let bad_template = WatchRule {
+            filename_template: "{nope}".to_string(),
+            ..rule
+        };
+        assert!(bad_template.validate().is_err());
+    }
+
+    #[test]
+    fn test_wants_media_files_not_yet_seen() {
+        let mut rule = rule(Path::new("/in"), Path::new("/out"));
+        rule.seen.insert("old.mp4".to_string());
+
+        assert!(rule.wants("new.MOV"));
+        assert!(!rule.wants("old.mp4"));
+        assert!(!rule.wants(".new.mp4"));
+        assert!(!rule.wants("notes.txt"));
+        assert_eq!(rule.output_file("mp4"), Path::new("/out").join("{name}.mp4").to_string_lossy());
+    }
+
+    #[test]
+    fn test_stability_tracker_waits_for_unchanged_size() {
+        let mut tracker = StabilityTracker::default();
+        let path = Path::new("/in/clip.mp4");
+        let start = Instant::now();
+
+        assert!(!tracker.observe(path, 100, None, start));
+        assert!(!tracker.observe(path, 200, None, start + STABLE_FOR));
+        assert!(!tracker.observe(path, 200, None, start + STABLE_FOR + Duration::from_secs(1)));
+        assert!(tracker.observe(path, 200, None, start + STABLE_FOR * 2));
+
+        // Empty files are never considered finished
+        let empty = Path::new("/in/empty.mp4");
+        assert!(!tracker.observe(empty, 0, None, start));
+        assert!(!tracker.observe(empty, 0, None, start + STABLE_FOR * 2));
+    }
+
+    #[test]
+    fn test_archive_source_numbers_clashing_names() {
+        let dir = tempdir().unwrap();
+        let archive = dir.path().join("archive");
+        std::fs::create_dir(&archive).unwrap();
+        std::fs::write(archive.join("clip.mp4"), b"old").unwrap();
+
+        let source = dir.path().join("clip.mp4");
+        std::fs::write(&source, b"new").unwrap();
+
+        let archived = archive_source(&source, &archive).unwrap();
+        assert_eq!(archived, archive.join("clip_1.mp4"));
+        assert!(!source.exists());
+        assert_eq!(std::fs::read(archived).unwrap(), b"new");
+    }
+
+    #[test]
+    fn test_watch_rules_round_trip() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(WATCH_RULES_FILE_NAME);
+        assert!(read_watch_rules(&path).unwrap().is_empty());
+
+        let rules = vec![rule(dir.path(), &dir.path().join("out"))];
+        write_watch_rules(&path, &rules).unwrap();
+        assert_eq!(read_watch_rules(&path).unwrap(), rules);
+    }
+}
@@ -1,8 +1,13 @@
+#[cfg(feature = "gui")]
 mod commands;
+pub mod engine;
+#[cfg(feature = "gui")]
 mod state;
 
+#[cfg(feature = "gui")]
 use state::AppState;
 
+#[cfg(feature = "gui")]
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
@@ -13,19 +18,59 @@ pub fn run() {
             commands::dialog::select_subtitle_file,
             commands::dialog::select_output_file,
             commands::video::get_duration,
+            commands::video::probe_media,
+            commands::video::get_trim_keyframes,
             commands::video::check_ffmpeg_availability,
+            commands::capabilities::get_ffmpeg_capabilities,
+            commands::binaries::get_ffmpeg_binaries,
+            commands::binaries::set_ffmpeg_path,
+            commands::settings::get_settings,
+            commands::settings::update_settings,
+            commands::presets::list_presets,
+            commands::presets::save_preset,
+            commands::presets::delete_preset,
+            commands::presets::import_presets,
+            commands::presets::export_presets,
+            commands::naming::preview_output_name,
             commands::process::process_video,
             commands::process::cancel_process,
+            commands::process::pause_process,
+            commands::process::resume_process,
+            commands::process::get_default_encoding,
+            commands::concat::concat_videos,
+            commands::queue::enqueue_job,
+            commands::queue::get_queue,
+            commands::queue::set_queue_concurrency,
+            commands::queue::move_queued_job,
+            commands::queue::pause_queued_job,
+            commands::queue::resume_queued_job,
+            commands::queue::remove_queued_job,
+            commands::queue::pause_queue,
+            commands::queue::resume_queue,
+            commands::manifest::run_batch_manifest,
+            commands::watch::list_watch_rules,
+            commands::watch::save_watch_rule,
+            commands::watch::delete_watch_rule,
+            commands::history::list_job_history,
+            commands::history::get_job_details,
+            commands::history::clear_job_history,
+            commands::history::rerun_job,
             commands::logging::write_frontend_log,
             commands::logging::get_log_file_path,
         ])
         .setup(|app| {
             app.handle().plugin(
                 tauri_plugin_log::Builder::default()
-                    .level(log::LevelFilter::Debug)
+                    // The effective level comes from the settings
+                    .level(log::LevelFilter::Trace)
                     .build(),
             )?;
             log::info!("FFmpeg GUI starting up...");
+            tauri::async_runtime::block_on(commands::settings::load_settings(app.handle()));
+            commands::history::recover_history(app.handle());
+            tauri::async_runtime::spawn(commands::queue::run_dispatcher(app.handle().clone()));
+            tauri::async_runtime::block_on(commands::watch::load_watch_rules(app.handle()));
+            tauri::async_runtime::spawn(commands::watch::run_watcher(app.handle().clone()));
             Ok(())
         })
         .run(tauri::generate_context!())
@@ -1,22 +1,194 @@
-use std::collections::HashMap;
+use crate::commands::capabilities::FfmpegCapabilities;
+use crate::engine::job::{CancelMode, ProcessVideoParams};
+use crate::engine::progress::ActiveClock;
+use crate::engine::settings::{Settings, DEFAULT_MAX_CONCURRENT_JOBS, MAX_CONCURRENT_JOBS_LIMIT};
+use crate::engine::watch::WatchRule;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::Path;
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use tokio::sync::{Mutex, Notify};
 use uuid::Uuid;
 
 pub struct ProcessJob {
     pub child: tokio::process::Child,
     pub job_id: Uuid,
+    pub clock: ActiveClock,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueuedJob {
+    pub job_id: Uuid,
+    pub params: ProcessVideoParams,
+    /// Held jobs stay in the queue but are skipped by the dispatcher.
+    #[serde(default)]
+    pub held: bool,
+    /// Where a watch-folder job moves its input once it has completed.
+    #[serde(default)]
+    pub archive_dir: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct QueueSnapshot {
+    pub pending: Vec<QueuedJob>,
+    pub running: Vec<QueuedJob>,
+    pub max_concurrent: usize,
+    pub paused: bool,
+}
+
+/// Pending and running export jobs. The dispatcher in `commands::queue` pulls
+/// jobs from the front of `pending` until `max_concurrent` jobs are running.
+#[derive(Debug)]
+pub struct JobQueue {
+    pending: VecDeque<QueuedJob>,
+    running: Vec<QueuedJob>,
+    max_concurrent: usize,
+    paused: bool,
+}
+
+impl JobQueue {
+    pub fn new(max_concurrent: usize) -> Self {
+        Self {
+            pending: VecDeque::new(),
+            running: Vec::new(),
+            max_concurrent: max_concurrent.clamp(1, MAX_CONCURRENT_JOBS_LIMIT),
+            paused: false,
+        }
+    }
+
+    pub fn enqueue(&mut self, job: QueuedJob) {
+        self.pending.push_back(job);
+    }
+
+    /// Removes a pending job. Running jobs must be cancelled instead.
+    pub fn remove(&mut self, job_id: Uuid) -> Option<QueuedJob> {
+        let index = self.pending.iter().position(|job| job.job_id == job_id)?;
+        self.pending.remove(index)
+    }
+
+    /// Moves a pending job to `position`, clamped to the end of the queue.
+    pub fn move_to(&mut self, job_id: Uuid, position: usize) -> Result<(), String> {
+        let index = self
+            .pending
+            .iter()
+            .position(|job| job.job_id == job_id)
+            .ok_or_else(|| "Job is not pending".to_string())?;
+
+        let job = self.pending.remove(index).expect("index is in bounds");
+        let position = position.min(self.pending.len());
+        self.pending.insert(position, job);
+        Ok(())
+    }
+
+    pub fn set_held(&mut self, job_id: Uuid, held: bool) -> Result<(), String> {
+        let job = self
+            .pending
+            .iter_mut()
+            .find(|job| job.job_id == job_id)
+            .ok_or_else(|| "Job is not pending".to_string())?;
+        job.held = held;
+        Ok(())
+    }
+
+    pub fn set_max_concurrent(&mut self, max_concurrent: usize) -> Result<(), String> {
+        if max_concurrent == 0 || max_concurrent > MAX_CONCURRENT_JOBS_LIMIT {
+            return Err(format!(
+                "Concurrency must be between 1 and {}",
+                MAX_CONCURRENT_JOBS_LIMIT
+            ));
+        }
+        self.max_concurrent = max_concurrent;
+        Ok(())
+    }
+
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+
+    /// Takes the first runnable job and marks it as running, if a slot is free.
+    pub fn next_ready(&mut self) -> Option<QueuedJob> {
+        if self.paused || self.running.len() >= self.max_concurrent {
+            return None;
+        }
+
+        let index = self.pending.iter().position(|job| !job.held)?;
+        let job = self.pending.remove(index)?;
+        self.running.push(job.clone());
+        Some(job)
+    }
+
+    pub fn finish(&mut self, job_id: Uuid) {
+        self.running.retain(|job| job.job_id != job_id);
+    }
+
+    pub fn is_running(&self, job_id: Uuid) -> bool {
+        self.running.iter().any(|job| job.job_id == job_id)
+    }
+
+    /// Whether a pending or running job writes to `path`.
+    pub fn writes_to(&self, path: &Path) -> bool {
+        self.running
+            .iter()
+            .chain(self.pending.iter())
+            .any(|job| Path::new(&job.params.output_file) == path)
+    }
+
+    pub fn is_idle(&self) -> bool {
+        self.running.is_empty() && self.pending.iter().all(|job| job.held)
+    }
+
+    /// Jobs that have not completed yet, running first, in the order they should
+    /// be restarted after an unexpected shutdown.
+    pub fn unfinished(&self) -> Vec<QueuedJob> {
+        self.running.iter().chain(self.pending.iter()).cloned().collect()
+    }
+
+    pub fn snapshot(&self) -> QueueSnapshot {
+        QueueSnapshot {
+            pending: self.pending.iter().cloned().collect(),
+            running: self.running.clone(),
+            max_concurrent: self.max_concurrent,
+            paused: self.paused,
+        }
+    }
+}
+
+impl Default for JobQueue {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_CONCURRENT_JOBS)
+    }
 }
 
 #[derive(Clone)]
 pub struct AppState {
     pub active_jobs: Arc<Mutex<HashMap<Uuid, ProcessJob>>>,
+    pub queue: Arc<Mutex<JobQueue>>,
+    pub queue_notify: Arc<Notify>,
+    /// Running jobs the user has cancelled, and how. Multi-step jobs check
+    /// this before starting each ffmpeg step.
+    pub cancelled_jobs: Arc<Mutex<HashMap<Uuid, CancelMode>>>,
+    /// Running jobs the user has paused. Steps that start while a job is
+    /// paused are stopped straight away.
+    pub paused_jobs: Arc<Mutex<HashSet<Uuid>>>,
+    /// Discovered on first use by `commands::capabilities`.
+    pub capabilities: Arc<Mutex<Option<FfmpegCapabilities>>>,
+    /// Loaded from disk at startup by `commands::settings`.
+    pub settings: Arc<Mutex<Settings>>,
+    /// Watch-folder rules, loaded from disk at startup by `commands::watch`.
+    pub watch_rules: Arc<Mutex<Vec<WatchRule>>>,
 }
 
 impl AppState {
     pub fn new() -> Self {
         Self {
             active_jobs: Arc::new(Mutex::new(HashMap::new())),
+            queue: Arc::new(Mutex::new(JobQueue::default())),
+            queue_notify: Arc::new(Notify::new()),
+            cancelled_jobs: Arc::new(Mutex::new(HashMap::new())),
+            paused_jobs: Arc::new(Mutex::new(HashSet::new())),
+            capabilities: Arc::new(Mutex::new(None)),
+            settings: Arc::new(Mutex::new(Settings::default())),
+            watch_rules: Arc::new(Mutex::new(Vec::new())),
         }
     }
 }
@@ -26,3 +198,139 @@ impl Default for AppState {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn job(name: &str) -> QueuedJob {
+        QueuedJob {
+            job_id: Uuid::new_v4(),
+            params: ProcessVideoParams {
+                input_file: format!("/input/{}.mp4", name),
+                output_file: format!("/output/{}.mp4", name),
+                start_time: None,
+                end_time: None,
+                subtitle_file: None,
+                ..Default::default()
+            },
+            held: false,
+            archive_dir: None,
+        }
+    }
+
+    fn pending_ids(queue: &JobQueue) -> Vec<Uuid> {
+        queue.snapshot().pending.iter().map(|job| job.job_id).collect()
+    }
+
+    #[test]
+    fn test_next_ready_respects_concurrency_limit() {
+        let mut queue = JobQueue::new(2);
+        let (a, b, c) = (job("a"), job("b"), job("c"));
+        queue.enqueue(a.clone());
+        queue.enqueue(b.clone());
+        queue.enqueue(c.clone());
+
+        assert_eq!(queue.next_ready().unwrap().job_id, a.job_id);
+        assert_eq!(queue.next_ready().unwrap().job_id, b.job_id);
+        assert!(queue.next_ready().is_none());
+
+        queue.finish(a.job_id);
+        assert_eq!(queue.next_ready().unwrap().job_id, c.job_id);
+    }
+
+    #[test]
+    fn test_next_ready_skips_held_jobs() {
+        let mut queue = JobQueue::new(1);
+        let (a, b) = (job("a"), job("b"));
+        queue.enqueue(a.clone());
+        queue.enqueue(b.clone());
+        queue.set_held(a.job_id, true).unwrap();
+
+        assert_eq!(queue.next_ready().unwrap().job_id, b.job_id);
+        queue.finish(b.job_id);
+        assert!(queue.next_ready().is_none());
+        assert!(queue.is_idle());
+    }
+
+    #[test]
+    fn test_paused_queue_does_not_dispatch() {
+        let mut queue = JobQueue::new(1);
+        queue.enqueue(job("a"));
+        queue.set_paused(true);
+        assert!(queue.next_ready().is_none());
+
+        queue.set_paused(false);
+        assert!(queue.next_ready().is_some());
+    }
+
+    #[test]
+    fn test_move_to_reorders_pending_jobs() {
+        let mut queue = JobQueue::new(1);
+        let (a, b, c) = (job("a"), job("b"), job("c"));
+        queue.enqueue(a.clone());
+        queue.enqueue(b.clone());
+        queue.enqueue(c.clone());
+
+        queue.move_to(c.job_id, 0).unwrap();
+        assert_eq!(pending_ids(&queue), vec![c.job_id, a.job_id, b.job_id]);
+
+        queue.move_to(c.job_id, 99).unwrap();
+        assert_eq!(pending_ids(&queue), vec![a.job_id, b.job_id, c.job_id]);
+    }
+
+    #[test]
+    fn test_move_to_unknown_job_fails() {
+        let mut queue = JobQueue::new(1);
+        assert!(queue.move_to(Uuid::new_v4(), 0).is_err());
+    }
+
+    #[test]
+    fn test_remove_only_affects_pending_jobs() {
+        let mut queue = JobQueue::new(1);
+        let (a, b) = (job("a"), job("b"));
+        queue.enqueue(a.clone());
+        queue.enqueue(b.clone());
+        queue.next_ready();
+
+        assert!(queue.remove(a.job_id).is_none());
+        assert_eq!(queue.remove(b.job_id).unwrap().job_id, b.job_id);
+        assert!(pending_ids(&queue).is_empty());
+    }
+
+    #[test]
+    fn test_set_max_concurrent_rejects_out_of_range() {
+        let mut queue = JobQueue::new(1);
+        assert!(queue.set_max_concurrent(0).is_err());
+        assert!(queue.set_max_concurrent(MAX_CONCURRENT_JOBS_LIMIT + 1).is_err());
+        assert!(queue.set_max_concurrent(4).is_ok());
+        assert_eq!(queue.snapshot().max_concurrent, 4);
+    }
+
+    #[test]
+    fn test_unfinished_lists_running_before_pending() {
+        let mut queue = JobQueue::new(1);
+        let (a, b) = (job("a"), job("b"));
+        queue.enqueue(a.clone());
+        queue.enqueue(b.clone());
+        queue.next_ready();
+
+        let ids: Vec<Uuid> = queue.unfinished().iter().map(|job| job.job_id).collect();
+        assert_eq!(ids, vec![a.job_id, b.job_id]);
+    }
+
+    #[test]
+    fn test_is_running() {
+        let mut queue = JobQueue::new(1);
+        let (a, b) = (job("a"), job("b"));
+        queue.enqueue(a.clone());
+        queue.enqueue(b.clone());
+        queue.next_ready();
+
+        assert!(queue.is_running(a.job_id));
+        assert!(!queue.is_running(b.job_id));
+
+        queue.finish(a.job_id);
+        assert!(!queue.is_running(a.job_id));
+    }
+}